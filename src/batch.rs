@@ -0,0 +1,383 @@
+use crate::chart::ChartSpec;
+use crate::cleanup::DuplicateKeep;
+use crate::error::Error;
+use crate::event::{emit_changed, ChangeKind};
+use crate::filter::FilterCriteria;
+use crate::find::FindOptions;
+use crate::history::EditScope;
+use crate::sort::SortKey;
+use crate::workbook::{Workbook, WorkbookRegistry};
+use crate::worksheet::remove_columns;
+use serde::Deserialize;
+use serde_json::Value;
+use tauri::{command, AppHandle, Runtime, State, Window};
+use tracing::{debug, instrument};
+
+/// 批量执行的单个操作，`op` 为操作名称，其余字段与对应命令的参数相同。
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum Operation {
+    #[serde(rename_all = "camelCase")]
+    SetValue {
+        sheet_name: String,
+        local: (u32, u32),
+        value: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    AppendRow {
+        sheet_name: String,
+        data: Vec<Vec<String>>,
+    },
+    #[serde(rename_all = "camelCase")]
+    AppendColumn {
+        sheet_name: String,
+        data: Vec<Vec<String>>,
+    },
+    #[serde(rename_all = "camelCase")]
+    InsertRow {
+        sheet_name: String,
+        row_index: u32,
+        data: Vec<Vec<String>>,
+        #[serde(default)]
+        is_add: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    InsertColumn {
+        sheet_name: String,
+        column_index: u32,
+        data: Vec<Vec<String>>,
+        #[serde(default)]
+        is_add: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    InsertNewRow {
+        sheet_name: String,
+        row_index: u32,
+        num_rows: u32,
+    },
+    #[serde(rename_all = "camelCase")]
+    InsertNewColumn {
+        sheet_name: String,
+        column_index: u32,
+        num_columns: u32,
+    },
+    #[serde(rename_all = "camelCase")]
+    RemoveRow {
+        sheet_name: String,
+        row_index: u32,
+        num_rows: u32,
+    },
+    #[serde(rename_all = "camelCase")]
+    RemoveColumn {
+        sheet_name: String,
+        column_index: u32,
+        num_columns: u32,
+    },
+    #[serde(rename_all = "camelCase")]
+    NewSheet { sheet_name: String },
+    #[serde(rename_all = "camelCase")]
+    CopySheet {
+        source_sheet_name: String,
+        target_sheet_name: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    SortRange {
+        sheet_name: String,
+        range: String,
+        keys: Vec<SortKey>,
+        #[serde(default)]
+        has_header: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    RemoveDuplicates {
+        sheet_name: String,
+        range: String,
+        #[serde(default)]
+        key_columns: Vec<u32>,
+        #[serde(default)]
+        keep: DuplicateKeep,
+    },
+    #[serde(rename_all = "camelCase")]
+    RemoveBlankRows { sheet_name: String, range: String },
+    #[serde(rename_all = "camelCase")]
+    ReplaceAll {
+        query: String,
+        replacement: String,
+        #[serde(default)]
+        options: FindOptions,
+    },
+    #[serde(rename_all = "camelCase")]
+    SetAutoFilter { sheet_name: String, range: String },
+    #[serde(rename_all = "camelCase")]
+    ClearAutoFilter { sheet_name: String },
+    #[serde(rename_all = "camelCase")]
+    SetFilterColumn {
+        sheet_name: String,
+        column: u32,
+        criteria: Option<FilterCriteria>,
+    },
+    #[serde(rename_all = "camelCase")]
+    AddChart {
+        sheet_name: String,
+        anchor_range: String,
+        spec: ChartSpec,
+    },
+    #[serde(rename_all = "camelCase")]
+    RemoveChart { sheet_name: String, index: usize },
+    #[serde(rename_all = "camelCase")]
+    AddDefinedName {
+        name: String,
+        refers_to: String,
+        scope: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    UpdateDefinedName {
+        name: String,
+        scope: Option<String>,
+        refers_to: String,
+        new_name: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    RemoveDefinedName { name: String, scope: Option<String> },
+}
+
+impl Operation {
+    /// `sheet_name` 操作修改的 sheet，涉及整个文件时为空。
+    fn sheet_name(&self) -> Option<&str> {
+        match self {
+            Operation::SetValue { sheet_name, .. }
+            | Operation::AppendRow { sheet_name, .. }
+            | Operation::AppendColumn { sheet_name, .. }
+            | Operation::InsertRow { sheet_name, .. }
+            | Operation::InsertColumn { sheet_name, .. }
+            | Operation::InsertNewRow { sheet_name, .. }
+            | Operation::InsertNewColumn { sheet_name, .. }
+            | Operation::RemoveRow { sheet_name, .. }
+            | Operation::RemoveColumn { sheet_name, .. }
+            | Operation::SortRange { sheet_name, .. }
+            | Operation::RemoveDuplicates { sheet_name, .. }
+            | Operation::RemoveBlankRows { sheet_name, .. }
+            | Operation::SetAutoFilter { sheet_name, .. }
+            | Operation::ClearAutoFilter { sheet_name }
+            | Operation::SetFilterColumn { sheet_name, .. }
+            | Operation::AddChart { sheet_name, .. }
+            | Operation::RemoveChart { sheet_name, .. } => Some(sheet_name),
+            _ => None,
+        }
+    }
+
+    /// `apply` 执行操作，返回值与对应命令的返回值相同，没有返回值时为 `null`。
+    fn apply(&self, workbook: &mut Workbook) -> Result<Value, Error> {
+        let value = match self {
+            Operation::SetValue {
+                sheet_name,
+                local,
+                value,
+            } => to_value(workbook.set_value_by_column_and_row(sheet_name, *local, value)?)?,
+            Operation::AppendRow { sheet_name, data } => {
+                to_value(workbook.append_row(sheet_name, data)?)?
+            }
+            Operation::AppendColumn { sheet_name, data } => {
+                to_value(workbook.append_column(sheet_name, data)?)?
+            }
+            Operation::InsertRow {
+                sheet_name,
+                row_index,
+                data,
+                is_add,
+            } => to_value(workbook.insert_row(sheet_name, *row_index, data, *is_add)?)?,
+            Operation::InsertColumn {
+                sheet_name,
+                column_index,
+                data,
+                is_add,
+            } => to_value(workbook.insert_column(sheet_name, *column_index, data, *is_add)?)?,
+            Operation::InsertNewRow {
+                sheet_name,
+                row_index,
+                num_rows,
+            } => {
+                workbook
+                    .worksheet_mut(sheet_name)?
+                    .insert_new_row(row_index, num_rows);
+                Value::Null
+            }
+            Operation::InsertNewColumn {
+                sheet_name,
+                column_index,
+                num_columns,
+            } => {
+                workbook
+                    .worksheet_mut(sheet_name)?
+                    .insert_new_column_by_index(column_index, num_columns);
+                Value::Null
+            }
+            Operation::RemoveRow {
+                sheet_name,
+                row_index,
+                num_rows,
+            } => to_value(workbook.remove_row(sheet_name, *row_index, *num_rows)?)?,
+            Operation::RemoveColumn {
+                sheet_name,
+                column_index,
+                num_columns,
+            } => {
+                remove_columns(
+                    workbook.worksheet_mut(sheet_name)?,
+                    *column_index,
+                    *num_columns,
+                );
+                Value::Null
+            }
+            Operation::NewSheet { sheet_name } => to_value(workbook.new_sheet(sheet_name)?)?,
+            Operation::CopySheet {
+                source_sheet_name,
+                target_sheet_name,
+            } => to_value(workbook.copy_sheet(source_sheet_name, target_sheet_name)?)?,
+            Operation::SortRange {
+                sheet_name,
+                range,
+                keys,
+                has_header,
+            } => to_value(workbook.sort_range(sheet_name, range, keys, *has_header)?)?,
+            Operation::RemoveDuplicates {
+                sheet_name,
+                range,
+                key_columns,
+                keep,
+            } => to_value(workbook.remove_duplicates(sheet_name, range, key_columns, *keep)?)?,
+            Operation::RemoveBlankRows { sheet_name, range } => {
+                to_value(workbook.remove_blank_rows(sheet_name, range)?)?
+            }
+            Operation::ReplaceAll {
+                query,
+                replacement,
+                options,
+            } => to_value(workbook.replace_all(query, replacement, options)?)?,
+            Operation::SetAutoFilter { sheet_name, range } => {
+                to_value(workbook.set_auto_filter(sheet_name, range)?)?
+            }
+            Operation::ClearAutoFilter { sheet_name } => {
+                to_value(workbook.clear_auto_filter(sheet_name)?)?
+            }
+            Operation::SetFilterColumn {
+                sheet_name,
+                column,
+                criteria,
+            } => to_value(workbook.set_filter_column(sheet_name, *column, criteria.clone())?)?,
+            Operation::AddChart {
+                sheet_name,
+                anchor_range,
+                spec,
+            } => to_value(workbook.add_chart(sheet_name, anchor_range, spec)?)?,
+            Operation::RemoveChart { sheet_name, index } => {
+                to_value(workbook.remove_chart(sheet_name, *index)?)?
+            }
+            Operation::AddDefinedName {
+                name,
+                refers_to,
+                scope,
+            } => to_value(workbook.add_defined_name(name, refers_to, scope.clone())?)?,
+            Operation::UpdateDefinedName {
+                name,
+                scope,
+                refers_to,
+                new_name,
+            } => to_value(workbook.update_defined_name(
+                name,
+                scope.clone(),
+                refers_to,
+                new_name.clone(),
+            )?)?,
+            Operation::RemoveDefinedName { name, scope } => {
+                to_value(workbook.remove_defined_name(name, scope)?)?
+            }
+        };
+        Ok(value)
+    }
+}
+
+fn to_value<T: serde::Serialize>(value: T) -> Result<Value, Error> {
+    Ok(serde_json::to_value(value)?)
+}
+
+impl Workbook {
+    /// `batch` 依次执行 `operations`，任一操作失败时撤销全部操作并返回 [`Error::Batch`]，
+    /// 其中 `index` 为失败操作的索引，从 0 开始。
+    ///
+    /// 整批操作在撤销记录中只占一步。
+    ///
+    /// # Return value
+    ///
+    /// `Vec<Value>` - 各操作的返回值
+    pub fn batch(&mut self, operations: &[Operation]) -> Result<Vec<Value>, Error> {
+        self.transaction(EditScope::Workbook, "batch", |workbook| {
+            let mut results = Vec::with_capacity(operations.len());
+            for (index, operation) in operations.iter().enumerate() {
+                match operation.apply(workbook) {
+                    Ok(value) => results.push(value),
+                    Err(error) => {
+                        return Err(Error::Batch {
+                            index,
+                            source: Box::new(error),
+                        })
+                    }
+                }
+            }
+            debug!("批量执行 {} 个操作", results.len());
+            Ok(results)
+        })
+    }
+}
+
+/// `batch` 在同一个文件锁内依次执行 `operations`，任一操作失败时撤销全部操作。
+#[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path))]
+pub fn batch<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+    operations: Vec<Operation>,
+) -> Result<Vec<Value>, Error> {
+    let results = state.with_workbook(&path, |workbook| workbook.batch(&operations))?;
+    if let Some(first) = operations.first() {
+        // 所有操作都修改同一个 sheet 时才在事件中给出 sheet 名称
+        let sheet_name = first.sheet_name().filter(|sheet_name| {
+            operations
+                .iter()
+                .all(|operation| operation.sheet_name() == Some(*sheet_name))
+        });
+        emit_changed(&app, &path, sheet_name, None, ChangeKind::Batch);
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_operation_rolls_back_the_batch() {
+        let mut workbook = Workbook::new(umya_spreadsheet::new_file());
+        let operations: Vec<Operation> = serde_json::from_value(serde_json::json!([
+            { "op": "setValue", "sheetName": "Sheet1", "local": [1, 1], "value": "a" },
+            { "op": "newSheet", "sheetName": "Added" },
+            { "op": "setValue", "sheetName": "Missing", "local": [1, 1], "value": "b" },
+        ]))
+        .unwrap();
+        match workbook.batch(&operations) {
+            Err(Error::Batch { index, source }) => {
+                assert_eq!(index, 2);
+                assert!(source.to_string().contains("Missing"), "{}", source);
+            }
+            result => panic!("{:?}", result),
+        }
+        assert_eq!(workbook.worksheet("Sheet1").unwrap().get_value("A1"), "");
+        assert!(workbook.worksheet("Added").is_err());
+
+        let results = workbook.batch(&operations[..2]).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(workbook.worksheet("Sheet1").unwrap().get_value("A1"), "a");
+    }
+}
@@ -0,0 +1,421 @@
+use crate::defined_name::lookup_range;
+use crate::error::Error;
+use crate::event::{emit_changed, ChangeKind};
+use crate::history::EditScope;
+use crate::range::{self, CellRange};
+use crate::workbook::{Workbook, WorkbookRegistry};
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Runtime, State, Window};
+use tracing::{debug, instrument};
+use umya_spreadsheet::structs::drawing::charts::{
+    BarDirectionValues, CategoryAxisData, ChartSpace, Formula, LegendPositionValues,
+    StringReference,
+};
+use umya_spreadsheet::structs::drawing::spreadsheet::MarkerType;
+use umya_spreadsheet::structs::{Chart, ChartType};
+
+/// 图表类型。
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChartKind {
+    Bar,
+    Column,
+    Line,
+    Pie,
+    Scatter,
+    Area,
+}
+
+/// 图例位置。
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LegendPosition {
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopRight,
+}
+
+/// 图表数据系列。
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesSpec {
+    /// 数据区域，如 `"B2:B10"` 或 `"Sheet2!B2:B10"`。
+    pub values: String,
+    /// 系列名称。
+    pub title: Option<String>,
+}
+
+/// 图表描述。
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChartSpec {
+    pub kind: ChartKind,
+    pub series: Vec<SeriesSpec>,
+    /// 分类区域，如 `"A2:A10"` 或 `"Sheet2!A2:A10"`，散点图中作为 x 轴数据。
+    pub categories: Option<String>,
+    pub title: Option<String>,
+    pub x_axis_title: Option<String>,
+    pub y_axis_title: Option<String>,
+    pub legend: Option<LegendPosition>,
+}
+
+/// 图表信息。
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChartInfo {
+    pub index: usize,
+    pub anchor_range: String,
+}
+
+/// `sheet_reference` 将区域或名称转换为带 sheet 名称的绝对引用，区域没有 sheet 名称时使用 `sheet_name`。
+fn sheet_reference(
+    workbook: &Workbook,
+    sheet_name: &str,
+    reference: &str,
+) -> Result<String, Error> {
+    let (sheet, range) = lookup_range(&workbook.spreadsheet, sheet_name, reference)?;
+    Ok(range.to_absolute(&sheet))
+}
+
+/// `category_data` 生成引用分类区域的分类数据，单元格修改后图表随之更新。
+fn category_data(reference: &str) -> CategoryAxisData {
+    let mut formula = Formula::default();
+    formula.set_address_str(reference);
+    let mut string_reference = StringReference::default();
+    string_reference.set_formula(formula);
+    let mut data = CategoryAxisData::default();
+    data.set_string_reference(string_reference);
+    data
+}
+
+/// `chart_space_mut` 获取图表的 chart space。
+fn chart_space_mut(chart: &mut Chart) -> Option<&mut ChartSpace> {
+    chart
+        .get_two_cell_anchor_mut()
+        .get_graphic_frame_mut()
+        .as_mut()
+        .map(|frame| {
+            frame
+                .get_graphic_mut()
+                .get_graphic_data_mut()
+                .get_chart_space_mut()
+        })
+}
+
+/// `build_chart` 根据 `spec` 生成图表，`anchor` 为图表在 `sheet_name` 上覆盖的单元格区域。
+fn build_chart(
+    workbook: &Workbook,
+    sheet_name: &str,
+    anchor: &CellRange,
+    spec: &ChartSpec,
+) -> Result<Chart, Error> {
+    if spec.series.is_empty() {
+        return Err(Error::String(String::from("图表至少需要一个数据系列!")));
+    }
+    let chart_type = match spec.kind {
+        ChartKind::Bar | ChartKind::Column => ChartType::BarChart,
+        ChartKind::Line => ChartType::LineChart,
+        ChartKind::Pie => ChartType::PieChart,
+        ChartKind::Scatter => ChartType::ScatterChart,
+        ChartKind::Area => ChartType::AreaChart,
+    };
+    let categories = match &spec.categories {
+        Some(categories) => Some(sheet_reference(workbook, sheet_name, categories)?),
+        None => None,
+    };
+    // 散点图的数据按 (x, y) 成对传入，分类区域作为每个系列的 x 值。
+    let mut references = Vec::new();
+    for series in &spec.series {
+        if let ChartKind::Scatter = spec.kind {
+            match &categories {
+                Some(categories) => references.push(categories.clone()),
+                None => {
+                    return Err(Error::String(String::from(
+                        "散点图需要 categories 作为 x 轴数据!",
+                    )))
+                }
+            }
+        }
+        references.push(sheet_reference(workbook, sheet_name, &series.values)?);
+    }
+
+    // 结束标记是图表右下角所在的单元格左上角，因此向外扩展一格才能覆盖整个区域。
+    let mut from_marker = MarkerType::default();
+    from_marker.set_coordinate(range::coordinate(anchor.start.0, anchor.start.1));
+    let mut to_marker = MarkerType::default();
+    to_marker.set_coordinate(range::coordinate(anchor.end.0 + 1, anchor.end.1 + 1));
+
+    let mut chart = Chart::default();
+    chart.new_chart(
+        chart_type,
+        from_marker,
+        to_marker,
+        references.iter().map(|r| r.as_str()).collect(),
+    );
+
+    if spec.series.iter().any(|series| series.title.is_some()) {
+        let titles: Vec<String> = spec
+            .series
+            .iter()
+            .enumerate()
+            .map(|(i, series)| {
+                series
+                    .title
+                    .clone()
+                    .unwrap_or_else(|| format!("Series{}", i + 1))
+            })
+            .collect();
+        chart.set_series_title(titles.iter().map(|t| t.as_str()).collect());
+    }
+    match (&categories, spec.kind) {
+        (_, ChartKind::Scatter) | (None, _) => {}
+        (Some(categories), _) => {
+            let data = category_data(categories);
+            for series in chart
+                .get_area_chart_series_list_mut()
+                .get_area_chart_series_mut()
+            {
+                series.set_category_axis_data(data.clone());
+            }
+        }
+    }
+    if let Some(title) = &spec.title {
+        chart.set_title(title);
+    }
+    if let Some(title) = &spec.x_axis_title {
+        chart.set_horizontal_title(title);
+    }
+    if let Some(title) = &spec.y_axis_title {
+        chart.set_vertical_title(title);
+    }
+
+    if let Some(space) = chart_space_mut(&mut chart) {
+        let chart_element = space.get_chart_mut();
+        if let ChartKind::Bar = spec.kind {
+            if let Some(bar_chart) = chart_element.get_plot_area_mut().get_bar_chart_mut() {
+                bar_chart
+                    .get_bar_direction_mut()
+                    .set_val(BarDirectionValues::Bar);
+            }
+        }
+        if let Some(position) = spec.legend {
+            let value = match position {
+                LegendPosition::Top => LegendPositionValues::Top,
+                LegendPosition::Bottom => LegendPositionValues::Bottom,
+                LegendPosition::Left => LegendPositionValues::Left,
+                LegendPosition::Right => LegendPositionValues::Right,
+                LegendPosition::TopRight => LegendPositionValues::TopRight,
+            };
+            chart_element
+                .get_legend_mut()
+                .get_legend_position_mut()
+                .set_val(value);
+        }
+    }
+    Ok(chart)
+}
+
+impl Workbook {
+    /// `add_chart` 在 `anchor_range` 区域添加图表，返回图表索引。
+    ///
+    /// # Arguments
+    ///
+    /// - `anchor_range` 图表覆盖的单元格区域，如: "E2:L20"
+    /// - `spec` 图表描述，包括类型、数据系列、分类、标题和图例位置
+    pub fn add_chart(
+        &mut self,
+        sheet_name: &str,
+        anchor_range: &str,
+        spec: &ChartSpec,
+    ) -> Result<usize, Error> {
+        let anchor = self.resolve_range(sheet_name, anchor_range)?;
+        let chart = build_chart(self, sheet_name, &anchor, spec)?;
+        let worksheet = self.worksheet_mut(sheet_name)?;
+        worksheet.add_chart(chart);
+        let index = worksheet.get_chart_collection().len() - 1;
+        debug!("添加图表 {:?} 到区域 {}", spec.kind, anchor);
+        Ok(index)
+    }
+
+    /// `list_charts` 列出 sheet 中的图表。
+    pub fn list_charts(&self, sheet_name: &str) -> Result<Vec<ChartInfo>, Error> {
+        let worksheet = self.worksheet(sheet_name)?;
+        let mut charts = Vec::new();
+        for (index, chart) in worksheet.get_chart_collection().iter().enumerate() {
+            let anchor = chart.get_two_cell_anchor();
+            let from = range::parse_coordinate(&anchor.get_from_marker().get_coordinate());
+            let to = range::parse_coordinate(&anchor.get_to_marker().get_coordinate());
+            let anchor_range = match (from, to) {
+                (Some(from), Some(to)) => CellRange::new(
+                    from,
+                    (to.0.saturating_sub(1).max(1), to.1.saturating_sub(1).max(1)),
+                )
+                .to_string(),
+                _ => String::new(),
+            };
+            charts.push(ChartInfo {
+                index,
+                anchor_range,
+            });
+        }
+        debug!("获取图表列表: {:?}", &charts);
+        Ok(charts)
+    }
+
+    /// `remove_chart` 删除指定索引的图表。
+    pub fn remove_chart(&mut self, sheet_name: &str, index: usize) -> Result<(), Error> {
+        let charts = self.worksheet_mut(sheet_name)?.get_chart_collection_mut();
+        if index >= charts.len() {
+            return Err(Error::String(format!("图表索引 {} 不存在!", index)));
+        }
+        charts.remove(index);
+        debug!("删除图表 {}", index);
+        Ok(())
+    }
+}
+
+/// `add_chart` 在 `anchor_range` 区域添加图表，返回图表索引。
+#[command]
+#[instrument(
+    level = "debug",
+    skip_all,
+    fields(workbook = %path, sheet = %sheet_name, range = %anchor_range)
+)]
+pub fn add_chart<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+    sheet_name: String,
+    anchor_range: String,
+    spec: ChartSpec,
+) -> Result<usize, Error> {
+    let result = state.edit(
+        &path,
+        EditScope::Sheet(&sheet_name),
+        "add_chart",
+        |workbook| workbook.add_chart(&sheet_name, &anchor_range, &spec),
+    )?;
+    emit_changed(
+        &app,
+        &path,
+        Some(&sheet_name),
+        Some(anchor_range),
+        ChangeKind::Chart,
+    );
+    Ok(result)
+}
+
+/// `list_charts` 列出 sheet 中的图表。
+#[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, sheet = %sheet_name))]
+pub fn list_charts<R: Runtime>(
+    _app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+    sheet_name: String,
+) -> Result<Vec<ChartInfo>, Error> {
+    state.with_workbook(&path, |workbook| workbook.list_charts(&sheet_name))
+}
+
+/// `remove_chart` 删除指定索引的图表。
+#[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, sheet = %sheet_name))]
+pub fn remove_chart<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+    sheet_name: String,
+    index: usize,
+) -> Result<(), Error> {
+    state.edit(
+        &path,
+        EditScope::Sheet(&sheet_name),
+        "remove_chart",
+        |workbook| workbook.remove_chart(&sheet_name, index),
+    )?;
+    emit_changed(&app, &path, Some(&sheet_name), None, ChangeKind::Chart);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(value: serde_json::Value) -> ChartSpec {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn parse_chart_spec() {
+        let kinds = [
+            ("bar", "Bar"),
+            ("column", "Column"),
+            ("line", "Line"),
+            ("pie", "Pie"),
+            ("scatter", "Scatter"),
+            ("area", "Area"),
+        ];
+        for (name, expected) in kinds.iter() {
+            let kind: ChartKind = serde_json::from_value(serde_json::json!(name)).unwrap();
+            assert_eq!(format!("{:?}", kind), *expected);
+        }
+        assert!(serde_json::from_value::<ChartKind>(serde_json::json!("Bar")).is_err());
+
+        let spec = spec(serde_json::json!({
+            "kind": "line",
+            "series": [{ "values": "Sheet2!B2:B10", "title": "Sales" }, { "values": "C2:C10" }],
+            "categories": "A2:A10",
+            "xAxisTitle": "Month",
+            "legend": "topRight",
+        }));
+        assert_eq!(spec.series[0].values, "Sheet2!B2:B10");
+        assert_eq!(spec.series[0].title.as_deref(), Some("Sales"));
+        assert_eq!(spec.series[1].title, None);
+        assert_eq!(spec.x_axis_title.as_deref(), Some("Month"));
+        assert!(matches!(spec.legend, Some(LegendPosition::TopRight)));
+        assert!(serde_json::from_value::<SeriesSpec>(serde_json::json!({ "title": "x" })).is_err());
+    }
+
+    #[test]
+    fn add_list_and_remove_charts() {
+        let mut workbook = Workbook::new(umya_spreadsheet::new_file());
+        let line = spec(serde_json::json!({
+            "kind": "line",
+            "series": [{ "values": "B2:B10" }],
+            "categories": "A2:A10",
+        }));
+        let pie = spec(serde_json::json!({ "kind": "pie", "series": [{ "values": "C2:C5" }] }));
+        assert_eq!(workbook.add_chart("Sheet1", "E2:L20", &line).unwrap(), 0);
+        assert_eq!(workbook.add_chart("Sheet1", "N2:P8", &pie).unwrap(), 1);
+        let anchors = |workbook: &Workbook| -> Vec<String> {
+            workbook
+                .list_charts("Sheet1")
+                .unwrap()
+                .into_iter()
+                .map(|chart| chart.anchor_range)
+                .collect()
+        };
+        assert_eq!(anchors(&workbook), vec!["E2:L20", "N2:P8"]);
+
+        workbook.remove_chart("Sheet1", 0).unwrap();
+        assert_eq!(anchors(&workbook), vec!["N2:P8"]);
+        assert!(workbook.remove_chart("Sheet1", 1).is_err());
+        assert!(workbook.list_charts("Missing").is_err());
+    }
+
+    #[test]
+    fn invalid_charts_are_rejected() {
+        let mut workbook = Workbook::new(umya_spreadsheet::new_file());
+        let empty = spec(serde_json::json!({ "kind": "bar", "series": [] }));
+        assert!(workbook.add_chart("Sheet1", "E2:L20", &empty).is_err());
+        let scatter =
+            spec(serde_json::json!({ "kind": "scatter", "series": [{ "values": "B2:B10" }] }));
+        assert!(workbook.add_chart("Sheet1", "E2:L20", &scatter).is_err());
+        assert!(workbook.list_charts("Sheet1").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,305 @@
+use crate::error::Error;
+use crate::event::{emit_changed, ChangeKind};
+use crate::history::EditScope;
+use crate::range::CellRange;
+use crate::workbook::{Workbook, WorkbookRegistry};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tauri::{command, AppHandle, Runtime, State, Window};
+use tracing::{debug, instrument};
+use umya_spreadsheet::Worksheet;
+
+/// 重复行保留的位置。
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DuplicateKeep {
+    First,
+    Last,
+}
+
+impl Default for DuplicateKeep {
+    fn default() -> Self {
+        DuplicateKeep::First
+    }
+}
+
+/// 删除行的结果。
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemovedRows {
+    pub count: usize,
+    /// 删除前的行索引，升序。
+    pub rows: Vec<u32>,
+}
+
+/// `row_values` 获取区域中一行指定列的值。
+fn row_values(worksheet: &Worksheet, columns: &[u32], row: u32) -> Vec<String> {
+    columns
+        .iter()
+        .map(|column| {
+            worksheet
+                .get_value_by_column_and_row(column, &row)
+                .trim()
+                .to_string()
+        })
+        .collect()
+}
+
+/// `remove_rows` 从下往上删除整行，连续的行一次删除，下方的行上移。
+fn remove_rows(worksheet: &mut Worksheet, rows: &[u32]) {
+    let mut index = rows.len();
+    while index > 0 {
+        let end = rows[index - 1];
+        let mut start = end;
+        index -= 1;
+        while index > 0 && rows[index - 1] + 1 == start {
+            start -= 1;
+            index -= 1;
+        }
+        crate::worksheet::remove_rows(worksheet, start, end - start + 1);
+    }
+}
+
+/// `duplicate_rows` 查找区域中的重复行，返回需要删除的行索引，升序。
+fn duplicate_rows(
+    worksheet: &Worksheet,
+    range: &CellRange,
+    key_columns: &[u32],
+    keep: DuplicateKeep,
+) -> Vec<u32> {
+    let rows: Vec<u32> = match keep {
+        DuplicateKeep::First => range.rows().collect(),
+        DuplicateKeep::Last => range.rows().rev().collect(),
+    };
+    let mut seen = HashSet::new();
+    let mut duplicates: Vec<u32> = rows
+        .into_iter()
+        .filter(|row| !seen.insert(row_values(worksheet, key_columns, *row)))
+        .collect();
+    duplicates.sort_unstable();
+    duplicates
+}
+
+/// `blank_rows` 查找区域中整行都为空的行，升序。
+///
+/// 删除的是整行，因此区域外还有值或公式的行不算空行。
+fn blank_rows(worksheet: &Worksheet, range: &CellRange) -> Vec<u32> {
+    range
+        .rows()
+        .filter(|row| {
+            worksheet
+                .get_collection_by_row(row)
+                .iter()
+                .all(|cell| cell.get_value().trim().is_empty() && cell.get_formula().is_empty())
+        })
+        .collect()
+}
+
+impl Workbook {
+    /// `remove_duplicates` 删除区域中的重复行，整行删除，下方的行上移。
+    ///
+    /// 只按 `key_columns` 判断是否重复，区域外的单元格也会随整行一起删除。
+    ///
+    /// # Arguments
+    ///
+    /// - `range` 数据区域或名称，如: "A2:F500"
+    /// - `key_columns` 判断重复的列索引，从 1 开始，为空时使用区域的所有列
+    /// - `keep` 保留第一次或最后一次出现的行
+    pub fn remove_duplicates(
+        &mut self,
+        sheet_name: &str,
+        range: &str,
+        key_columns: &[u32],
+        keep: DuplicateKeep,
+    ) -> Result<RemovedRows, Error> {
+        let range = self.resolve_range(sheet_name, range)?;
+        let worksheet = self.worksheet_mut(sheet_name)?;
+        let key_columns: Vec<u32> = if key_columns.is_empty() {
+            range.columns().collect()
+        } else {
+            key_columns.to_vec()
+        };
+        for column in &key_columns {
+            if !range.columns().contains(column) {
+                return Err(Error::String(format!(
+                    "列 {} 不在区域 {} 内!",
+                    column, range
+                )));
+            }
+        }
+        let rows = duplicate_rows(worksheet, &range, &key_columns, keep);
+        remove_rows(worksheet, &rows);
+        debug!("删除区域 {} 中的重复行 {} 行", range, rows.len());
+        Ok(RemovedRows {
+            count: rows.len(),
+            rows,
+        })
+    }
+
+    /// `remove_blank_rows` 删除区域中整行都为空的行，下方的行上移。
+    ///
+    /// 区域外还有值或公式的行不会删除。
+    pub fn remove_blank_rows(
+        &mut self,
+        sheet_name: &str,
+        range: &str,
+    ) -> Result<RemovedRows, Error> {
+        let range = self.resolve_range(sheet_name, range)?;
+        let worksheet = self.worksheet_mut(sheet_name)?;
+        let rows = blank_rows(worksheet, &range);
+        remove_rows(worksheet, &rows);
+        debug!("删除区域 {} 中的空行 {} 行", range, rows.len());
+        Ok(RemovedRows {
+            count: rows.len(),
+            rows,
+        })
+    }
+}
+
+/// `remove_duplicates` 删除区域中的重复行，整行删除，下方的行上移。
+///
+/// 只按 `key_columns` 判断是否重复，区域外的单元格也会随整行一起删除。
+///
+/// # Arguments
+///
+/// - `range` 数据区域或名称，如: "A2:F500"
+/// - `key_columns` 判断重复的列索引，从 1 开始，默认为区域的所有列
+/// - `keep` 保留第一次 (first) 或最后一次 (last) 出现的行，默认为 first
+#[command]
+#[instrument(
+    level = "debug",
+    skip_all,
+    fields(workbook = %path, sheet = %sheet_name, range = %range)
+)]
+pub fn remove_duplicates<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+    sheet_name: String,
+    range: String,
+    key_columns: Option<Vec<u32>>,
+    keep: Option<DuplicateKeep>,
+) -> Result<RemovedRows, Error> {
+    let result = state.edit(
+        &path,
+        EditScope::Sheet(&sheet_name),
+        "remove_duplicates",
+        |workbook| {
+            workbook.remove_duplicates(
+                &sheet_name,
+                &range,
+                &key_columns.unwrap_or_default(),
+                keep.unwrap_or_default(),
+            )
+        },
+    )?;
+    if result.count > 0 {
+        emit_changed(
+            &app,
+            &path,
+            Some(&sheet_name),
+            Some(range),
+            ChangeKind::RemoveRows,
+        );
+    }
+    Ok(result)
+}
+
+/// `remove_blank_rows` 删除区域中整行都为空的行，下方的行上移。
+///
+/// 区域外还有值或公式的行不会删除。
+///
+/// # Arguments
+///
+/// - `range` 数据区域或名称，如: "A1:F500"
+#[command]
+#[instrument(
+    level = "debug",
+    skip_all,
+    fields(workbook = %path, sheet = %sheet_name, range = %range)
+)]
+pub fn remove_blank_rows<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+    sheet_name: String,
+    range: String,
+) -> Result<RemovedRows, Error> {
+    let result = state.edit(
+        &path,
+        EditScope::Sheet(&sheet_name),
+        "remove_blank_rows",
+        |workbook| workbook.remove_blank_rows(&sheet_name, &range),
+    )?;
+    if result.count > 0 {
+        emit_changed(
+            &app,
+            &path,
+            Some(&sheet_name),
+            Some(range),
+            ChangeKind::RemoveRows,
+        );
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blank_rows_ignore_rows_with_content_outside_range() {
+        let mut worksheet = Worksheet::default();
+        worksheet
+            .get_cell_by_column_and_row_mut(&1, &1)
+            .set_value("a");
+        worksheet
+            .get_cell_by_column_and_row_mut(&5, &2)
+            .set_value("note");
+        worksheet
+            .get_cell_by_column_and_row_mut(&5, &4)
+            .set_formula("A1");
+        worksheet
+            .get_cell_by_column_and_row_mut(&2, &5)
+            .set_value(" ");
+        let range = CellRange::new((1, 1), (3, 6));
+        assert_eq!(blank_rows(&worksheet, &range), vec![3, 5, 6]);
+    }
+
+    #[test]
+    fn duplicate_rows_keep_first_or_last() {
+        let mut worksheet = Worksheet::default();
+        for (row, value) in ["x", "y", "x ", "x"].iter().enumerate() {
+            worksheet
+                .get_cell_by_column_and_row_mut(&1, &(row as u32 + 1))
+                .set_value(*value);
+        }
+        let range = CellRange::new((1, 1), (1, 4));
+        assert_eq!(
+            duplicate_rows(&worksheet, &range, &[1], DuplicateKeep::First),
+            vec![3, 4]
+        );
+        assert_eq!(
+            duplicate_rows(&worksheet, &range, &[1], DuplicateKeep::Last),
+            vec![1, 3]
+        );
+    }
+
+    #[test]
+    fn remove_rows_deletes_cells_and_shifts_rows_up() {
+        let mut worksheet = Worksheet::default();
+        for row in 1..=6 {
+            worksheet
+                .get_cell_by_column_and_row_mut(&1, &row)
+                .set_value(row.to_string());
+        }
+        remove_rows(&mut worksheet, &[2, 3, 5]);
+        let values: Vec<String> = (1..=6)
+            .map(|row| worksheet.get_value_by_column_and_row(&1, &row))
+            .collect();
+        assert_eq!(values, vec!["1", "4", "6", "", "", ""]);
+        assert_eq!(worksheet.get_cell_collection().len(), 3);
+    }
+}
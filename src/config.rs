@@ -0,0 +1,121 @@
+use crate::error::Error;
+use crate::scope::PathScope;
+use serde::Deserialize;
+use std::path::PathBuf;
+use tracing::level_filters::LevelFilter;
+use tracing::warn;
+use tracing_subscriber::{filter::Targets, fmt::format::FmtSpan, prelude::*};
+
+/// 新建文件时 umya-spreadsheet 创建的 sheet 名称。
+pub(crate) const NEW_FILE_SHEET_NAME: &str = "Sheet1";
+
+/// 插件配置，对应 `tauri.conf.json` 中的 `plugins.spreadsheet`，也可以通过 [`crate::Builder`] 设置。
+///
+/// ```json
+/// {
+///   "plugins": {
+///     "spreadsheet": {
+///       "scope": { "allow": ["/data/reports"] },
+///       "maxWorkbooks": 8,
+///       "maxCells": 200000,
+///       "defaultSheetName": "Data",
+///       "logLevel": "info",
+///       "autosaveInterval": 60,
+///       "backups": 3
+///     }
+///   }
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Config {
+    /// 允许读写的路径范围。
+    pub scope: PathScope,
+    /// 同时打开的文件数量上限。
+    pub max_workbooks: Option<usize>,
+    /// 单次返回给前端的单元格数量上限。
+    pub max_cells: Option<usize>,
+    /// `new_xlsx` 新建文件的 sheet 名称，默认 "Sheet1"。
+    pub default_sheet_name: Option<String>,
+    /// 插件日志级别，设置后插件会安装只输出本插件日志的 tracing subscriber。
+    pub log_level: Option<LogLevel>,
+    /// 自动保存间隔 (秒)，为空时不自动保存。
+    pub autosave_interval: Option<u64>,
+    /// 自动保存的目录，默认为应用数据目录下的 `spreadsheet-recovery`。
+    pub recovery_dir: Option<PathBuf>,
+    /// 保存时保留的备份数量，最近的备份为 `name.bak.xlsx`，为空时不备份。
+    pub backups: Option<usize>,
+}
+
+/// 日志级别。
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Off => LevelFilter::OFF,
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+/// `init_logging` 安装输出本插件日志的 tracing subscriber，命令结束时输出耗时。
+///
+/// 应用已经设置全局 subscriber 时忽略，日志由应用的 subscriber 处理。
+pub(crate) fn init_logging(level: LogLevel) {
+    let filter = Targets::new().with_target(env!("CARGO_CRATE_NAME"), LevelFilter::from(level));
+    let layer = tracing_subscriber::fmt::layer().with_span_events(FmtSpan::CLOSE);
+    if tracing_subscriber::registry()
+        .with(layer)
+        .with(filter)
+        .try_init()
+        .is_err()
+    {
+        warn!("已存在全局 tracing subscriber，忽略插件日志级别配置");
+    }
+}
+
+impl Config {
+    /// `merge` 合并 `tauri.conf.json` 中的配置，已设置的选项优先，路径范围取并集。
+    pub(crate) fn merge(mut self, other: Config) -> Config {
+        self.scope.allow.extend(other.scope.allow);
+        self.scope.deny.extend(other.scope.deny);
+        Config {
+            scope: self.scope,
+            max_workbooks: self.max_workbooks.or(other.max_workbooks),
+            max_cells: self.max_cells.or(other.max_cells),
+            default_sheet_name: self.default_sheet_name.or(other.default_sheet_name),
+            log_level: self.log_level.or(other.log_level),
+            autosave_interval: self.autosave_interval.or(other.autosave_interval),
+            recovery_dir: self.recovery_dir.or(other.recovery_dir),
+            backups: self.backups.or(other.backups),
+        }
+    }
+
+    /// `check_cells` 检查返回给前端的单元格数量是否超过上限。
+    pub(crate) fn check_cells(&self, data: &[Vec<String>]) -> Result<(), Error> {
+        if let Some(max_cells) = self.max_cells {
+            let count: usize = data.iter().map(|row| row.len()).sum();
+            if count > max_cells {
+                return Err(Error::String(format!(
+                    "返回的单元格数量 {} 超过上限 {}，请缩小读取范围!",
+                    count, max_cells
+                )));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,383 @@
+use crate::error::Error;
+use crate::event::{emit_changed, ChangeKind};
+use crate::history::EditScope;
+use crate::range::{self, CellRange};
+use crate::workbook::{Workbook, WorkbookRegistry};
+use serde::Serialize;
+use tauri::{command, AppHandle, Runtime, State, Window};
+use tracing::{debug, instrument};
+use umya_spreadsheet::structs::DefinedName;
+use umya_spreadsheet::{Spreadsheet, Worksheet};
+
+/// 名称信息。
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DefinedNameInfo {
+    pub name: String,
+    /// 作用范围，`None` 表示整个文件，否则为 sheet 名称。
+    pub scope: Option<String>,
+    /// 引用位置，如 `'Sheet1'!$A$1:$C$10`。
+    pub refers_to: String,
+}
+
+/// `validate_name` 检查名称是否符合 Excel 规则，不能与单元格坐标相同。
+fn validate_name(name: &str) -> Result<(), Error> {
+    let mut chars = name.chars();
+    let valid_first = match chars.next() {
+        Some(c) => c.is_alphabetic() || c == '_' || c == '\\',
+        None => false,
+    };
+    let valid_rest = chars.all(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == '\\');
+    if !valid_first || !valid_rest || range::parse_coordinate(name).is_some() {
+        return Err(Error::String(format!("名称 {} 不合法!", name)));
+    }
+    Ok(())
+}
+
+/// `sheet_index` 根据名称获取 sheet 索引。
+fn sheet_index(spreadsheet: &Spreadsheet, sheet_name: &str) -> Result<usize, Error> {
+    spreadsheet
+        .get_sheet_collection()
+        .iter()
+        .position(|worksheet| worksheet.get_name() == sheet_name)
+        .ok_or_else(|| Error::String(format!("sheet {} 不存在!", sheet_name)))
+}
+
+/// `scope_of` 获取名称的作用范围。
+///
+/// umya 0.8 读取文件时不保留名称所属 sheet 的索引，只记录名称是否只在 sheet 内有效，
+/// 并把名称保存在引用的 sheet 上，因此以保存名称的 sheet 作为作用范围。
+fn scope_of(worksheet: &Worksheet, defined_name: &DefinedName) -> Option<String> {
+    if *defined_name.get_is_local_only() {
+        Some(worksheet.get_name().to_string())
+    } else {
+        None
+    }
+}
+
+/// `find_defined_name` 查找指定作用范围的名称，返回 (sheet 索引, 名称索引)。
+fn find_defined_name(
+    spreadsheet: &Spreadsheet,
+    name: &str,
+    scope: &Option<String>,
+) -> Option<(usize, usize)> {
+    for (sheet_index, worksheet) in spreadsheet.get_sheet_collection().iter().enumerate() {
+        for (name_index, defined_name) in worksheet.get_defined_names().iter().enumerate() {
+            if defined_name.get_name().eq_ignore_ascii_case(name)
+                && &scope_of(worksheet, defined_name) == scope
+            {
+                return Some((sheet_index, name_index));
+            }
+        }
+    }
+    None
+}
+
+/// `visible_name` 查找在 `sheet_name` 中可用的名称，返回名称所在的 sheet 和名称。
+///
+/// 作用范围为 `sheet_name` 的名称优先，其次是作用于整个文件的名称。
+fn visible_name<'a>(
+    spreadsheet: &'a Spreadsheet,
+    sheet_name: &str,
+    name: &str,
+) -> Option<(&'a Worksheet, &'a DefinedName)> {
+    let mut workbook_name = None;
+    for worksheet in spreadsheet.get_sheet_collection() {
+        for defined_name in worksheet.get_defined_names() {
+            if !defined_name.get_name().eq_ignore_ascii_case(name) {
+                continue;
+            }
+            match scope_of(worksheet, defined_name) {
+                Some(scope) if scope == sheet_name => return Some((worksheet, defined_name)),
+                Some(_) => {}
+                None => {
+                    if workbook_name.is_none() {
+                        workbook_name = Some((worksheet, defined_name));
+                    }
+                }
+            }
+        }
+    }
+    workbook_name
+}
+
+/// `lookup_range` 在整个文件中解析区域，返回 (区域所在的 sheet 名称, 区域)。
+///
+/// `range` 可以是在 `sheet_name` 中可用的名称，也可以带 sheet 名称，如 `Sheet2!A1:C10`，
+/// 不带 sheet 名称的区域位于 `sheet_name`。
+pub(crate) fn lookup_range(
+    spreadsheet: &Spreadsheet,
+    sheet_name: &str,
+    range: &str,
+) -> Result<(String, CellRange), Error> {
+    let (reference, default_sheet) = match visible_name(spreadsheet, sheet_name, range.trim()) {
+        Some((worksheet, defined_name)) => (defined_name.get_address(), worksheet.get_name()),
+        None => (range.to_string(), sheet_name),
+    };
+    let (sheet, range) = range::split_sheet(&reference);
+    let sheet = sheet.unwrap_or_else(|| default_sheet.to_string());
+    let worksheet = match spreadsheet.get_sheet_by_name(&sheet) {
+        Ok(worksheet) => worksheet,
+        Err(error) => {
+            return Err(Error::String(format!(
+                "获取 sheet {} 失败! {}",
+                &sheet, error
+            )))
+        }
+    };
+    let range = CellRange::parse(range, worksheet.get_highest_column_and_row())?;
+    Ok((sheet, range))
+}
+
+/// `normalize_reference` 将引用转换为带 sheet 名称的绝对引用，返回 (sheet 名称, 引用)。
+fn normalize_reference(
+    spreadsheet: &Spreadsheet,
+    refers_to: &str,
+) -> Result<(String, String), Error> {
+    let (sheet_name, range) = range::split_sheet(refers_to);
+    let sheet_name = match sheet_name {
+        Some(sheet_name) => sheet_name,
+        None => {
+            return Err(Error::String(format!(
+                "引用 {} 需要包含 sheet 名称!",
+                refers_to
+            )))
+        }
+    };
+    let worksheet = match spreadsheet.get_sheet_by_name(&sheet_name) {
+        Ok(worksheet) => worksheet,
+        Err(error) => {
+            return Err(Error::String(format!(
+                "获取 sheet {} 失败! {}",
+                &sheet_name, error
+            )))
+        }
+    };
+    let range = CellRange::parse(range, worksheet.get_highest_column_and_row())?;
+    let reference = range.to_absolute(&sheet_name);
+    Ok((sheet_name, reference))
+}
+
+/// `unsupported_scope` umya 0.8 只能新建作用于整个文件的名称。
+fn unsupported_scope(scope: &str) -> Error {
+    Error::String(format!(
+        "不支持新建或修改作用范围为 sheet {} 的名称!",
+        scope
+    ))
+}
+
+impl Workbook {
+    /// `resolve_range` 解析 `sheet_name` 上的区域，`range` 也可以是名称。
+    ///
+    /// 名称按作用范围查找，名称或带 sheet 名称的区域位于其他 sheet 时返回错误。
+    pub fn resolve_range(&self, sheet_name: &str, range: &str) -> Result<CellRange, Error> {
+        let (sheet, cell_range) = lookup_range(&self.spreadsheet, sheet_name, range)?;
+        if sheet != sheet_name {
+            return Err(Error::String(format!(
+                "区域 {} 位于 sheet {}，不在 sheet {} 中!",
+                range, sheet, sheet_name
+            )));
+        }
+        Ok(cell_range)
+    }
+
+    /// `list_defined_names` 列出文件中的所有名称。
+    pub fn list_defined_names(&self) -> Vec<DefinedNameInfo> {
+        let mut names = Vec::new();
+        for worksheet in self.spreadsheet.get_sheet_collection() {
+            for defined_name in worksheet.get_defined_names() {
+                names.push(DefinedNameInfo {
+                    name: defined_name.get_name().to_string(),
+                    scope: scope_of(worksheet, defined_name),
+                    refers_to: defined_name.get_address(),
+                });
+            }
+        }
+        debug!("获取名称列表: {} 个", names.len());
+        names
+    }
+
+    /// `add_defined_name` 新建作用于整个文件的名称。
+    ///
+    /// # Arguments
+    ///
+    /// - `name` 名称，如: "InvoiceTotal"
+    /// - `refers_to` 引用位置，如: "Sheet1!$F$20"
+    /// - `scope` 作用的 sheet 名称，umya 0.8 不支持写入 sheet 作用范围，不为空时返回错误
+    pub fn add_defined_name(
+        &mut self,
+        name: &str,
+        refers_to: &str,
+        scope: Option<String>,
+    ) -> Result<DefinedNameInfo, Error> {
+        let book = &mut self.spreadsheet;
+        validate_name(name)?;
+        if let Some(scope) = &scope {
+            return Err(unsupported_scope(scope));
+        }
+        if find_defined_name(book, name, &scope).is_some() {
+            return Err(Error::String(format!("名称 {} 已存在!", name)));
+        }
+        let (sheet_name, reference) = normalize_reference(book, refers_to)?;
+        let index = sheet_index(book, &sheet_name)?;
+        // 名称保存在引用的 sheet 上，与 umya 读取文件时的处理一致。
+        let worksheet = &mut book.get_sheet_collection_mut()[index];
+        if let Err(error) = worksheet.add_defined_name(name, reference.as_str()) {
+            return Err(Error::String(format!("新建名称 {} 失败! {}", name, error)));
+        }
+        debug!("新建名称 {} 引用 {}", name, &reference);
+        Ok(DefinedNameInfo {
+            name: name.to_string(),
+            scope,
+            refers_to: reference,
+        })
+    }
+
+    /// `update_defined_name` 修改名称的引用位置，`new_name` 不为空时同时重命名。
+    ///
+    /// 只能修改作用于整个文件的名称。
+    pub fn update_defined_name(
+        &mut self,
+        name: &str,
+        scope: Option<String>,
+        refers_to: &str,
+        new_name: Option<String>,
+    ) -> Result<DefinedNameInfo, Error> {
+        let book = &mut self.spreadsheet;
+        let (source_index, name_index) = match find_defined_name(book, name, &scope) {
+            Some(found) => found,
+            None => return Err(Error::String(format!("名称 {} 不存在!", name))),
+        };
+        // umya 0.8 不能修改已有名称，只能删除后重新添加，而新添加的名称总是作用于整个文件。
+        if let Some(scope) = &scope {
+            return Err(unsupported_scope(scope));
+        }
+        let target_name = new_name.unwrap_or_else(|| name.to_string());
+        validate_name(&target_name)?;
+        if !target_name.eq_ignore_ascii_case(name)
+            && find_defined_name(book, &target_name, &scope).is_some()
+        {
+            return Err(Error::String(format!("名称 {} 已存在!", &target_name)));
+        }
+        let (sheet_name, reference) = normalize_reference(book, refers_to)?;
+        let target_index = sheet_index(book, &sheet_name)?;
+        let removed = book.get_sheet_collection_mut()[source_index]
+            .get_defined_names_mut()
+            .remove(name_index);
+        let added = book.get_sheet_collection_mut()[target_index]
+            .add_defined_name(target_name.as_str(), reference.as_str())
+            .map_err(|error| error.to_string());
+        if let Err(error) = added {
+            book.get_sheet_collection_mut()[source_index]
+                .get_defined_names_mut()
+                .insert(name_index, removed);
+            return Err(Error::String(format!("修改名称 {} 失败! {}", name, error)));
+        }
+        debug!("修改名称 {} 为 {} 引用 {}", name, &target_name, &reference);
+        Ok(DefinedNameInfo {
+            name: target_name,
+            scope,
+            refers_to: reference,
+        })
+    }
+
+    /// `remove_defined_name` 删除名称。
+    pub fn remove_defined_name(&mut self, name: &str, scope: &Option<String>) -> Result<(), Error> {
+        let book = &mut self.spreadsheet;
+        match find_defined_name(book, name, scope) {
+            Some((sheet_index, name_index)) => {
+                book.get_sheet_collection_mut()[sheet_index]
+                    .get_defined_names_mut()
+                    .remove(name_index);
+                debug!("删除名称 {}", name);
+                Ok(())
+            }
+            None => Err(Error::String(format!("名称 {} 不存在!", name))),
+        }
+    }
+}
+
+/// `list_defined_names` 列出文件中的所有名称。
+#[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path))]
+pub fn list_defined_names<R: Runtime>(
+    _app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+) -> Result<Vec<DefinedNameInfo>, Error> {
+    state.with_workbook(&path, |workbook| Ok(workbook.list_defined_names()))
+}
+
+/// `add_defined_name` 新建作用于整个文件的名称。
+///
+/// # Arguments
+///
+/// - `name` 名称，如: "InvoiceTotal"
+/// - `refers_to` 引用位置，如: "Sheet1!$F$20"
+/// - `scope` 作用的 sheet 名称，umya 0.8 不支持写入 sheet 作用范围，不为空时返回错误
+#[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, name = %name))]
+pub fn add_defined_name<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+    name: String,
+    refers_to: String,
+    scope: Option<String>,
+) -> Result<DefinedNameInfo, Error> {
+    let result = state.edit(&path, EditScope::Workbook, "add_defined_name", |workbook| {
+        workbook.add_defined_name(&name, &refers_to, scope)
+    })?;
+    let range = Some(result.refers_to.clone());
+    emit_changed(&app, &path, None, range, ChangeKind::DefinedName);
+    Ok(result)
+}
+
+/// `update_defined_name` 修改名称的引用位置，`new_name` 不为空时同时重命名。
+///
+/// 只能修改作用于整个文件的名称。
+#[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, name = %name))]
+pub fn update_defined_name<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+    name: String,
+    scope: Option<String>,
+    refers_to: String,
+    new_name: Option<String>,
+) -> Result<DefinedNameInfo, Error> {
+    let result = state.edit(
+        &path,
+        EditScope::Workbook,
+        "update_defined_name",
+        |workbook| workbook.update_defined_name(&name, scope, &refers_to, new_name),
+    )?;
+    let range = Some(result.refers_to.clone());
+    emit_changed(&app, &path, None, range, ChangeKind::DefinedName);
+    Ok(result)
+}
+
+/// `remove_defined_name` 删除名称。
+#[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, name = %name))]
+pub fn remove_defined_name<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+    name: String,
+    scope: Option<String>,
+) -> Result<(), Error> {
+    state.edit(
+        &path,
+        EditScope::Workbook,
+        "remove_defined_name",
+        |workbook| workbook.remove_defined_name(&name, &scope),
+    )?;
+    emit_changed(&app, &path, None, None, ChangeKind::DefinedName);
+    Ok(())
+}
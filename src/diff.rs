@@ -0,0 +1,648 @@
+use crate::error::Error;
+use crate::range::{self, column_index_from_string};
+use crate::save::write_atomic;
+use crate::scope::check_path;
+use crate::workbook::{Workbook, WorkbookRegistry};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use tauri::{command, AppHandle, Runtime, State, Window};
+use tracing::{debug, info, instrument, warn};
+use umya_spreadsheet::{Spreadsheet, Worksheet};
+
+/// 按内容对齐行时动态规划表的最大单元数，超过时只对齐首尾相同的行。
+const LCS_LIMIT: usize = 4_000_000;
+/// sheet 名称不同但内容相似度达到该值时视为重命名。
+const RENAME_SIMILARITY: f64 = 0.5;
+/// 差异文件中修改的单元格和插入的行的背景色。
+const CHANGED_COLOR: &str = "FFFFEB9C";
+const INSERTED_COLOR: &str = "FFC6EFCE";
+
+/// 比较选项。
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DiffOptions {
+    /// 按该列的值对齐行，如 `"A"`，为空时按内容对齐。
+    pub key_column: Option<String>,
+    /// 表头行数，表头按位置对齐。
+    pub header_rows: u32,
+    /// 保存差异文件的路径，在第二个文件的副本中标出修改的单元格和插入的行。
+    pub output_path: Option<String>,
+}
+
+/// 单元格的值和公式。
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CellContent {
+    pub value: String,
+    pub formula: String,
+}
+
+impl CellContent {
+    pub fn is_empty(&self) -> bool {
+        self.value.is_empty() && self.formula.is_empty()
+    }
+}
+
+/// `cell_contents` 获取 sheet 中所有非空单元格的内容，键为 (column, row)。
+pub(crate) fn cell_contents(worksheet: &Worksheet) -> HashMap<(u32, u32), CellContent> {
+    worksheet
+        .get_cell_collection()
+        .iter()
+        .map(|cell| {
+            let coordinate = cell.get_coordinate();
+            (
+                (*coordinate.get_col_num(), *coordinate.get_row_num()),
+                CellContent {
+                    value: cell.get_value().to_string(),
+                    formula: cell.get_formula().to_string(),
+                },
+            )
+        })
+        .filter(|(_, content)| !content.is_empty())
+        .collect()
+}
+
+/// 修改的单元格，行对齐后坐标可能不同。
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CellChange {
+    /// 第一个文件中的坐标。
+    pub old_cell: String,
+    /// 第二个文件中的坐标。
+    pub cell: String,
+    pub old_value: String,
+    pub new_value: String,
+    pub old_formula: String,
+    pub new_formula: String,
+}
+
+/// 同一 sheet 的差异。
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SheetDiff {
+    /// 第一个文件中的 sheet 名称，重命名时与 `name` 不同。
+    pub old_name: String,
+    pub name: String,
+    /// 第二个文件中插入的行。
+    pub inserted_rows: Vec<u32>,
+    /// 第一个文件中被删除的行。
+    pub deleted_rows: Vec<u32>,
+    pub changed_cells: Vec<CellChange>,
+}
+
+impl SheetDiff {
+    fn is_empty(&self) -> bool {
+        self.old_name == self.name
+            && self.inserted_rows.is_empty()
+            && self.deleted_rows.is_empty()
+            && self.changed_cells.is_empty()
+    }
+}
+
+/// 重命名的 sheet。
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenamedSheet {
+    pub from: String,
+    pub to: String,
+}
+
+/// 两个文件的差异。
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkbookDiff {
+    pub added_sheets: Vec<String>,
+    pub removed_sheets: Vec<String>,
+    pub renamed_sheets: Vec<RenamedSheet>,
+    /// 有差异的 sheet。
+    pub sheets: Vec<SheetDiff>,
+}
+
+/// 行对齐结果，索引从 0 开始。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RowPair {
+    Matched(usize, usize),
+    Deleted(usize),
+    Inserted(usize),
+}
+
+/// 按行排列的非空单元格，键为列索引，从 1 开始。
+struct Rows {
+    rows: Vec<BTreeMap<u32, CellContent>>,
+}
+
+impl Rows {
+    fn new(worksheet: &Worksheet) -> Self {
+        let height = worksheet.get_highest_row();
+        let mut rows = vec![BTreeMap::new(); height as usize];
+        for ((column, row), content) in cell_contents(worksheet) {
+            rows[row as usize - 1].insert(column, content);
+        }
+        Rows { rows }
+    }
+
+    fn cell(&self, row: usize, column: u32) -> CellContent {
+        self.rows
+            .get(row)
+            .and_then(|row| row.get(&column))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// `columns` 两行中任一行有内容的列，升序。
+    fn columns(&self, row: usize, other: &Rows, other_row: usize) -> BTreeSet<u32> {
+        let keys = |rows: &Rows, row: usize| {
+            rows.rows
+                .get(row)
+                .map(|row| row.keys().copied().collect::<Vec<_>>())
+                .unwrap_or_default()
+        };
+        keys(self, row)
+            .into_iter()
+            .chain(keys(other, other_row))
+            .collect()
+    }
+
+    fn hashes(&self) -> Vec<u64> {
+        self.rows
+            .iter()
+            .map(|row| {
+                let mut hasher = DefaultHasher::new();
+                row.hash(&mut hasher);
+                hasher.finish()
+            })
+            .collect()
+    }
+}
+
+/// `align_by_content` 按最长公共子序列对齐内容相同的行，相邻的未对齐行按位置配对为修改的行。
+fn align_by_content(old: &[u64], new: &[u64]) -> Vec<RowPair> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    // 相同的行作为锚点
+    let mut anchors: Vec<(usize, usize)> = (0..prefix).map(|i| (i, i)).collect();
+    if old_middle.len() * new_middle.len() <= LCS_LIMIT {
+        anchors.extend(
+            lcs(old_middle, new_middle)
+                .into_iter()
+                .map(|(i, j)| (i + prefix, j + prefix)),
+        );
+    } else {
+        warn!(
+            "对比的行数过多 ({} x {})，中间的行按位置对齐",
+            old_middle.len(),
+            new_middle.len()
+        );
+    }
+    anchors.extend((0..suffix).map(|i| (old.len() - suffix + i, new.len() - suffix + i)));
+
+    let mut pairs = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    for (anchor_i, anchor_j) in anchors
+        .into_iter()
+        .chain(std::iter::once((old.len(), new.len())))
+    {
+        pair_gap(&mut pairs, i..anchor_i, j..anchor_j);
+        if anchor_i < old.len() {
+            pairs.push(RowPair::Matched(anchor_i, anchor_j));
+        }
+        i = anchor_i + 1;
+        j = anchor_j + 1;
+    }
+    pairs
+}
+
+/// `pair_gap` 两个锚点之间的行按位置配对，多出的行为删除或插入。
+fn pair_gap(pairs: &mut Vec<RowPair>, old: std::ops::Range<usize>, new: std::ops::Range<usize>) {
+    let common = old.len().min(new.len());
+    for k in 0..common {
+        pairs.push(RowPair::Matched(old.start + k, new.start + k));
+    }
+    pairs.extend((old.start + common..old.end).map(RowPair::Deleted));
+    pairs.extend((new.start + common..new.end).map(RowPair::Inserted));
+}
+
+/// `lcs` 最长公共子序列，返回相同元素的索引对。
+fn lcs(old: &[u64], new: &[u64]) -> Vec<(usize, usize)> {
+    let (n, m) = (old.len(), new.len());
+    let mut table = vec![0u32; (n + 1) * (m + 1)];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i * (m + 1) + j] = if old[i] == new[j] {
+                table[(i + 1) * (m + 1) + j + 1] + 1
+            } else {
+                table[(i + 1) * (m + 1) + j].max(table[i * (m + 1) + j + 1])
+            };
+        }
+    }
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old[i] == new[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if table[(i + 1) * (m + 1) + j] >= table[i * (m + 1) + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// `align_by_key` 表头按位置对齐，其余行按 `column` 列的值对齐，重复的值按出现顺序配对。
+fn align_by_key(old: &Rows, new: &Rows, column: u32, header_rows: usize) -> Vec<RowPair> {
+    let header = header_rows.min(old.rows.len()).min(new.rows.len());
+    let mut pairs: Vec<RowPair> = (0..header).map(|i| RowPair::Matched(i, i)).collect();
+    // 倒序放入，`pop` 时先取出靠前的行
+    let mut keys: HashMap<String, Vec<usize>> = HashMap::new();
+    for i in (header..old.rows.len()).rev() {
+        let key = old.cell(i, column).value;
+        if !key.is_empty() {
+            keys.entry(key).or_default().push(i);
+        }
+    }
+    let mut matched = vec![false; old.rows.len()];
+    for j in header..new.rows.len() {
+        let key = new.cell(j, column).value;
+        match keys.get_mut(&key).and_then(Vec::pop) {
+            Some(i) => {
+                matched[i] = true;
+                pairs.push(RowPair::Matched(i, j));
+            }
+            _ if new.rows[j].is_empty() => {}
+            _ => pairs.push(RowPair::Inserted(j)),
+        }
+    }
+    for (i, row) in old.rows.iter().enumerate().skip(header) {
+        if !matched[i] && !row.is_empty() {
+            pairs.push(RowPair::Deleted(i));
+        }
+    }
+    pairs
+}
+
+/// `diff_sheets` 对齐两个 sheet 的行并比较对应的单元格。
+fn diff_sheets(
+    old: &Worksheet,
+    new: &Worksheet,
+    options: &DiffOptions,
+) -> Result<SheetDiff, Error> {
+    let old_rows = Rows::new(old);
+    let new_rows = Rows::new(new);
+    let pairs = match &options.key_column {
+        Some(key_column) => {
+            let column = column_index_from_string(key_column)
+                .ok_or_else(|| Error::String(format!("列 {} 格式错误!", key_column)))?;
+            align_by_key(&old_rows, &new_rows, column, options.header_rows as usize)
+        }
+        None => align_by_content(&old_rows.hashes(), &new_rows.hashes()),
+    };
+    let mut diff = SheetDiff {
+        old_name: old.get_name().to_string(),
+        name: new.get_name().to_string(),
+        inserted_rows: Vec::new(),
+        deleted_rows: Vec::new(),
+        changed_cells: Vec::new(),
+    };
+    for pair in pairs {
+        match pair {
+            RowPair::Matched(i, j) => {
+                for column in old_rows.columns(i, &new_rows, j) {
+                    let before = old_rows.cell(i, column);
+                    let after = new_rows.cell(j, column);
+                    if before != after {
+                        diff.changed_cells.push(CellChange {
+                            old_cell: range::coordinate(column, i as u32 + 1),
+                            cell: range::coordinate(column, j as u32 + 1),
+                            old_value: before.value,
+                            new_value: after.value,
+                            old_formula: before.formula,
+                            new_formula: after.formula,
+                        });
+                    }
+                }
+            }
+            RowPair::Deleted(i) => diff.deleted_rows.push(i as u32 + 1),
+            RowPair::Inserted(j) => diff.inserted_rows.push(j as u32 + 1),
+        }
+    }
+    diff.inserted_rows.sort_unstable();
+    diff.deleted_rows.sort_unstable();
+    Ok(diff)
+}
+
+/// `similarity` 两个 sheet 中位置和内容都相同的单元格所占的比例。
+fn similarity(old: &Worksheet, new: &Worksheet) -> f64 {
+    let old = cell_contents(old);
+    let new = cell_contents(new);
+    let total = old.len().max(new.len());
+    if total == 0 {
+        return 1.0;
+    }
+    let common = old
+        .iter()
+        .filter(|(key, content)| new.get(*key) == Some(*content))
+        .count();
+    common as f64 / total as f64
+}
+
+/// `diff_spreadsheets` 比较两个文件，名称相同的 sheet 直接比较，其余按内容相似度识别重命名。
+pub(crate) fn diff_spreadsheets(
+    old: &Spreadsheet,
+    new: &Spreadsheet,
+    options: &DiffOptions,
+) -> Result<WorkbookDiff, Error> {
+    let mut result = WorkbookDiff::default();
+    let mut removed: Vec<&Worksheet> = Vec::new();
+    let mut pairs: Vec<(&Worksheet, &Worksheet)> = Vec::new();
+    for worksheet in old.get_sheet_collection() {
+        match new.get_sheet_by_name(worksheet.get_name()) {
+            Ok(other) => pairs.push((worksheet, other)),
+            Err(_) => removed.push(worksheet),
+        }
+    }
+    let mut added: Vec<&Worksheet> = new
+        .get_sheet_collection()
+        .iter()
+        .filter(|worksheet| old.get_sheet_by_name(worksheet.get_name()).is_err())
+        .collect();
+    for worksheet in removed {
+        let best = added
+            .iter()
+            .enumerate()
+            .map(|(index, other)| (index, similarity(worksheet, other)))
+            .filter(|(_, score)| *score >= RENAME_SIMILARITY)
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+        match best {
+            Some((index, _)) => {
+                let other = added.remove(index);
+                result.renamed_sheets.push(RenamedSheet {
+                    from: worksheet.get_name().to_string(),
+                    to: other.get_name().to_string(),
+                });
+                pairs.push((worksheet, other));
+            }
+            None => result.removed_sheets.push(worksheet.get_name().to_string()),
+        }
+    }
+    result.added_sheets = added
+        .iter()
+        .map(|worksheet| worksheet.get_name().to_string())
+        .collect();
+    for (worksheet, other) in pairs {
+        let diff = diff_sheets(worksheet, other, options)?;
+        if !diff.is_empty() {
+            result.sheets.push(diff);
+        }
+    }
+    Ok(result)
+}
+
+/// `write_highlighted` 在 `spreadsheet` 中标出 `diff` 中修改的单元格和插入的行并保存到 `path`。
+fn write_highlighted(
+    mut spreadsheet: Spreadsheet,
+    diff: &WorkbookDiff,
+    path: &Path,
+) -> Result<(), Error> {
+    highlight(&mut spreadsheet, diff)?;
+    write_atomic(&spreadsheet, path, None)?;
+    info!("保存差异文件 {}", path.display());
+    Ok(())
+}
+
+/// `highlight` 在 `spreadsheet` 中标出修改的单元格和插入的行。
+fn highlight(spreadsheet: &mut Spreadsheet, diff: &WorkbookDiff) -> Result<(), Error> {
+    for sheet in &diff.sheets {
+        let worksheet = spreadsheet
+            .get_sheet_by_name_mut(&sheet.name)
+            .map_err(|error| {
+                Error::String(format!("获取 sheet {} 失败! {}", &sheet.name, error))
+            })?;
+        let width = worksheet.get_highest_column().max(1);
+        for row in &sheet.inserted_rows {
+            for column in 1..=width {
+                worksheet
+                    .get_style_mut(&range::coordinate(column, *row))
+                    .set_background_color(INSERTED_COLOR);
+            }
+        }
+        for change in &sheet.changed_cells {
+            if let Some((column, row)) = range::parse_coordinate(&change.cell) {
+                worksheet
+                    .get_style_mut(&range::coordinate(column, row))
+                    .set_background_color(CHANGED_COLOR);
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Workbook {
+    /// `diff` 比较当前文件和 `other`，返回新增、删除、重命名的 sheet 以及各 sheet 中插入、删除的行和修改的单元格。
+    ///
+    /// 行按 `options.key_column` 列的值或按内容对齐，列按位置对齐。
+    pub fn diff(&self, other: &Workbook, options: &DiffOptions) -> Result<WorkbookDiff, Error> {
+        let diff = diff_spreadsheets(&self.spreadsheet, &other.spreadsheet, options)?;
+        debug!(
+            "对比文件: {} 个 sheet 有差异，新增 {} 个，删除 {} 个",
+            diff.sheets.len(),
+            diff.added_sheets.len(),
+            diff.removed_sheets.len()
+        );
+        Ok(diff)
+    }
+
+    /// `write_diff` 把当前文件的副本保存到 `path`，并标出 `diff` 中修改的单元格和插入的行。
+    pub fn write_diff(&self, diff: &WorkbookDiff, path: &Path) -> Result<(), Error> {
+        write_highlighted(self.spreadsheet.clone(), diff, path)
+    }
+}
+
+/// `diff_workbooks` 比较两个打开的文件。
+///
+/// # Arguments
+///
+/// - `path_a` 原文件
+/// - `path_b` 修改后的文件
+/// - `options` 对齐方式和差异文件路径
+#[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path_a, other = %path_b))]
+pub fn diff_workbooks<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path_a: String,
+    path_b: String,
+    options: Option<DiffOptions>,
+) -> Result<WorkbookDiff, Error> {
+    let options = options.unwrap_or_default();
+    let output_file = match &options.output_path {
+        Some(output_path) => Some(check_path(&app, output_path)?),
+        None => None,
+    };
+    // 只在锁内复制第二个文件，标记和保存在释放锁后进行
+    let (diff, copy) = state.with_workbooks(|workbooks| {
+        let old = workbooks
+            .get(&path_a)
+            .ok_or_else(|| Error::NotFound(path_a.clone()))?;
+        let new = workbooks
+            .get(&path_b)
+            .ok_or_else(|| Error::NotFound(path_b.clone()))?;
+        let diff = old.diff(new, &options)?;
+        let copy = output_file.as_ref().map(|_| new.spreadsheet.clone());
+        Ok((diff, copy))
+    })?;
+    if let (Some(output_file), Some(copy)) = (&output_file, copy) {
+        write_highlighted(copy, &diff, output_file)?;
+    }
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use RowPair::{Deleted, Inserted, Matched};
+
+    type Table<'a> = &'a [&'a [&'a str]];
+
+    fn sheet(rows: Table) -> Worksheet {
+        let mut worksheet = Worksheet::default();
+        for (row, values) in rows.iter().enumerate() {
+            for (column, value) in values.iter().enumerate() {
+                if !value.is_empty() {
+                    worksheet
+                        .get_cell_by_column_and_row_mut(&(column as u32 + 1), &(row as u32 + 1))
+                        .set_value(*value);
+                }
+            }
+        }
+        worksheet
+    }
+
+    #[test]
+    fn lcs_table() {
+        type Case<'a> = (&'a [u64], &'a [u64], &'a [(usize, usize)]);
+        let cases: [Case; 5] = [
+            (&[], &[], &[]),
+            (&[1, 2, 3], &[1, 2, 3], &[(0, 0), (1, 1), (2, 2)]),
+            (&[1, 2, 3], &[4, 5], &[]),
+            (&[1, 2, 3, 4], &[2, 4], &[(1, 0), (3, 1)]),
+            (&[1, 3, 2], &[1, 2, 3], &[(0, 0), (2, 1)]),
+        ];
+        for (old, new, expected) in cases.iter() {
+            assert_eq!(lcs(old, new), *expected, "{:?} {:?}", old, new);
+        }
+    }
+
+    #[test]
+    fn align_by_content_table() {
+        let cases: [(&[u64], &[u64], &[RowPair]); 5] = [
+            (
+                &[1, 2, 3],
+                &[1, 2, 3],
+                &[Matched(0, 0), Matched(1, 1), Matched(2, 2)],
+            ),
+            (
+                &[1, 2, 3],
+                &[1, 9, 2, 3],
+                &[Matched(0, 0), Inserted(1), Matched(1, 2), Matched(2, 3)],
+            ),
+            (
+                &[1, 2, 3],
+                &[1, 3],
+                &[Matched(0, 0), Deleted(1), Matched(2, 1)],
+            ),
+            (
+                &[1, 2, 3],
+                &[1, 5, 3],
+                &[Matched(0, 0), Matched(1, 1), Matched(2, 2)],
+            ),
+            (
+                &[1, 2, 3],
+                &[3, 1, 2],
+                &[Inserted(0), Matched(0, 1), Matched(1, 2), Deleted(2)],
+            ),
+        ];
+        for (old, new, expected) in cases.iter() {
+            assert_eq!(align_by_content(old, new), *expected, "{:?} {:?}", old, new);
+        }
+    }
+
+    #[test]
+    fn align_by_key_table() {
+        let cases: [(Table, Table, usize, &[RowPair]); 2] = [
+            (
+                &[&["id", "name"], &["1", "a"], &["2", "b"], &["3", "c"]],
+                &[
+                    &["id", "name"],
+                    &["3", "c"],
+                    &["1", "a2"],
+                    &["4", "d"],
+                    &[],
+                    &["5", "e"],
+                ],
+                1,
+                &[
+                    Matched(0, 0),
+                    Matched(3, 1),
+                    Matched(1, 2),
+                    Inserted(3),
+                    Inserted(5),
+                    Deleted(2),
+                ],
+            ),
+            (
+                &[&["x"], &["x"], &["y"]],
+                &[&["x"], &["y"], &["x"], &["x"]],
+                0,
+                &[Matched(0, 0), Matched(2, 1), Matched(1, 2), Inserted(3)],
+            ),
+        ];
+        for (old, new, header_rows, expected) in cases.iter() {
+            let old_rows = Rows::new(&sheet(old));
+            let new_rows = Rows::new(&sheet(new));
+            assert_eq!(
+                align_by_key(&old_rows, &new_rows, 1, *header_rows),
+                *expected,
+                "{:?} {:?}",
+                old,
+                new
+            );
+        }
+    }
+
+    #[test]
+    fn diff_sheets_compares_cells_present_in_either_row() {
+        let old = sheet(&[&["1", "a"], &["2", "b"]]);
+        let new = sheet(&[&["1", "", "c"], &["2", "b"], &["3"]]);
+        let diff = diff_sheets(&old, &new, &DiffOptions::default()).unwrap();
+        let cells: Vec<(&str, &str, &str)> = diff
+            .changed_cells
+            .iter()
+            .map(|change| {
+                (
+                    change.cell.as_str(),
+                    change.old_value.as_str(),
+                    change.new_value.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(cells, vec![("B1", "a", ""), ("C1", "", "c")]);
+        assert_eq!(diff.inserted_rows, vec![3]);
+        assert!(diff.deleted_rows.is_empty());
+    }
+}
@@ -0,0 +1,423 @@
+use crate::error::Error;
+use aes::cipher::{block_padding::NoPadding, BlockDecryptMut, KeyIvInit};
+use aes::{Aes128, Aes192, Aes256};
+use hmac::digest::KeyInit;
+use hmac::{Hmac, Mac};
+use regex::Regex;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use tracing::{debug, warn};
+use umya_spreadsheet::{reader::xlsx::read_reader, Spreadsheet};
+
+/// 加密的 xlsx 文件是 OLE 复合文档，以该签名开头。
+const CFB_SIGNATURE: [u8; 8] = [0xd0, 0xcf, 0x11, 0xe0, 0xa1, 0xb1, 0x1a, 0xe1];
+
+/// 加密数据按 4096 字节分段，每段使用不同的 IV。
+const SEGMENT_LENGTH: usize = 4096;
+
+/// 派生密钥时哈希迭代次数的上限，Excel 默认为 100000，避免构造的文件耗尽 CPU。
+const MAX_SPIN_COUNT: usize = 10_000_000;
+
+/// ECMA-376 agile 加密中派生各密钥使用的 block key。
+const VERIFIER_INPUT_BLOCK_KEY: [u8; 8] = [0xfe, 0xa7, 0xd2, 0x76, 0x3b, 0x4b, 0x9e, 0x79];
+const VERIFIER_VALUE_BLOCK_KEY: [u8; 8] = [0xd7, 0xaa, 0x0f, 0x6d, 0x30, 0x61, 0x34, 0x4e];
+const KEY_VALUE_BLOCK_KEY: [u8; 8] = [0x14, 0x6e, 0x0b, 0xe7, 0xab, 0xac, 0xd0, 0xd6];
+const HMAC_KEY_BLOCK_KEY: [u8; 8] = [0x5f, 0xb2, 0xad, 0x01, 0x0c, 0xb9, 0xe1, 0xf6];
+const HMAC_VALUE_BLOCK_KEY: [u8; 8] = [0xa0, 0x67, 0x7f, 0x02, 0xb2, 0x2c, 0x84, 0x33];
+
+/// `read_file` 读取 `path` 的 xlsx 文件，文件加密时使用 `password` 解密。
+///
+/// 文件加密但没有提供密码时返回 [`Error::Encrypted`]，密码错误时返回 [`Error::Password`]。
+pub(crate) fn read_file(path: &Path, password: Option<&str>) -> Result<Spreadsheet, Error> {
+    let data = fs::read(path)?;
+    let package = if data.starts_with(&CFB_SIGNATURE) {
+        let password = password.ok_or_else(|| Error::Encrypted(path.display().to_string()))?;
+        let package = decrypt(&data, password).map_err(|error| match error {
+            Error::Password(_) => Error::Password(path.display().to_string()),
+            error => error,
+        })?;
+        debug!("解密文件 {}", path.display());
+        package
+    } else {
+        data
+    };
+    read_reader(Cursor::new(package), true).map_err(|error| {
+        warn!("读取 {} 文件失败! {:?}", path.display(), error);
+        Error::String(format!("读取 {} 文件失败!", path.display()))
+    })
+}
+
+/// `decrypt` 使用 `password` 解密 agile 加密的复合文档，返回其中的 xlsx 数据。
+fn decrypt(data: &[u8], password: &str) -> Result<Vec<u8>, Error> {
+    let mut compound = cfb::CompoundFile::open(Cursor::new(data))?;
+    let mut info = Vec::new();
+    compound
+        .open_stream("/EncryptionInfo")?
+        .read_to_end(&mut info)?;
+    let mut package = Vec::new();
+    compound
+        .open_stream("/EncryptedPackage")?
+        .read_to_end(&mut package)?;
+
+    // 版本 4.4 为 agile 加密，其余为 standard 或 extensible 加密
+    if info.len() < 8 || info[0..4] != [4, 0, 4, 0] {
+        return Err(Error::String(
+            "不支持的加密方式，只支持 ECMA-376 agile 加密!".to_string(),
+        ));
+    }
+    let xml = String::from_utf8_lossy(&info[8..]);
+    let key_data = CipherParams::parse(&xml, "keyData")?;
+    let encrypted_key = CipherParams::parse(&xml, "encryptedKey")?;
+
+    let key = encrypted_key.secret_key(password)?;
+    verify_integrity(&xml, &key_data, &key, &package)?;
+    decrypt_package(&key_data, &key, &package)
+}
+
+/// `verify_integrity` 使用 `dataIntegrity` 中的 HMAC 校验 `EncryptedPackage` 没有被修改。
+fn verify_integrity(
+    xml: &str,
+    key_data: &CipherParams,
+    key: &[u8],
+    package: &[u8],
+) -> Result<(), Error> {
+    let attributes = match element_attributes(xml, "dataIntegrity")? {
+        Some(attributes) => attributes,
+        None => {
+            warn!("加密信息中没有 dataIntegrity，跳过完整性校验");
+            return Ok(());
+        }
+    };
+    let decrypt_value = |name: &str, block_key: &[u8]| -> Result<Vec<u8>, Error> {
+        let mut value = attributes
+            .get(name)
+            .and_then(|value| base64::decode(value).ok())
+            .ok_or_else(|| Error::String(format!("加密信息中 {} 属性无效!", name)))?;
+        let iv = fit(
+            key_data.hash.digest(&[&key_data.salt, block_key]),
+            key_data.block_size,
+        );
+        aes_cbc_decrypt(key, &iv, &mut value)?;
+        value.truncate(key_data.hash.size());
+        Ok(value)
+    };
+    let hmac_key = decrypt_value("encryptedHmacKey", &HMAC_KEY_BLOCK_KEY)?;
+    let hmac_value = decrypt_value("encryptedHmacValue", &HMAC_VALUE_BLOCK_KEY)?;
+    if !key_data.hash.verify_hmac(&hmac_key, package, &hmac_value) {
+        return Err(Error::String(
+            "文件完整性校验失败，加密数据已被修改!".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// `element_attributes` 获取 xml 中第一个 `element` 元素的属性，没有该元素时返回 `None`。
+fn element_attributes(xml: &str, element: &str) -> Result<Option<HashMap<String, String>>, Error> {
+    let pattern = format!(r"<(?:\w+:)?{}\b([^>]*)>", element);
+    let element_regex = Regex::new(&pattern).map_err(|error| Error::String(error.to_string()))?;
+    let attribute_regex =
+        Regex::new(r#"(\w+)="([^"]*)""#).map_err(|error| Error::String(error.to_string()))?;
+    Ok(element_regex.captures(xml).map(|captures| {
+        attribute_regex
+            .captures_iter(&captures[1])
+            .map(|attribute| (attribute[1].to_string(), attribute[2].to_string()))
+            .collect()
+    }))
+}
+
+/// 加密参数，对应 `EncryptionInfo` 中 `keyData` 和 `encryptedKey` 元素的属性。
+struct CipherParams {
+    attributes: HashMap<String, String>,
+    salt: Vec<u8>,
+    block_size: usize,
+    key_bytes: usize,
+    hash: HashAlgorithm,
+}
+
+impl CipherParams {
+    fn parse(xml: &str, element: &str) -> Result<Self, Error> {
+        let attributes = element_attributes(xml, element)?
+            .ok_or_else(|| Error::String(format!("加密信息中缺少 {}!", element)))?;
+        let mut params = CipherParams {
+            attributes,
+            salt: Vec::new(),
+            block_size: 0,
+            key_bytes: 0,
+            hash: HashAlgorithm::Sha512,
+        };
+        if params.attribute("cipherAlgorithm")? != "AES"
+            || params.attribute("cipherChaining")? != "ChainingModeCBC"
+        {
+            return Err(Error::String(
+                "不支持的加密算法，只支持 AES-CBC!".to_string(),
+            ));
+        }
+        params.salt = params.base64("saltValue")?;
+        params.block_size = params.number("blockSize")?;
+        params.key_bytes = params.number("keyBits")? / 8;
+        params.hash = HashAlgorithm::parse(params.attribute("hashAlgorithm")?)?;
+        Ok(params)
+    }
+
+    fn attribute(&self, name: &str) -> Result<&str, Error> {
+        self.attributes
+            .get(name)
+            .map(String::as_str)
+            .ok_or_else(|| Error::String(format!("加密信息中缺少 {} 属性!", name)))
+    }
+
+    fn number(&self, name: &str) -> Result<usize, Error> {
+        self.attribute(name)?
+            .parse()
+            .map_err(|_| Error::String(format!("加密信息中 {} 属性无效!", name)))
+    }
+
+    fn base64(&self, name: &str) -> Result<Vec<u8>, Error> {
+        base64::decode(self.attribute(name)?)
+            .map_err(|_| Error::String(format!("加密信息中 {} 属性无效!", name)))
+    }
+
+    /// `secret_key` 由密码派生密钥，校验密码后解密出文件的密钥。
+    fn secret_key(&self, password: &str) -> Result<Vec<u8>, Error> {
+        let password: Vec<u8> = password
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect();
+        let spin_count = self.number("spinCount")?;
+        if spin_count > MAX_SPIN_COUNT {
+            return Err(Error::String(format!(
+                "加密信息中 spinCount 属性超过 {}!",
+                MAX_SPIN_COUNT
+            )));
+        }
+        let mut hash = self.hash.digest(&[&self.salt, &password]);
+        for iteration in 0..spin_count as u32 {
+            hash = self.hash.digest(&[&iteration.to_le_bytes(), &hash]);
+        }
+        let derive = |block_key: &[u8]| fit(self.hash.digest(&[&hash, block_key]), self.key_bytes);
+
+        let mut verifier_input = self.base64("encryptedVerifierHashInput")?;
+        aes_cbc_decrypt(
+            &derive(&VERIFIER_INPUT_BLOCK_KEY),
+            &self.salt,
+            &mut verifier_input,
+        )?;
+        let mut verifier_value = self.base64("encryptedVerifierHashValue")?;
+        aes_cbc_decrypt(
+            &derive(&VERIFIER_VALUE_BLOCK_KEY),
+            &self.salt,
+            &mut verifier_value,
+        )?;
+        let salt_size = self.number("saltSize")?.min(verifier_input.len());
+        let expected = self.hash.digest(&[&verifier_input[..salt_size]]);
+        if verifier_value.len() < expected.len() || verifier_value[..expected.len()] != expected {
+            return Err(Error::Password(String::new()));
+        }
+
+        let mut key = self.base64("encryptedKeyValue")?;
+        aes_cbc_decrypt(&derive(&KEY_VALUE_BLOCK_KEY), &self.salt, &mut key)?;
+        key.truncate(self.key_bytes);
+        Ok(key)
+    }
+}
+
+/// `decrypt_package` 分段解密 `EncryptedPackage`，前 8 个字节为解密后的长度。
+fn decrypt_package(key_data: &CipherParams, key: &[u8], package: &[u8]) -> Result<Vec<u8>, Error> {
+    if package.len() < 8 {
+        return Err(Error::String("加密数据无效!".to_string()));
+    }
+    let mut size = [0; 8];
+    size.copy_from_slice(&package[..8]);
+    let size = u64::from_le_bytes(size) as usize;
+    let mut output = Vec::with_capacity(package.len());
+    for (index, segment) in package[8..].chunks(SEGMENT_LENGTH).enumerate() {
+        let iv = fit(
+            key_data
+                .hash
+                .digest(&[&key_data.salt, &(index as u32).to_le_bytes()]),
+            key_data.block_size,
+        );
+        let mut segment = segment.to_vec();
+        aes_cbc_decrypt(key, &iv, &mut segment)?;
+        output.extend_from_slice(&segment);
+    }
+    if output.len() < size {
+        return Err(Error::String("加密数据长度无效!".to_string()));
+    }
+    output.truncate(size);
+    Ok(output)
+}
+
+#[derive(Clone, Copy)]
+enum HashAlgorithm {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    fn parse(name: &str) -> Result<Self, Error> {
+        match name {
+            "SHA256" => Ok(HashAlgorithm::Sha256),
+            "SHA384" => Ok(HashAlgorithm::Sha384),
+            "SHA512" => Ok(HashAlgorithm::Sha512),
+            _ => Err(Error::String(format!("不支持的哈希算法 {}!", name))),
+        }
+    }
+
+    /// `size` 哈希值的字节数。
+    fn size(self) -> usize {
+        match self {
+            HashAlgorithm::Sha256 => 32,
+            HashAlgorithm::Sha384 => 48,
+            HashAlgorithm::Sha512 => 64,
+        }
+    }
+
+    /// `verify_hmac` 以常量时间比较 `data` 的 HMAC 和 `expected`。
+    fn verify_hmac(self, key: &[u8], data: &[u8], expected: &[u8]) -> bool {
+        fn verify<M: Mac + KeyInit>(key: &[u8], data: &[u8], expected: &[u8]) -> bool {
+            match <M as KeyInit>::new_from_slice(key) {
+                Ok(mut mac) => {
+                    mac.update(data);
+                    mac.verify_slice(expected).is_ok()
+                }
+                Err(_) => false,
+            }
+        }
+        match self {
+            HashAlgorithm::Sha256 => verify::<Hmac<Sha256>>(key, data, expected),
+            HashAlgorithm::Sha384 => verify::<Hmac<Sha384>>(key, data, expected),
+            HashAlgorithm::Sha512 => verify::<Hmac<Sha512>>(key, data, expected),
+        }
+    }
+
+    fn digest(self, parts: &[&[u8]]) -> Vec<u8> {
+        fn digest<D: Digest>(parts: &[&[u8]]) -> Vec<u8> {
+            let mut hasher = D::new();
+            for part in parts {
+                hasher.update(part);
+            }
+            hasher.finalize().to_vec()
+        }
+        match self {
+            HashAlgorithm::Sha256 => digest::<Sha256>(parts),
+            HashAlgorithm::Sha384 => digest::<Sha384>(parts),
+            HashAlgorithm::Sha512 => digest::<Sha512>(parts),
+        }
+    }
+}
+
+/// `fit` 截断或用 0x36 填充到 `len` 个字节。
+fn fit(mut bytes: Vec<u8>, len: usize) -> Vec<u8> {
+    bytes.resize(len, 0x36);
+    bytes
+}
+
+fn aes_cbc_decrypt(key: &[u8], iv: &[u8], data: &mut [u8]) -> Result<(), Error> {
+    let invalid = || Error::String("解密失败，加密数据无效!".to_string());
+    let iv = &iv[..iv.len().min(16)];
+    match key.len() {
+        16 => cbc::Decryptor::<Aes128>::new_from_slices(key, iv)
+            .map_err(|_| invalid())?
+            .decrypt_padded_mut::<NoPadding>(data)
+            .map(|_| ())
+            .map_err(|_| invalid()),
+        24 => cbc::Decryptor::<Aes192>::new_from_slices(key, iv)
+            .map_err(|_| invalid())?
+            .decrypt_padded_mut::<NoPadding>(data)
+            .map(|_| ())
+            .map_err(|_| invalid()),
+        32 => cbc::Decryptor::<Aes256>::new_from_slices(key, iv)
+            .map_err(|_| invalid())?
+            .decrypt_padded_mut::<NoPadding>(data)
+            .map(|_| ())
+            .map_err(|_| invalid()),
+        len => Err(Error::String(format!("不支持的密钥长度 {} 位!", len * 8))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use umya_spreadsheet::writer::xlsx::write_with_password;
+
+    fn encrypted_file(dir: &Path) -> std::path::PathBuf {
+        let mut spreadsheet = umya_spreadsheet::new_file();
+        spreadsheet
+            .get_sheet_by_name_mut("Sheet1")
+            .unwrap()
+            .get_cell_mut("A1")
+            .set_value("secret value");
+        let path = dir.join("encrypted.xlsx");
+        write_with_password(&spreadsheet, &path, "secret").unwrap();
+        path
+    }
+
+    #[test]
+    fn read_encrypted_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = encrypted_file(dir.path());
+
+        let spreadsheet = read_file(&path, Some("secret")).unwrap();
+        assert_eq!(
+            spreadsheet
+                .get_sheet_by_name("Sheet1")
+                .unwrap()
+                .get_value("A1"),
+            "secret value"
+        );
+        assert!(matches!(
+            read_file(&path, Some("wrong")),
+            Err(Error::Password(_))
+        ));
+        assert!(matches!(read_file(&path, None), Err(Error::Encrypted(_))));
+    }
+
+    #[test]
+    fn modified_package_fails_integrity_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = encrypted_file(dir.path());
+        let data = fs::read(&path).unwrap();
+        let mut compound = cfb::CompoundFile::open(Cursor::new(&data)).unwrap();
+        let mut info = Vec::new();
+        compound
+            .open_stream("/EncryptionInfo")
+            .unwrap()
+            .read_to_end(&mut info)
+            .unwrap();
+        let mut package = Vec::new();
+        compound
+            .open_stream("/EncryptedPackage")
+            .unwrap()
+            .read_to_end(&mut package)
+            .unwrap();
+        let last = package.len() - 1;
+        package[last] ^= 1;
+
+        let mut tampered = cfb::CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        for (name, stream) in [("/EncryptionInfo", &info), ("/EncryptedPackage", &package)] {
+            tampered
+                .create_stream(name)
+                .unwrap()
+                .write_all(stream)
+                .unwrap();
+        }
+        let tampered = tampered.into_inner().into_inner();
+        assert!(decrypt(&data, "secret").is_ok());
+        let error = decrypt(&tampered, "secret").err().unwrap();
+        assert!(error.to_string().contains("完整性"), "{}", error);
+    }
+
+    #[test]
+    fn spin_count_is_limited() {
+        let xml = r#"<keyEncryptor><p:encryptedKey spinCount="4294967296" saltSize="16" blockSize="16" keyBits="256" hashSize="64" cipherAlgorithm="AES" cipherChaining="ChainingModeCBC" hashAlgorithm="SHA512" saltValue="AAAAAAAAAAAAAAAAAAAAAA=="/></keyEncryptor>"#;
+        let params = CipherParams::parse(xml, "encryptedKey").unwrap();
+        let error = params.secret_key("secret").err().unwrap();
+        assert!(error.to_string().contains("spinCount"), "{}", error);
+    }
+}
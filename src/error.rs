@@ -14,6 +14,24 @@ pub enum Error {
     NotFound(String),
     #[error("{0}")]
     String(String),
+    /// The path is outside the allowed scope.
+    #[error("path \"{0}\" is not allowed")]
+    Forbidden(String),
+    /// The file was modified or deleted on disk by another program since it was read.
+    #[error("file \"{0}\" was modified on disk")]
+    Modified(String),
+    /// The file is encrypted and no password was given.
+    #[error("file \"{0}\" is encrypted, a password is required")]
+    Encrypted(String),
+    /// The password of an encrypted file is wrong.
+    #[error("wrong password for file \"{0}\"")]
+    Password(String),
+    /// An operation of a batch failed and the whole batch was rolled back.
+    #[error("operation {index} failed, all operations were rolled back: {source}")]
+    Batch { index: usize, source: Box<Error> },
+    /// A cell could not be converted to or from a record field.
+    #[error("{cell}: {message}")]
+    Cell { cell: String, message: String },
 }
 
 impl Serialize for Error {
@@ -0,0 +1,104 @@
+use crate::range::string_from_column_index;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Runtime};
+use tracing::warn;
+
+/// 文件内容修改事件。
+pub const CHANGED_EVENT: &str = "spreadsheet://changed";
+/// 文件打开或新建事件。
+pub const OPENED_EVENT: &str = "spreadsheet://opened";
+/// 文件关闭事件。
+pub const CLOSED_EVENT: &str = "spreadsheet://closed";
+/// 文件保存事件。
+pub const SAVED_EVENT: &str = "spreadsheet://saved";
+/// 文件被其他程序修改事件。
+pub const MODIFIED_EVENT: &str = "spreadsheet://modified";
+
+/// 修改类型。
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+    /// 单元格的值或公式。
+    Values,
+    InsertRows,
+    RemoveRows,
+    InsertColumns,
+    RemoveColumns,
+    Sort,
+    AddSheet,
+    Chart,
+    DefinedName,
+    Filter,
+    /// 撤销修改，`sheet` 为空时恢复了整个文件。
+    Undo,
+    Redo,
+    /// 批量操作，`sheet` 为空时涉及多个 sheet 或整个文件。
+    Batch,
+    /// 重新读取文件。
+    Reload,
+    /// 三方合并，`sheet` 为空。
+    Merge,
+}
+
+/// `spreadsheet://changed` 事件内容。
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeEvent {
+    /// 文件路径。
+    pub workbook: String,
+    /// sheet 名称，修改涉及多个 sheet 或整个文件时为空。
+    pub sheet: Option<String>,
+    /// 修改的区域，如 `"B3"`、`"5:7"`、`"B:D"`，无法确定时为空。
+    pub range: Option<String>,
+    pub kind: ChangeKind,
+}
+
+/// 文件生命周期事件内容。
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkbookEvent {
+    pub workbook: String,
+}
+
+/// `emit_changed` 向所有窗口发送 `spreadsheet://changed` 事件。
+pub(crate) fn emit_changed<R: Runtime>(
+    app: &AppHandle<R>,
+    workbook: &str,
+    sheet: Option<&str>,
+    range: Option<String>,
+    kind: ChangeKind,
+) {
+    let payload = ChangeEvent {
+        workbook: workbook.to_string(),
+        sheet: sheet.map(|sheet| sheet.to_string()),
+        range,
+        kind,
+    };
+    if let Err(error) = app.emit_all(CHANGED_EVENT, payload) {
+        warn!("发送 {} 事件失败! {}", CHANGED_EVENT, error);
+    }
+}
+
+/// `emit_workbook` 向所有窗口发送文件生命周期事件。
+pub(crate) fn emit_workbook<R: Runtime>(app: &AppHandle<R>, event: &str, workbook: &str) {
+    let payload = WorkbookEvent {
+        workbook: workbook.to_string(),
+    };
+    if let Err(error) = app.emit_all(event, payload) {
+        warn!("发送 {} 事件失败! {}", event, error);
+    }
+}
+
+/// `row_span` 生成整行区域，如 `(5, 3)` -> `"5:7"`。
+pub(crate) fn row_span(start: u32, count: u32) -> String {
+    format!("{}:{}", start, start + count.max(1) - 1)
+}
+
+/// `column_span` 生成整列区域，如 `(2, 3)` -> `"B:D"`。
+pub(crate) fn column_span(start: u32, count: u32) -> String {
+    format!(
+        "{}:{}",
+        string_from_column_index(start),
+        string_from_column_index(start + count.max(1) - 1)
+    )
+}
@@ -0,0 +1,579 @@
+use crate::error::Error;
+use crate::event::{emit_changed, ChangeKind};
+use crate::history::EditScope;
+use crate::range::{self, CellRange};
+use crate::workbook::{Workbook, WorkbookRegistry};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use tauri::{command, AppHandle, Runtime, State, Window};
+use tracing::{debug, instrument};
+use umya_spreadsheet::Worksheet;
+
+/// 自定义筛选的比较方式。
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FilterOperator {
+    Equal,
+    NotEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    LessThan,
+    LessThanOrEqual,
+}
+
+/// 自定义筛选条件，`Equal`/`NotEqual` 支持 `*` 和 `?` 通配符。
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomCondition {
+    pub operator: FilterOperator,
+    pub value: String,
+}
+
+/// 单列筛选条件。
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum FilterCriteria {
+    /// 值列表，`blank` 为 `true` 时同时保留空白单元格。
+    #[serde(rename_all = "camelCase")]
+    Values {
+        values: Vec<String>,
+        #[serde(default)]
+        blank: bool,
+    },
+    /// 自定义比较，`and` 为 `true` 时需满足全部条件，否则满足任一条件即可。
+    #[serde(rename_all = "camelCase")]
+    Custom {
+        conditions: Vec<CustomCondition>,
+        #[serde(default)]
+        and: bool,
+    },
+    /// 前 N 项 (或前 N%)，`top` 为 `false` 时取最小的 N 项。
+    #[serde(rename_all = "camelCase")]
+    Top10 {
+        #[serde(default = "default_top")]
+        top: bool,
+        #[serde(default)]
+        percent: bool,
+        value: f64,
+    },
+}
+
+fn default_top() -> bool {
+    true
+}
+
+/// 指定列的筛选条件，`column` 为列索引，从 1 开始。
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterColumn {
+    pub column: u32,
+    pub criteria: FilterCriteria,
+}
+
+/// 筛选结果。
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterResult {
+    /// 匹配的行索引。
+    pub rows: Vec<u32>,
+    /// 匹配行在区域内的值。
+    pub data: Vec<Vec<String>>,
+}
+
+/// `wildcard_match` 按 Excel 规则匹配 `*` 和 `?` 通配符，忽略大小写。
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let value: Vec<char> = value.to_lowercase().chars().collect();
+    let (mut p, mut v) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while v < value.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == value[v]) {
+            p += 1;
+            v += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, v));
+            p += 1;
+        } else if let Some((star_p, star_v)) = star {
+            p = star_p + 1;
+            v = star_v + 1;
+            star = Some((star_p, star_v + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// `compare_values` 两边都是数字时按数值比较，否则按忽略大小写的文本比较。
+fn compare_values(left: &str, right: &str) -> Ordering {
+    match (left.trim().parse::<f64>(), right.trim().parse::<f64>()) {
+        (Ok(left), Ok(right)) => left.partial_cmp(&right).unwrap_or(Ordering::Equal),
+        _ => left.to_lowercase().cmp(&right.to_lowercase()),
+    }
+}
+
+impl CustomCondition {
+    fn matches(&self, value: &str) -> bool {
+        match self.operator {
+            FilterOperator::Equal => wildcard_match(&self.value, value),
+            FilterOperator::NotEqual => !wildcard_match(&self.value, value),
+            FilterOperator::GreaterThan => compare_values(value, &self.value) == Ordering::Greater,
+            FilterOperator::GreaterThanOrEqual => {
+                compare_values(value, &self.value) != Ordering::Less
+            }
+            FilterOperator::LessThan => compare_values(value, &self.value) == Ordering::Less,
+            FilterOperator::LessThanOrEqual => {
+                compare_values(value, &self.value) != Ordering::Greater
+            }
+        }
+    }
+}
+
+/// `column_predicate` 根据列的全部值生成匹配函数，前 N 项条件需要先计算阈值。
+fn column_predicate(criteria: &FilterCriteria, values: &[String]) -> Box<dyn Fn(&str) -> bool> {
+    match criteria.clone() {
+        FilterCriteria::Values { values, blank } => Box::new(move |value: &str| {
+            if value.trim().is_empty() {
+                return blank;
+            }
+            values.iter().any(|v| v.eq_ignore_ascii_case(value))
+        }),
+        FilterCriteria::Custom { conditions, and } => Box::new(move |value: &str| {
+            if and {
+                conditions.iter().all(|condition| condition.matches(value))
+            } else {
+                conditions.iter().any(|condition| condition.matches(value))
+            }
+        }),
+        FilterCriteria::Top10 {
+            top,
+            percent,
+            value,
+        } => {
+            let mut numbers: Vec<f64> = values
+                .iter()
+                .filter_map(|v| v.trim().parse::<f64>().ok())
+                .collect();
+            numbers.sort_by(|a, b| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+            if !top {
+                numbers.reverse();
+            }
+            let count = if percent {
+                (numbers.len() as f64 * value / 100.0).ceil() as usize
+            } else {
+                value as usize
+            };
+            let threshold = numbers
+                .get(count.min(numbers.len()).saturating_sub(1))
+                .copied();
+            Box::new(
+                move |value: &str| match (value.trim().parse::<f64>(), threshold) {
+                    (Ok(number), Some(threshold)) if count > 0 => {
+                        if top {
+                            number >= threshold
+                        } else {
+                            number <= threshold
+                        }
+                    }
+                    _ => false,
+                },
+            )
+        }
+    }
+}
+
+/// `matching_rows` 返回区域中满足全部筛选条件的数据行，区域首行为表头。
+fn matching_rows(
+    worksheet: &Worksheet,
+    range: &CellRange,
+    columns: &[FilterColumn],
+) -> Result<Vec<u32>, Error> {
+    let rows: Vec<u32> = (range.start.1 + 1..=range.end.1).collect();
+    let mut predicates = Vec::new();
+    for filter in columns {
+        if !range.columns().contains(&filter.column) {
+            return Err(Error::String(format!(
+                "筛选列 {} 不在区域 {} 内!",
+                filter.column, range
+            )));
+        }
+        let values: Vec<String> = rows
+            .iter()
+            .map(|row| worksheet.get_value_by_column_and_row(&filter.column, row))
+            .collect();
+        predicates.push((filter.column, column_predicate(&filter.criteria, &values)));
+    }
+    Ok(rows
+        .into_iter()
+        .filter(|row| {
+            predicates.iter().all(|(column, predicate)| {
+                predicate(&worksheet.get_value_by_column_and_row(column, row))
+            })
+        })
+        .collect())
+}
+
+/// `auto_filter_range` 获取 sheet 已设置的筛选区域。
+fn auto_filter_range(worksheet: &Worksheet) -> Result<CellRange, Error> {
+    match worksheet.get_auto_filter() {
+        Some(auto_filter) => range::resolve(worksheet, &auto_filter.get_range().get_range()),
+        None => Err(Error::String(format!(
+            "sheet {} 未设置筛选!",
+            worksheet.get_name()
+        ))),
+    }
+}
+
+/// `apply_hidden_rows` 按筛选条件隐藏不匹配的数据行。
+fn apply_hidden_rows(
+    worksheet: &mut Worksheet,
+    range: &CellRange,
+    columns: &[FilterColumn],
+) -> Result<usize, Error> {
+    let visible = matching_rows(worksheet, range, columns)?;
+    for row in range.start.1 + 1..=range.end.1 {
+        let hidden = visible.binary_search(&row).is_err();
+        worksheet.get_row_dimension_mut(&row).set_hidden(hidden);
+    }
+    Ok(visible.len())
+}
+
+impl Workbook {
+    /// `set_auto_filter` 为 `range` 区域设置筛选按钮，区域首行为表头。
+    pub fn set_auto_filter(&mut self, sheet_name: &str, range: &str) -> Result<(), Error> {
+        let range = self.resolve_range(sheet_name, range)?;
+        // 直接借用 spreadsheet 字段，才能同时修改 auto_filters。
+        let worksheet = match self.spreadsheet.get_sheet_by_name_mut(sheet_name) {
+            Ok(worksheet) => worksheet,
+            Err(error) => {
+                return Err(Error::String(format!(
+                    "获取 sheet {} 失败! {}",
+                    sheet_name, error
+                )))
+            }
+        };
+        worksheet.set_auto_filter(range.to_string());
+        if let Some(columns) = self.auto_filters.remove(sheet_name) {
+            let columns: Vec<FilterColumn> = columns
+                .into_iter()
+                .filter(|filter| range.columns().contains(&filter.column))
+                .collect();
+            apply_hidden_rows(worksheet, &range, &columns)?;
+            self.auto_filters.insert(sheet_name.to_string(), columns);
+        }
+        debug!("设置 sheet {} 筛选区域 {}", sheet_name, range);
+        Ok(())
+    }
+
+    /// `clear_auto_filter` 清除 sheet 的筛选按钮和筛选条件，并显示所有被筛选隐藏的行。
+    pub fn clear_auto_filter(&mut self, sheet_name: &str) -> Result<(), Error> {
+        let worksheet = match self.spreadsheet.get_sheet_by_name_mut(sheet_name) {
+            Ok(worksheet) => worksheet,
+            Err(error) => {
+                return Err(Error::String(format!(
+                    "获取 sheet {} 失败! {}",
+                    sheet_name, error
+                )))
+            }
+        };
+        if let Ok(range) = auto_filter_range(worksheet) {
+            apply_hidden_rows(worksheet, &range, &[])?;
+        }
+        worksheet.remove_auto_filter();
+        self.auto_filters.remove(sheet_name);
+        debug!("清除 sheet {} 筛选", sheet_name);
+        Ok(())
+    }
+
+    /// `set_filter_column` 设置筛选区域中指定列的筛选条件，`criteria` 为空时清除该列条件。
+    ///
+    /// 不匹配的数据行会被隐藏，返回可见的数据行数。
+    ///
+    /// umya 0.8 的筛选只保存区域，筛选条件只在文件打开期间有效，不会写入文件，
+    /// 保存后重新打开时只保留筛选按钮和已隐藏的行。
+    pub fn set_filter_column(
+        &mut self,
+        sheet_name: &str,
+        column: u32,
+        criteria: Option<FilterCriteria>,
+    ) -> Result<usize, Error> {
+        let worksheet = match self.spreadsheet.get_sheet_by_name_mut(sheet_name) {
+            Ok(worksheet) => worksheet,
+            Err(error) => {
+                return Err(Error::String(format!(
+                    "获取 sheet {} 失败! {}",
+                    sheet_name, error
+                )))
+            }
+        };
+        let range = auto_filter_range(worksheet)?;
+        let mut columns = self
+            .auto_filters
+            .get(sheet_name)
+            .cloned()
+            .unwrap_or_default();
+        columns.retain(|filter| filter.column != column);
+        if let Some(criteria) = criteria {
+            columns.push(FilterColumn { column, criteria });
+        }
+        let visible = apply_hidden_rows(worksheet, &range, &columns)?;
+        self.auto_filters.insert(sheet_name.to_string(), columns);
+        debug!(
+            "设置 sheet {} 第 {} 列筛选条件，可见 {} 行",
+            sheet_name, column, visible
+        );
+        Ok(visible)
+    }
+
+    /// `apply_filter` 返回 `range` 区域中满足筛选条件的数据行，不修改 sheet。
+    ///
+    /// 区域首行视为表头，不参与筛选。
+    pub fn apply_filter(
+        &self,
+        sheet_name: &str,
+        range: &str,
+        columns: &[FilterColumn],
+    ) -> Result<FilterResult, Error> {
+        let range = self.resolve_range(sheet_name, range)?;
+        let worksheet = self.worksheet(sheet_name)?;
+        let rows = matching_rows(worksheet, &range, columns)?;
+        let data = rows
+            .iter()
+            .map(|row| {
+                range
+                    .columns()
+                    .map(|column| worksheet.get_value_by_column_and_row(&column, row))
+                    .collect()
+            })
+            .collect();
+        debug!("区域 {} 筛选结果 {} 行", range, rows.len());
+        Ok(FilterResult { rows, data })
+    }
+}
+
+/// `set_auto_filter` 为 `range` 区域设置筛选按钮，区域首行为表头。
+#[command]
+#[instrument(
+    level = "debug",
+    skip_all,
+    fields(workbook = %path, sheet = %sheet_name, range = %range)
+)]
+pub fn set_auto_filter<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+    sheet_name: String,
+    range: String,
+) -> Result<(), Error> {
+    state.edit(
+        &path,
+        EditScope::Sheet(&sheet_name),
+        "set_auto_filter",
+        |workbook| workbook.set_auto_filter(&sheet_name, &range),
+    )?;
+    emit_changed(
+        &app,
+        &path,
+        Some(&sheet_name),
+        Some(range),
+        ChangeKind::Filter,
+    );
+    Ok(())
+}
+
+/// `clear_auto_filter` 清除 sheet 的筛选按钮和筛选条件，并显示所有被筛选隐藏的行。
+#[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, sheet = %sheet_name))]
+pub fn clear_auto_filter<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+    sheet_name: String,
+) -> Result<(), Error> {
+    state.edit(
+        &path,
+        EditScope::Sheet(&sheet_name),
+        "clear_auto_filter",
+        |workbook| workbook.clear_auto_filter(&sheet_name),
+    )?;
+    emit_changed(&app, &path, Some(&sheet_name), None, ChangeKind::Filter);
+    Ok(())
+}
+
+/// `set_filter_column` 设置筛选区域中指定列的筛选条件，`criteria` 为空时清除该列条件。
+///
+/// 不匹配的数据行会被隐藏，返回可见的数据行数。筛选条件不会写入文件，
+/// 保存后重新打开时只保留筛选按钮和已隐藏的行。
+#[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, sheet = %sheet_name))]
+pub fn set_filter_column<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+    sheet_name: String,
+    column: u32,
+    criteria: Option<FilterCriteria>,
+) -> Result<usize, Error> {
+    let result = state.edit(
+        &path,
+        EditScope::Sheet(&sheet_name),
+        "set_filter_column",
+        |workbook| workbook.set_filter_column(&sheet_name, column, criteria),
+    )?;
+    emit_changed(&app, &path, Some(&sheet_name), None, ChangeKind::Filter);
+    Ok(result)
+}
+
+/// `apply_filter` 返回 `range` 区域中满足筛选条件的数据行，不修改 sheet。
+#[command]
+#[instrument(
+    level = "debug",
+    skip_all,
+    fields(workbook = %path, sheet = %sheet_name, range = %range)
+)]
+pub fn apply_filter<R: Runtime>(
+    _app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+    sheet_name: String,
+    range: String,
+    columns: Vec<FilterColumn>,
+) -> Result<FilterResult, Error> {
+    state.with_workbook(&path, |workbook| {
+        workbook.apply_filter(&sheet_name, &range, &columns)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workbook() -> Workbook {
+        let mut workbook = Workbook::new(umya_spreadsheet::new_file());
+        let rows = [
+            ["Name", "Status", "Amount"],
+            ["Apple", "Open", "10"],
+            ["banana", "Closed", "25"],
+            ["Cherry", "open", "5"],
+            ["", "Open", "40"],
+            ["Avocado", "Pending", "15"],
+        ];
+        let worksheet = workbook.worksheet_mut("Sheet1").unwrap();
+        for (row, values) in rows.iter().enumerate() {
+            for (column, value) in values.iter().enumerate() {
+                worksheet
+                    .get_cell_by_column_and_row_mut(&(column as u32 + 1), &(row as u32 + 1))
+                    .set_value(*value);
+            }
+        }
+        workbook
+    }
+
+    fn hidden_rows(workbook: &Workbook) -> Vec<u32> {
+        let worksheet = workbook.worksheet("Sheet1").unwrap();
+        (2..=6)
+            .filter(|row| {
+                worksheet
+                    .get_row_dimension(row)
+                    .map_or(false, |dimension| *dimension.get_hidden())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn apply_filter_table() {
+        let workbook = workbook();
+        let cases = [
+            (
+                serde_json::json!([{ "column": 2, "criteria": { "type": "values", "values": ["open"] } }]),
+                vec![2, 4, 5],
+            ),
+            (
+                serde_json::json!([{ "column": 1, "criteria": { "type": "values", "values": ["Apple"], "blank": true } }]),
+                vec![2, 5],
+            ),
+            (
+                serde_json::json!([{ "column": 1, "criteria": { "type": "custom", "conditions": [
+                    { "operator": "equal", "value": "a*" },
+                ] } }]),
+                vec![2, 6],
+            ),
+            (
+                serde_json::json!([{ "column": 3, "criteria": { "type": "custom", "and": true, "conditions": [
+                    { "operator": "greaterThanOrEqual", "value": "10" },
+                    { "operator": "lessThanOrEqual", "value": "25" },
+                ] } }]),
+                vec![2, 3, 6],
+            ),
+            (
+                serde_json::json!([{ "column": 3, "criteria": { "type": "custom", "conditions": [
+                    { "operator": "lessThan", "value": "10" },
+                    { "operator": "greaterThan", "value": "30" },
+                ] } }]),
+                vec![4, 5],
+            ),
+            (
+                serde_json::json!([{ "column": 3, "criteria": { "type": "top10", "value": 2 } }]),
+                vec![3, 5],
+            ),
+            (
+                serde_json::json!([{ "column": 3, "criteria": { "type": "top10", "top": false, "percent": true, "value": 40 } }]),
+                vec![2, 4],
+            ),
+            (
+                serde_json::json!([
+                    { "column": 2, "criteria": { "type": "values", "values": ["open"] } },
+                    { "column": 3, "criteria": { "type": "custom", "conditions": [
+                        { "operator": "greaterThanOrEqual", "value": "10" },
+                    ] } },
+                ]),
+                vec![2, 5],
+            ),
+        ];
+        for (columns, expected) in cases.iter() {
+            let columns: Vec<FilterColumn> = serde_json::from_value(columns.clone()).unwrap();
+            let result = workbook.apply_filter("Sheet1", "A1:C6", &columns).unwrap();
+            assert_eq!(result.rows, *expected, "{:?}", columns);
+        }
+
+        let columns: Vec<FilterColumn> = serde_json::from_value(serde_json::json!([
+            { "column": 1, "criteria": { "type": "custom", "conditions": [{ "operator": "equal", "value": "a?ple" }] } },
+        ]))
+        .unwrap();
+        let result = workbook.apply_filter("Sheet1", "A1:C6", &columns).unwrap();
+        assert_eq!(result.data, vec![vec!["Apple", "Open", "10"]]);
+        let outside: Vec<FilterColumn> = serde_json::from_value(serde_json::json!([
+            { "column": 4, "criteria": { "type": "values", "values": [] } },
+        ]))
+        .unwrap();
+        assert!(workbook.apply_filter("Sheet1", "A1:C6", &outside).is_err());
+    }
+
+    #[test]
+    fn clear_auto_filter_shows_hidden_rows() {
+        let mut workbook = workbook();
+        workbook.set_auto_filter("Sheet1", "A1:C6").unwrap();
+        let criteria = FilterCriteria::Values {
+            values: vec![String::from("Open")],
+            blank: false,
+        };
+        let visible = workbook
+            .set_filter_column("Sheet1", 2, Some(criteria))
+            .unwrap();
+        assert_eq!(visible, 3);
+        assert_eq!(hidden_rows(&workbook), vec![3, 6]);
+
+        workbook.clear_auto_filter("Sheet1").unwrap();
+        assert!(hidden_rows(&workbook).is_empty());
+        assert!(workbook
+            .worksheet("Sheet1")
+            .unwrap()
+            .get_auto_filter()
+            .is_none());
+        assert!(workbook.set_filter_column("Sheet1", 2, None).is_err());
+    }
+}
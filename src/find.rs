@@ -0,0 +1,408 @@
+use crate::defined_name::lookup_range;
+use crate::error::Error;
+use crate::event::{emit_changed, ChangeKind};
+use crate::history::EditScope;
+use crate::range::{self, CellRange};
+use crate::workbook::{Workbook, WorkbookRegistry};
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Runtime, State, Window};
+use tracing::{debug, instrument};
+use umya_spreadsheet::{Spreadsheet, Worksheet};
+
+/// 查找内容的位置。
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LookIn {
+    /// 单元格的值。
+    Values,
+    /// 单元格的公式。
+    Formulas,
+}
+
+impl Default for LookIn {
+    fn default() -> Self {
+        LookIn::Values
+    }
+}
+
+/// 查找选项。
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FindOptions {
+    /// 区分大小写。
+    pub match_case: bool,
+    /// 单元格内容需要与查找内容完全一致。
+    pub whole_cell: bool,
+    /// 查找内容为正则表达式。
+    pub regex: bool,
+    pub look_in: LookIn,
+    /// 查找的 sheet 名称，为空时查找所有 sheet。
+    pub sheets: Option<Vec<String>>,
+    /// 查找的区域或名称，为空时查找整个 sheet。
+    pub range: Option<String>,
+}
+
+/// 查找结果。
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindMatch {
+    pub sheet_name: String,
+    /// 单元格坐标，如 `"B3"`。
+    pub cell: String,
+    pub column: u32,
+    pub row: u32,
+    pub value: String,
+    pub formula: String,
+}
+
+/// `build_matcher` 根据查找选项生成正则表达式。
+fn build_matcher(query: &str, options: &FindOptions) -> Result<Regex, Error> {
+    let pattern = if options.regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    let pattern = if options.whole_cell {
+        format!("^(?:{})$", pattern)
+    } else {
+        pattern
+    };
+    match RegexBuilder::new(&pattern)
+        .case_insensitive(!options.match_case)
+        .build()
+    {
+        Ok(regex) => Ok(regex),
+        Err(error) => Err(Error::String(format!(
+            "查找内容 {} 格式错误! {}",
+            query, error
+        ))),
+    }
+}
+
+/// `target_sheets` 获取需要查找的 sheet 索引。
+fn target_sheets(spreadsheet: &Spreadsheet, options: &FindOptions) -> Result<Vec<usize>, Error> {
+    let sheets = spreadsheet.get_sheet_collection();
+    match &options.sheets {
+        Some(names) => {
+            let mut indexes = Vec::new();
+            for name in names {
+                match sheets.iter().position(|sheet| sheet.get_name() == name) {
+                    Some(index) => indexes.push(index),
+                    None => return Err(Error::String(format!("sheet {} 不存在!", name))),
+                }
+            }
+            Ok(indexes)
+        }
+        None => Ok((0..sheets.len()).collect()),
+    }
+}
+
+/// `sheet_scope` 获取 sheet 中的查找区域，区域或名称位于其他 sheet 时返回 `None`，跳过该 sheet。
+fn sheet_scope(
+    spreadsheet: &Spreadsheet,
+    sheet_name: &str,
+    options: &FindOptions,
+) -> Result<Option<Option<CellRange>>, Error> {
+    match &options.range {
+        Some(range) => match lookup_range(spreadsheet, sheet_name, range)? {
+            (sheet, range) if sheet == sheet_name => Ok(Some(Some(range))),
+            _ => Ok(None),
+        },
+        None => Ok(Some(None)),
+    }
+}
+
+/// `find_in_sheet` 查找 sheet 中 `scope` 区域内匹配的单元格，按行列排序。
+fn find_in_sheet(
+    worksheet: &Worksheet,
+    scope: Option<CellRange>,
+    matcher: &Regex,
+    options: &FindOptions,
+) -> Vec<FindMatch> {
+    let mut matches = Vec::new();
+    for cell in worksheet.get_cell_collection() {
+        let coordinate = cell.get_coordinate();
+        let (column, row) = (*coordinate.get_col_num(), *coordinate.get_row_num());
+        if let Some(scope) = &scope {
+            if !scope.contains(column, row) {
+                continue;
+            }
+        }
+        let value = cell.get_value().to_string();
+        let formula = cell.get_formula().to_string();
+        let text = match options.look_in {
+            LookIn::Values => &value,
+            LookIn::Formulas => &formula,
+        };
+        if !text.is_empty() && matcher.is_match(text) {
+            matches.push(FindMatch {
+                sheet_name: worksheet.get_name().to_string(),
+                cell: range::coordinate(column, row),
+                column,
+                row,
+                value,
+                formula,
+            });
+        }
+    }
+    matches.sort_by_key(|m| (m.row, m.column));
+    matches
+}
+
+impl Workbook {
+    /// `find` 在文件中查找内容，返回匹配的单元格。
+    ///
+    /// # Arguments
+    ///
+    /// - `query` 查找内容，`options.regex` 为 `true` 时为正则表达式
+    /// - `options` 查找选项，包括区分大小写、完全匹配、查找值或公式以及 sheet 和区域范围
+    pub fn find(&self, query: &str, options: &FindOptions) -> Result<Vec<FindMatch>, Error> {
+        let matcher = build_matcher(query, options)?;
+        let book = &self.spreadsheet;
+        let mut matches = Vec::new();
+        for index in target_sheets(book, options)? {
+            let worksheet = &book.get_sheet_collection()[index];
+            if let Some(scope) = sheet_scope(book, worksheet.get_name(), options)? {
+                matches.extend(find_in_sheet(worksheet, scope, &matcher, options));
+            }
+        }
+        debug!("查找 {} 共 {} 个结果", query, matches.len());
+        Ok(matches)
+    }
+
+    /// `replace_all` 替换文件中所有匹配的内容，返回修改的单元格数量。
+    ///
+    /// `options.regex` 为 `true` 时 `replacement` 可以使用 `$1` 引用分组，否则按原文替换。
+    /// 替换值时跳过有公式的单元格，写入值会丢失公式。
+    pub fn replace_all(
+        &mut self,
+        query: &str,
+        replacement: &str,
+        options: &FindOptions,
+    ) -> Result<usize, Error> {
+        let matcher = build_matcher(query, options)?;
+        let book = &mut self.spreadsheet;
+        let mut changed = 0;
+        for index in target_sheets(book, options)? {
+            let sheet_name = book.get_sheet_collection()[index].get_name().to_string();
+            let scope = match sheet_scope(book, &sheet_name, options)? {
+                Some(scope) => scope,
+                None => continue,
+            };
+            let worksheet = &mut book.get_sheet_collection_mut()[index];
+            for found in find_in_sheet(worksheet, scope, &matcher, options) {
+                if options.look_in == LookIn::Values && !found.formula.is_empty() {
+                    continue;
+                }
+                let text = match options.look_in {
+                    LookIn::Values => &found.value,
+                    LookIn::Formulas => &found.formula,
+                };
+                let replaced = if options.regex {
+                    matcher.replace_all(text, replacement)
+                } else {
+                    matcher.replace_all(text, NoExpand(replacement))
+                };
+                if replaced == *text {
+                    continue;
+                }
+                let cell = worksheet.get_cell_by_column_and_row_mut(&found.column, &found.row);
+                match options.look_in {
+                    LookIn::Values => cell.set_value(replaced.to_string()),
+                    LookIn::Formulas => cell.set_formula(replaced.to_string()),
+                };
+                changed += 1;
+            }
+        }
+        debug!("替换 {} 为 {} 共 {} 个单元格", query, replacement, changed);
+        Ok(changed)
+    }
+}
+
+/// `find` 在文件中查找内容，返回匹配的单元格。
+#[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path))]
+pub fn find<R: Runtime>(
+    _app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+    query: String,
+    options: Option<FindOptions>,
+) -> Result<Vec<FindMatch>, Error> {
+    let options = options.unwrap_or_default();
+    state.with_workbook(&path, |workbook| workbook.find(&query, &options))
+}
+
+/// `replace_all` 替换文件中所有匹配的内容，返回修改的单元格数量。
+///
+/// 替换值时跳过有公式的单元格。
+#[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path))]
+pub fn replace_all<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+    query: String,
+    replacement: String,
+    options: Option<FindOptions>,
+) -> Result<usize, Error> {
+    let options = options.unwrap_or_default();
+    let result = state.edit(&path, EditScope::Workbook, "replace_all", |workbook| {
+        workbook.replace_all(&query, &replacement, &options)
+    })?;
+    if result > 0 {
+        emit_changed(&app, &path, None, options.range, ChangeKind::Values);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workbook() -> Workbook {
+        let mut spreadsheet = umya_spreadsheet::new_file();
+        let sheet1 = spreadsheet.get_sheet_by_name_mut("Sheet1").unwrap();
+        sheet1.get_cell_mut("A1").set_value("Apple pie");
+        sheet1.get_cell_mut("B1").set_value("apple");
+        sheet1.get_cell_mut("A2").set_value("Pineapple");
+        sheet1
+            .get_cell_mut("B2")
+            .set_value("APPLE")
+            .set_formula("UPPER(B1)");
+        spreadsheet
+            .new_sheet("Sheet2")
+            .unwrap()
+            .get_cell_mut("A1")
+            .set_value("apple");
+        Workbook::new(spreadsheet)
+    }
+
+    fn options(value: serde_json::Value) -> FindOptions {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn find_table() {
+        let workbook = workbook();
+        let cases = [
+            (
+                "apple",
+                serde_json::json!({}),
+                vec![
+                    "Sheet1!A1",
+                    "Sheet1!B1",
+                    "Sheet1!A2",
+                    "Sheet1!B2",
+                    "Sheet2!A1",
+                ],
+            ),
+            (
+                "apple",
+                serde_json::json!({ "matchCase": true }),
+                vec!["Sheet1!B1", "Sheet1!A2", "Sheet2!A1"],
+            ),
+            (
+                "apple",
+                serde_json::json!({ "wholeCell": true }),
+                vec!["Sheet1!B1", "Sheet1!B2", "Sheet2!A1"],
+            ),
+            (
+                "^p.*e$",
+                serde_json::json!({ "regex": true }),
+                vec!["Sheet1!A2"],
+            ),
+            (
+                "b1",
+                serde_json::json!({ "lookIn": "formulas" }),
+                vec!["Sheet1!B2"],
+            ),
+            (
+                "apple",
+                serde_json::json!({ "sheets": ["Sheet2"] }),
+                vec!["Sheet2!A1"],
+            ),
+            (
+                "apple",
+                serde_json::json!({ "range": "A1:A2" }),
+                vec!["Sheet1!A1", "Sheet1!A2", "Sheet2!A1"],
+            ),
+        ];
+        for (query, value, expected) in cases.iter() {
+            let matches: Vec<String> = workbook
+                .find(query, &options(value.clone()))
+                .unwrap()
+                .into_iter()
+                .map(|found| format!("{}!{}", found.sheet_name, found.cell))
+                .collect();
+            assert_eq!(matches, *expected, "{} {}", query, value);
+        }
+        assert!(workbook
+            .find("(", &options(serde_json::json!({ "regex": true })))
+            .is_err());
+        assert!(workbook
+            .find(
+                "apple",
+                &options(serde_json::json!({ "sheets": ["Missing"] }))
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn replace_values_skips_formula_cells() {
+        let mut workbook = workbook();
+        let changed = workbook
+            .replace_all("apple", "orange", &FindOptions::default())
+            .unwrap();
+        assert_eq!(changed, 4);
+        let sheet1 = workbook.worksheet("Sheet1").unwrap();
+        assert_eq!(sheet1.get_value("A1"), "orange pie");
+        assert_eq!(sheet1.get_value("B1"), "orange");
+        assert_eq!(sheet1.get_value("A2"), "Pineorange");
+        assert_eq!(sheet1.get_value("B2"), "APPLE");
+        assert_eq!(sheet1.get_cell("B2").unwrap().get_formula(), "UPPER(B1)");
+        assert_eq!(
+            workbook.worksheet("Sheet2").unwrap().get_value("A1"),
+            "orange"
+        );
+    }
+
+    #[test]
+    fn replace_with_groups_and_in_formulas() {
+        let mut workbook = workbook();
+        let regex = options(serde_json::json!({ "regex": true, "matchCase": true }));
+        assert_eq!(
+            workbook
+                .replace_all(r"(\w+) pie", "$1 tart", &regex)
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            workbook.worksheet("Sheet1").unwrap().get_value("A1"),
+            "Apple tart"
+        );
+        // 非正则替换时 `$1` 按原文写入
+        assert_eq!(
+            workbook
+                .replace_all("Pineapple", "$1", &FindOptions::default())
+                .unwrap(),
+            1
+        );
+        assert_eq!(workbook.worksheet("Sheet1").unwrap().get_value("A2"), "$1");
+
+        let formulas = options(serde_json::json!({ "lookIn": "formulas" }));
+        assert_eq!(workbook.replace_all("B1", "A1", &formulas).unwrap(), 1);
+        assert_eq!(
+            workbook
+                .worksheet("Sheet1")
+                .unwrap()
+                .get_cell("B2")
+                .unwrap()
+                .get_formula(),
+            "UPPER(A1)"
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use crate::range::{column_index_from_string, MAX_COLUMN, MAX_ROW};
+
+/// 引用失效时的错误值。
+const REF_ERROR: &str = "#REF!";
+
+/// `shift_rows` 将公式中的相对行引用平移 `delta` 行，绝对行引用 (如 `A$1`) 保持不变。
+///
+/// 支持单元格引用和整行引用 (如 `2:5`)，字符串常量和带引号的 sheet 名称不会被修改，
+/// 平移到表格范围之外的引用替换为 `#REF!`。
+pub fn shift_rows(formula: &str, delta: i64) -> String {
+    if delta == 0 {
+        return formula.to_string();
+    }
+    let chars: Vec<char> = formula.chars().collect();
+    let mut result = String::with_capacity(formula.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '"' || c == '\'' {
+            // 引号内的内容原样复制，连续两个引号表示转义。
+            result.push(c);
+            i += 1;
+            while i < chars.len() {
+                result.push(chars[i]);
+                if chars[i] == c {
+                    if i + 1 < chars.len() && chars[i + 1] == c {
+                        result.push(c);
+                        i += 2;
+                        continue;
+                    }
+                    i += 1;
+                    break;
+                }
+                i += 1;
+            }
+            continue;
+        }
+        let boundary = i == 0 || !is_name_char(chars[i - 1]);
+        if boundary && (c == '$' || c.is_ascii_alphanumeric()) {
+            let shifted =
+                shift_reference(&chars, i, delta).or_else(|| shift_row_range(&chars, i, delta));
+            if let Some((end, reference)) = shifted {
+                result.push_str(&reference);
+                i = end;
+                continue;
+            }
+        }
+        if is_name_char(c) {
+            // 函数名、名称等标识符整体复制，避免匹配到其中的片段。
+            let start = i;
+            while i < chars.len() && is_name_char(chars[i]) {
+                i += 1;
+            }
+            result.extend(&chars[start..i]);
+            continue;
+        }
+        result.push(c);
+        i += 1;
+    }
+    result
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
+
+/// `shift_reference` 尝试从 `start` 位置解析单元格引用，返回 (结束位置, 平移后的引用)。
+fn shift_reference(chars: &[char], start: usize, delta: i64) -> Option<(usize, String)> {
+    let mut i = start;
+    if chars[i] == '$' {
+        i += 1;
+    }
+    let letters_start = i;
+    while i < chars.len() && chars[i].is_ascii_alphabetic() {
+        i += 1;
+    }
+    if i == letters_start || i - letters_start > 3 {
+        return None;
+    }
+    let letters: String = chars[letters_start..i].iter().collect();
+    match column_index_from_string(&letters) {
+        Some(column) if column <= MAX_COLUMN => {}
+        _ => return None,
+    }
+    let row_absolute = i < chars.len() && chars[i] == '$';
+    if row_absolute {
+        i += 1;
+    }
+    let digits_start = i;
+    while i < chars.len() && chars[i].is_ascii_digit() {
+        i += 1;
+    }
+    if i == digits_start {
+        return None;
+    }
+    // 后面紧跟标识符字符、函数括号或 `!` 时不是单元格引用。
+    if i < chars.len() && (is_name_char(chars[i]) || chars[i] == '(' || chars[i] == '!') {
+        return None;
+    }
+    let prefix: String = chars[start..digits_start].iter().collect();
+    let row = shift_row(chars, digits_start, i, row_absolute, delta)?;
+    if row == REF_ERROR {
+        return Some((i, row));
+    }
+    Some((i, format!("{}{}", prefix, row)))
+}
+
+/// `shift_row_range` 尝试从 `start` 位置解析 `2:5`、`$2:$5` 形式的整行引用。
+fn shift_row_range(chars: &[char], start: usize, delta: i64) -> Option<(usize, String)> {
+    let mut rows = Vec::new();
+    let mut i = start;
+    for part in 0..2 {
+        if part == 1 {
+            if i >= chars.len() || chars[i] != ':' {
+                return None;
+            }
+            i += 1;
+        }
+        let absolute = i < chars.len() && chars[i] == '$';
+        if absolute {
+            i += 1;
+        }
+        let digits_start = i;
+        while i < chars.len() && chars[i].is_ascii_digit() {
+            i += 1;
+        }
+        if i == digits_start {
+            return None;
+        }
+        let row = shift_row(chars, digits_start, i, absolute, delta)?;
+        rows.push(if absolute { format!("${}", row) } else { row });
+    }
+    if i < chars.len() && (is_name_char(chars[i]) || chars[i] == '(' || chars[i] == '!') {
+        return None;
+    }
+    if rows.iter().any(|row| row == REF_ERROR) {
+        return Some((i, String::from(REF_ERROR)));
+    }
+    Some((i, format!("{}:{}", rows[0], rows[1])))
+}
+
+/// `shift_row` 平移 `chars[start..end]` 中的行号，超出表格范围时返回 `#REF!`。
+fn shift_row(
+    chars: &[char],
+    start: usize,
+    end: usize,
+    absolute: bool,
+    delta: i64,
+) -> Option<String> {
+    let digits: String = chars[start..end].iter().collect();
+    if absolute {
+        return Some(digits);
+    }
+    let row = digits.parse::<i64>().ok()? + delta;
+    if row < 1 || row > MAX_ROW as i64 {
+        return Some(String::from(REF_ERROR));
+    }
+    Some(row.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::shift_rows;
+
+    #[test]
+    fn shift_rows_table() {
+        let cases = [
+            ("A1+B2", 2, "A3+B4"),
+            ("=A1*2", 1, "=A2*2"),
+            ("$A1", 2, "$A3"),
+            ("A$1", 2, "A$1"),
+            ("$A$1+A1", 2, "$A$1+A3"),
+            ("SUM(A1:B10)", 1, "SUM(A2:B11)"),
+            ("SUM(a1:b2)", 1, "SUM(a2:b3)"),
+            ("Sheet2!A1", 1, "Sheet2!A2"),
+            ("'My Sheet'!B3", 1, "'My Sheet'!B4"),
+            ("'A1''s'!A1", 1, "'A1''s'!A2"),
+            ("\"A1\"&A1", 1, "\"A1\"&A2"),
+            ("\"say \"\"B2\"\"\"&B2", 1, "\"say \"\"B2\"\"\"&B3"),
+            ("LOG10(A1)", 1, "LOG10(A2)"),
+            ("ATAN2(A1,B1)", 1, "ATAN2(A2,B2)"),
+            ("MyName1+A1", 1, "MyName1+A2"),
+            ("Table_1.A1", 1, "Table_1.A1"),
+            ("A:A", 5, "A:A"),
+            ("SUM(2:5)", 1, "SUM(3:6)"),
+            ("SUM($2:5)", 1, "SUM($2:6)"),
+            ("SUM(1:3)", -1, "SUM(#REF!)"),
+            ("A1+12", 3, "A4+12"),
+            ("1.5*A1", 1, "1.5*A2"),
+            ("XFD1+XFE1", 1, "XFD2+XFE1"),
+            ("A3", -2, "A1"),
+            ("A3+B1", -3, "#REF!+#REF!"),
+            ("A1048576", 1, "#REF!"),
+            ("A1", 0, "A1"),
+        ];
+        for (formula, delta, expected) in cases.iter() {
+            assert_eq!(
+                shift_rows(formula, *delta),
+                *expected,
+                "shift_rows({:?}, {})",
+                formula,
+                delta
+            );
+        }
+    }
+}
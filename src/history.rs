@@ -0,0 +1,589 @@
+use crate::error::Error;
+use crate::event::{emit_changed, ChangeKind};
+use crate::filter::FilterColumn;
+use crate::range::{CellRange, MAX_COLUMN, MAX_ROW};
+use crate::workbook::{Workbook, WorkbookRegistry};
+use crate::worksheet::{remove_columns, remove_rows};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use tauri::{command, AppHandle, Runtime, State, Window};
+use tracing::{debug, error, instrument};
+use umya_spreadsheet::{Cell, Spreadsheet, Worksheet};
+
+/// 默认保留的撤销步数。
+pub const DEFAULT_HISTORY_LIMIT: usize = 50;
+
+/// 修改影响的范围，撤销时恢复该范围修改前的内容。
+#[derive(Clone, Copy, Debug)]
+pub enum EditScope<'a> {
+    /// 只修改 sheet 中区域内的单元格。
+    Cells(&'a str, CellRange),
+    /// 在 sheet 最后一行后面写入内容。
+    AppendRows(&'a str),
+    /// 在 sheet 最后一列后面写入内容。
+    AppendColumns(&'a str),
+    /// 在 sheet 的 `index` 位置插入 `count` 行，撤销时删除插入的行。
+    InsertRows(&'a str, u32, u32),
+    /// 在 sheet 的 `index` 位置插入 `count` 列，撤销时删除插入的列。
+    InsertColumns(&'a str, u32, u32),
+    /// 修改 sheet 的结构，如删除行列、筛选和图表。
+    Sheet(&'a str),
+    /// 修改多个 sheet、新建 sheet 或修改名称。
+    Workbook,
+}
+
+impl EditScope<'_> {
+    /// `sheet_name` 修改的 sheet 名称，修改整个文件时为 `None`。
+    fn sheet_name(&self) -> Option<&str> {
+        match *self {
+            EditScope::Cells(sheet_name, _)
+            | EditScope::AppendRows(sheet_name)
+            | EditScope::AppendColumns(sheet_name)
+            | EditScope::InsertRows(sheet_name, _, _)
+            | EditScope::InsertColumns(sheet_name, _, _)
+            | EditScope::Sheet(sheet_name) => Some(sheet_name),
+            EditScope::Workbook => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Axis {
+    Rows,
+    Columns,
+}
+
+/// 修改前的内容，撤销和重做都是与当前内容交换。
+enum Snapshot {
+    /// 区域内原有的单元格。
+    Cells {
+        sheet: String,
+        range: CellRange,
+        cells: Vec<Cell>,
+    },
+    /// 插入的行或列，恢复时删除。
+    Inserted {
+        sheet: String,
+        axis: Axis,
+        index: u32,
+        count: u32,
+    },
+    /// 删除的行或列中的单元格，恢复时重新插入。
+    Removed {
+        sheet: String,
+        axis: Axis,
+        index: u32,
+        count: u32,
+        cells: Vec<Cell>,
+    },
+    Sheet {
+        worksheet: Box<Worksheet>,
+        filters: Option<Vec<FilterColumn>>,
+    },
+    Workbook {
+        spreadsheet: Box<Spreadsheet>,
+        filters: HashMap<String, Vec<FilterColumn>>,
+    },
+}
+
+struct HistoryEntry {
+    label: String,
+    sheet: Option<String>,
+    snapshot: Snapshot,
+}
+
+/// 单个文件的撤销和重做记录。
+pub(crate) struct History {
+    undo: VecDeque<HistoryEntry>,
+    redo: Vec<HistoryEntry>,
+    limit: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            limit: DEFAULT_HISTORY_LIMIT,
+        }
+    }
+}
+
+impl History {
+    fn push(&mut self, entry: HistoryEntry) {
+        self.redo.clear();
+        if self.limit == 0 {
+            return;
+        }
+        self.undo.push_back(entry);
+        while self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+    }
+}
+
+/// 撤销和重做记录，按操作名称列出。
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryInfo {
+    /// 可撤销的操作，最后一项最先撤销。
+    pub undo: Vec<String>,
+    /// 可重做的操作，最后一项最先重做。
+    pub redo: Vec<String>,
+    pub limit: usize,
+}
+
+/// 撤销或重做的操作。
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryStep {
+    pub label: String,
+    /// 恢复的 sheet 名称，恢复整个文件时为空。
+    pub sheet: Option<String>,
+}
+
+/// `copy_cells` 复制 `range` 内已有的单元格。
+fn copy_cells(worksheet: &Worksheet, range: &CellRange) -> Vec<Cell> {
+    worksheet
+        .get_cell_collection()
+        .into_iter()
+        .filter(|cell| {
+            let coordinate = cell.get_coordinate();
+            range.contains(*coordinate.get_col_num(), *coordinate.get_row_num())
+        })
+        .cloned()
+        .collect()
+}
+
+/// `replace_cells` 用 `cells` 替换 `range` 内的单元格，返回替换前的单元格。
+fn replace_cells(worksheet: &mut Worksheet, range: &CellRange, cells: Vec<Cell>) -> Vec<Cell> {
+    let previous = copy_cells(worksheet, range);
+    for cell in &previous {
+        let coordinate = cell.get_coordinate();
+        worksheet
+            .remove_cell_by_column_and_row_mut(coordinate.get_col_num(), coordinate.get_row_num());
+    }
+    for cell in cells {
+        // 没有行信息的单元格不会被保存
+        worksheet.get_row_dimension_mut(cell.get_coordinate().get_row_num());
+        worksheet.set_cell(cell);
+    }
+    previous
+}
+
+/// `band` 从 `index` 开始的 `count` 行或列。
+fn band(axis: Axis, index: u32, count: u32) -> CellRange {
+    let last = index + count.max(1) - 1;
+    match axis {
+        Axis::Rows => CellRange::new((1, index), (MAX_COLUMN, last)),
+        Axis::Columns => CellRange::new((index, 1), (last, MAX_ROW)),
+    }
+}
+
+impl Workbook {
+    /// `snapshot` 保存 `scope` 范围当前的内容。
+    fn snapshot(&self, scope: EditScope<'_>) -> Result<Snapshot, Error> {
+        match scope {
+            EditScope::Cells(sheet_name, range) => Ok(Snapshot::Cells {
+                sheet: sheet_name.to_string(),
+                range,
+                cells: copy_cells(self.worksheet(sheet_name)?, &range),
+            }),
+            EditScope::AppendRows(sheet_name) => {
+                let row = self.worksheet(sheet_name)?.get_highest_row() + 1;
+                Ok(Snapshot::Cells {
+                    sheet: sheet_name.to_string(),
+                    range: CellRange::new((1, row), (MAX_COLUMN, MAX_ROW)),
+                    cells: Vec::new(),
+                })
+            }
+            EditScope::AppendColumns(sheet_name) => {
+                let column = self.worksheet(sheet_name)?.get_highest_column() + 1;
+                Ok(Snapshot::Cells {
+                    sheet: sheet_name.to_string(),
+                    range: CellRange::new((column, 1), (MAX_COLUMN, MAX_ROW)),
+                    cells: Vec::new(),
+                })
+            }
+            EditScope::InsertRows(sheet_name, index, count) => {
+                self.worksheet(sheet_name)?;
+                Ok(Snapshot::Inserted {
+                    sheet: sheet_name.to_string(),
+                    axis: Axis::Rows,
+                    index,
+                    count,
+                })
+            }
+            EditScope::InsertColumns(sheet_name, index, count) => {
+                self.worksheet(sheet_name)?;
+                Ok(Snapshot::Inserted {
+                    sheet: sheet_name.to_string(),
+                    axis: Axis::Columns,
+                    index,
+                    count,
+                })
+            }
+            EditScope::Sheet(sheet_name) => Ok(Snapshot::Sheet {
+                worksheet: Box::new(self.worksheet(sheet_name)?.clone()),
+                filters: self.auto_filters.get(sheet_name).cloned(),
+            }),
+            EditScope::Workbook => Ok(Snapshot::Workbook {
+                spreadsheet: Box::new(self.spreadsheet.clone()),
+                filters: self.auto_filters.clone(),
+            }),
+        }
+    }
+
+    /// `restore` 恢复保存的内容，返回恢复前的内容。
+    fn restore(&mut self, snapshot: Snapshot) -> Result<Snapshot, Error> {
+        match snapshot {
+            Snapshot::Cells {
+                sheet,
+                range,
+                cells,
+            } => {
+                let previous = replace_cells(self.worksheet_mut(&sheet)?, &range, cells);
+                Ok(Snapshot::Cells {
+                    sheet,
+                    range,
+                    cells: previous,
+                })
+            }
+            Snapshot::Inserted {
+                sheet,
+                axis,
+                index,
+                count,
+            } => {
+                let worksheet = self.worksheet_mut(&sheet)?;
+                let cells = copy_cells(worksheet, &band(axis, index, count));
+                match axis {
+                    Axis::Rows => remove_rows(worksheet, index, count),
+                    Axis::Columns => remove_columns(worksheet, index, count),
+                }
+                Ok(Snapshot::Removed {
+                    sheet,
+                    axis,
+                    index,
+                    count,
+                    cells,
+                })
+            }
+            Snapshot::Removed {
+                sheet,
+                axis,
+                index,
+                count,
+                cells,
+            } => {
+                let worksheet = self.worksheet_mut(&sheet)?;
+                match axis {
+                    Axis::Rows => worksheet.insert_new_row(&index, &count),
+                    Axis::Columns => worksheet.insert_new_column_by_index(&index, &count),
+                }
+                replace_cells(worksheet, &band(axis, index, count), cells);
+                Ok(Snapshot::Inserted {
+                    sheet,
+                    axis,
+                    index,
+                    count,
+                })
+            }
+            Snapshot::Sheet { worksheet, filters } => {
+                let sheet_name = worksheet.get_name().to_string();
+                let current = self.worksheet_mut(&sheet_name)?;
+                let previous = std::mem::replace(current, *worksheet);
+                let previous_filters = match filters {
+                    Some(filters) => self.auto_filters.insert(sheet_name, filters),
+                    None => self.auto_filters.remove(&sheet_name),
+                };
+                Ok(Snapshot::Sheet {
+                    worksheet: Box::new(previous),
+                    filters: previous_filters,
+                })
+            }
+            Snapshot::Workbook {
+                spreadsheet,
+                filters,
+            } => Ok(Snapshot::Workbook {
+                spreadsheet: Box::new(std::mem::replace(&mut self.spreadsheet, *spreadsheet)),
+                filters: std::mem::replace(&mut self.auto_filters, filters),
+            }),
+        }
+    }
+
+    /// `record` 执行修改并记录撤销信息。
+    ///
+    /// 只保存 `scope` 范围修改前的内容，修改失败时恢复该内容。撤销步数为 0 时不保存，
+    /// 修改失败时也不会恢复，需要全部成功或全部失败的修改使用 [`Workbook::transaction`]。
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// workbook.record(EditScope::InsertRows("Sheet1", 2, 1), "insert_row", |workbook| {
+    ///     workbook.insert_row("Sheet1", 2, &data, true)
+    /// })?;
+    /// ```
+    pub fn record<T, F: FnOnce(&mut Workbook) -> Result<T, Error>>(
+        &mut self,
+        scope: EditScope<'_>,
+        label: &str,
+        f: F,
+    ) -> Result<T, Error> {
+        if self.history.limit == 0 {
+            let value = f(self)?;
+            self.mark_dirty();
+            self.history.redo.clear();
+            return Ok(value);
+        }
+        self.transaction(scope, label, f)
+    }
+
+    /// `transaction` 同 [`Workbook::record`]，撤销步数为 0 时也保存修改前的内容，
+    /// 修改失败时总是恢复。
+    pub fn transaction<T, F: FnOnce(&mut Workbook) -> Result<T, Error>>(
+        &mut self,
+        scope: EditScope<'_>,
+        label: &str,
+        f: F,
+    ) -> Result<T, Error> {
+        let snapshot = self.snapshot(scope)?;
+        match f(self) {
+            Ok(value) => {
+                self.mark_dirty();
+                self.history.push(HistoryEntry {
+                    label: label.to_string(),
+                    sheet: scope.sheet_name().map(str::to_string),
+                    snapshot,
+                });
+                Ok(value)
+            }
+            Err(error) => {
+                if let Err(restore_error) = self.restore(snapshot) {
+                    error!("恢复 {} 修改前的内容失败! {}", label, restore_error);
+                }
+                Err(error)
+            }
+        }
+    }
+
+    /// `undo` 撤销最近一次修改，没有可撤销的修改时返回 `None`。
+    pub fn undo(&mut self) -> Result<Option<HistoryStep>, Error> {
+        let entry = match self.history.undo.pop_back() {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let current = self.restore(entry.snapshot)?;
+        self.mark_dirty();
+        debug!("撤销 {}", &entry.label);
+        let step = HistoryStep {
+            label: entry.label.clone(),
+            sheet: entry.sheet.clone(),
+        };
+        self.history.redo.push(HistoryEntry {
+            snapshot: current,
+            ..entry
+        });
+        Ok(Some(step))
+    }
+
+    /// `redo` 重做最近一次撤销的修改，没有可重做的修改时返回 `None`。
+    pub fn redo(&mut self) -> Result<Option<HistoryStep>, Error> {
+        let entry = match self.history.redo.pop() {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let current = self.restore(entry.snapshot)?;
+        self.mark_dirty();
+        debug!("重做 {}", &entry.label);
+        let step = HistoryStep {
+            label: entry.label.clone(),
+            sheet: entry.sheet.clone(),
+        };
+        self.history.undo.push_back(HistoryEntry {
+            snapshot: current,
+            ..entry
+        });
+        Ok(Some(step))
+    }
+
+    /// `history` 获取撤销和重做记录。
+    pub fn history(&self) -> HistoryInfo {
+        HistoryInfo {
+            undo: self.history.undo.iter().map(|e| e.label.clone()).collect(),
+            redo: self.history.redo.iter().map(|e| e.label.clone()).collect(),
+            limit: self.history.limit,
+        }
+    }
+
+    /// `set_history_limit` 设置保留的撤销步数，为 0 时不记录撤销信息，修改失败时也不恢复。
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history.limit = limit;
+        while self.history.undo.len() > limit {
+            self.history.undo.pop_front();
+        }
+        self.history.redo.truncate(limit);
+    }
+}
+
+/// `undo` 撤销最近一次修改，没有可撤销的修改时返回空。
+#[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path))]
+pub fn undo<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+) -> Result<Option<HistoryStep>, Error> {
+    let step = state.with_workbook(&path, |workbook| workbook.undo())?;
+    if let Some(step) = &step {
+        emit_changed(&app, &path, step.sheet.as_deref(), None, ChangeKind::Undo);
+    }
+    Ok(step)
+}
+
+/// `redo` 重做最近一次撤销的修改，没有可重做的修改时返回空。
+#[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path))]
+pub fn redo<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+) -> Result<Option<HistoryStep>, Error> {
+    let step = state.with_workbook(&path, |workbook| workbook.redo())?;
+    if let Some(step) = &step {
+        emit_changed(&app, &path, step.sheet.as_deref(), None, ChangeKind::Redo);
+    }
+    Ok(step)
+}
+
+/// `history` 获取撤销和重做记录。
+#[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path))]
+pub fn history<R: Runtime>(
+    _app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+) -> Result<HistoryInfo, Error> {
+    state.with_workbook(&path, |workbook| Ok(workbook.history()))
+}
+
+/// `set_history_limit` 设置保留的撤销步数，为 0 时不记录撤销信息，修改失败时也不恢复。
+#[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path))]
+pub fn set_history_limit<R: Runtime>(
+    _app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+    limit: usize,
+) -> Result<(), Error> {
+    state.with_workbook(&path, |workbook| {
+        workbook.set_history_limit(limit);
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workbook() -> Workbook {
+        let mut workbook = Workbook::new(umya_spreadsheet::new_file());
+        workbook
+            .append_row(
+                "Sheet1",
+                &[vec!["a".into()], vec!["b".into()], vec!["c".into()]],
+            )
+            .unwrap();
+        workbook
+    }
+
+    fn column(workbook: &Workbook) -> Vec<String> {
+        let worksheet = workbook.worksheet("Sheet1").unwrap();
+        (1..=worksheet.get_highest_row())
+            .map(|row| worksheet.get_value_by_column_and_row(&1, &row))
+            .collect()
+    }
+
+    #[test]
+    fn undo_and_redo_cells() {
+        let mut workbook = workbook();
+        workbook
+            .record(
+                EditScope::Cells("Sheet1", CellRange::new((1, 2), (1, 2))),
+                "set_value",
+                |workbook| workbook.set_value_by_column_and_row("Sheet1", (1, 2), "x"),
+            )
+            .unwrap();
+        assert_eq!(column(&workbook), ["a", "x", "c"]);
+        workbook.undo().unwrap().unwrap();
+        assert_eq!(column(&workbook), ["a", "b", "c"]);
+        workbook.redo().unwrap().unwrap();
+        assert_eq!(column(&workbook), ["a", "x", "c"]);
+    }
+
+    #[test]
+    fn undo_inserted_and_appended_rows() {
+        let mut workbook = workbook();
+        let data = [vec!["x".to_string()], vec!["y".to_string()]];
+        workbook
+            .record(
+                EditScope::InsertRows("Sheet1", 2, 2),
+                "insert_row",
+                |workbook| workbook.insert_row("Sheet1", 2, &data, true),
+            )
+            .unwrap();
+        workbook
+            .record(EditScope::AppendRows("Sheet1"), "append_row", |workbook| {
+                workbook.append_row("Sheet1", &[vec!["z".into()]])
+            })
+            .unwrap();
+        assert_eq!(column(&workbook), ["a", "x", "y", "b", "c", "z"]);
+
+        workbook.undo().unwrap().unwrap();
+        assert_eq!(column(&workbook), ["a", "x", "y", "b", "c"]);
+        workbook.undo().unwrap().unwrap();
+        assert_eq!(column(&workbook), ["a", "b", "c"]);
+        workbook.redo().unwrap().unwrap();
+        assert_eq!(column(&workbook), ["a", "x", "y", "b", "c"]);
+        workbook.redo().unwrap().unwrap();
+        assert_eq!(column(&workbook), ["a", "x", "y", "b", "c", "z"]);
+    }
+
+    #[test]
+    fn failed_edit_is_rolled_back() {
+        let mut workbook = workbook();
+        let result: Result<(), Error> =
+            workbook.record(EditScope::Sheet("Sheet1"), "remove_row", |workbook| {
+                workbook.set_value_by_column_and_row("Sheet1", (1, 1), "x")?;
+                Err(Error::String("失败".into()))
+            });
+        assert!(result.is_err());
+        assert_eq!(column(&workbook), ["a", "b", "c"]);
+        assert!(workbook.history().undo.is_empty());
+    }
+
+    #[test]
+    fn zero_limit_skips_history_but_transactions_roll_back() {
+        let mut workbook = workbook();
+        workbook.set_history_limit(0);
+        workbook
+            .record(EditScope::Workbook, "set_value", |workbook| {
+                workbook.set_value_by_column_and_row("Sheet1", (1, 1), "x")
+            })
+            .unwrap();
+        assert!(workbook.history().undo.is_empty());
+        assert!(workbook.undo().unwrap().is_none());
+
+        let result: Result<(), Error> =
+            workbook.transaction(EditScope::Workbook, "batch", |workbook| {
+                workbook.set_value_by_column_and_row("Sheet1", (1, 2), "y")?;
+                Err(Error::String("失败".into()))
+            });
+        assert!(result.is_err());
+        assert_eq!(column(&workbook), ["x", "b", "c"]);
+    }
+}
@@ -1,13 +1,32 @@
+use std::path::PathBuf;
+use std::time::Duration;
 use tauri::{
-    plugin::{Builder, TauriPlugin},
-    Manager, Runtime,
+    plugin::{Builder as PluginBuilder, TauriPlugin},
+    Manager, Runtime, State,
 };
 
+use batch::batch;
+use chart::{add_chart, list_charts, remove_chart};
+use cleanup::{remove_blank_rows, remove_duplicates};
+use config::init_logging;
+use defined_name::{
+    add_defined_name, list_defined_names, remove_defined_name, update_defined_name,
+};
+use diff::diff_workbooks;
+use filter::{apply_filter, clear_auto_filter, set_auto_filter, set_filter_column};
+use find::{find, replace_all};
+use history::{history, redo, set_history_limit, undo};
+use merge::merge_workbooks;
+use query::query;
+use recovery::{default_dir, discard_recoverable, list_recoverable, recover, spawn_autosave};
+use scope::ScopeMatcher;
+use sort::sort_range;
 use spreadsheet::{
-    close_all_xlsx, close_xlsx, copy_sheet, list_xlsx, new_sheet, new_xlsx, read_xlsx, write_xlsx,
+    check_xlsx, close_all_xlsx, close_xlsx, copy_sheet, list_xlsx, new_sheet, new_xlsx, read_xlsx,
+    reload_xlsx, write_xlsx,
 };
-use state::SpreadsheetState;
-use std::{collections::HashMap, sync::Mutex};
+use summary::summarize;
+use template::render_template;
 
 use crate::worksheet::{
     append_column, append_row, get_collection_by_column, get_collection_by_row,
@@ -17,46 +36,235 @@ use crate::worksheet::{
     set_value_by_column_and_row,
 };
 
+mod batch;
+mod chart;
+mod cleanup;
+mod config;
+mod defined_name;
+mod diff;
+mod encryption;
 mod error;
+mod event;
+mod filter;
+mod find;
+mod formula;
+mod history;
+mod merge;
+mod query;
+mod range;
+mod records;
+mod recovery;
+mod save;
+mod scope;
+mod sort;
 mod spreadsheet;
-mod state;
+mod summary;
+mod template;
+mod workbook;
 mod worksheet;
 
-/// 初始化插件。
-pub fn init<R: Runtime>() -> TauriPlugin<R> {
-    Builder::new("spreadsheet")
-        .invoke_handler(tauri::generate_handler![
-            append_column,
-            append_row,
-            close_all_xlsx,
-            close_xlsx,
-            copy_sheet,
-            get_collection_by_column,
-            get_collection_by_row,
-            get_sheet_highest_column,
-            get_sheet_highest_column_and_row,
-            get_sheet_highest_row,
-            get_value_by_column_and_row,
-            insert_column,
-            insert_new_column,
-            insert_new_column_by_index,
-            insert_new_row,
-            insert_row,
-            list_xlsx,
-            new_sheet,
-            new_xlsx,
-            read_xlsx,
-            remove_column,
-            remove_column_by_index,
-            remove_row,
-            set_value_by_column_and_row,
-            write_xlsx,
-        ])
-        .setup(|app| {
-            app.manage(SpreadsheetState {
-                spreadsheets: Mutex::new(HashMap::new()),
-            });
-            Ok(())
-        })
-        .build()
+pub use batch::Operation;
+pub use chart::{ChartInfo, ChartKind, ChartSpec, LegendPosition, SeriesSpec};
+pub use cleanup::{DuplicateKeep, RemovedRows};
+pub use config::{Config, LogLevel};
+pub use defined_name::DefinedNameInfo;
+pub use diff::{CellChange, CellContent, DiffOptions, RenamedSheet, SheetDiff, WorkbookDiff};
+pub use error::Error;
+pub use event::{
+    ChangeEvent, ChangeKind, WorkbookEvent, CHANGED_EVENT, CLOSED_EVENT, MODIFIED_EVENT,
+    OPENED_EVENT, SAVED_EVENT,
+};
+pub use filter::{CustomCondition, FilterColumn, FilterCriteria, FilterOperator, FilterResult};
+pub use find::{FindMatch, FindOptions, LookIn};
+pub use history::{EditScope, HistoryInfo, HistoryStep, DEFAULT_HISTORY_LIMIT};
+pub use merge::{MergeConflict, MergeResult};
+pub use query::QueryResult;
+pub use records::Records;
+pub use recovery::{RecoverableWorkbook, Recovery};
+pub use scope::PathScope;
+pub use sort::{SortKey, SortOrder, SortType};
+pub use summary::{Aggregate, AggregateFunction, SummaryOutput, SummaryResult};
+pub use template::TemplateResult;
+pub use workbook::{Workbook, WorkbookRegistry};
+pub use worksheet::IndexRange;
+
+/// 扩展 [`Manager`]，Rust 代码可以访问前端正在编辑的 xlsx 文件。
+///
+/// ```ignore
+/// use tauri_plugin_spreadsheet::SpreadsheetExt;
+///
+/// app.spreadsheet().with_workbook("data.xlsx", |workbook| {
+///     workbook.set_value_by_column_and_row("Sheet1", (1, 1), "Hello")
+/// })?;
+/// ```
+pub trait SpreadsheetExt<R: Runtime> {
+    fn spreadsheet(&self) -> State<'_, WorkbookRegistry>;
+}
+
+impl<R: Runtime, T: Manager<R>> SpreadsheetExt<R> for T {
+    fn spreadsheet(&self) -> State<'_, WorkbookRegistry> {
+        self.state::<WorkbookRegistry>()
+    }
+}
+
+/// 插件构建器，设置的选项优先于 `tauri.conf.json` 中的配置。
+///
+/// ```ignore
+/// tauri::Builder::default()
+///     .plugin(
+///         tauri_plugin_spreadsheet::Builder::new()
+///             .max_workbooks(8)
+///             .default_sheet_name("Data")
+///             .build(),
+///     )
+/// ```
+#[derive(Default)]
+pub struct Builder {
+    config: Config,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Builder::default()
+    }
+
+    /// `scope` 允许读写的路径范围，与 `tauri.conf.json` 中的范围合并。
+    pub fn scope(mut self, scope: PathScope) -> Self {
+        self.config.scope = scope;
+        self
+    }
+
+    /// `max_workbooks` 同时打开的文件数量上限。
+    pub fn max_workbooks(mut self, max_workbooks: usize) -> Self {
+        self.config.max_workbooks = Some(max_workbooks);
+        self
+    }
+
+    /// `max_cells` 单次返回给前端的单元格数量上限。
+    pub fn max_cells(mut self, max_cells: usize) -> Self {
+        self.config.max_cells = Some(max_cells);
+        self
+    }
+
+    /// `default_sheet_name` 新建文件的 sheet 名称。
+    pub fn default_sheet_name<S: Into<String>>(mut self, sheet_name: S) -> Self {
+        self.config.default_sheet_name = Some(sheet_name.into());
+        self
+    }
+
+    /// `log_level` 插件日志级别，设置后输出本插件的 tracing 日志和命令耗时。
+    pub fn log_level(mut self, log_level: LogLevel) -> Self {
+        self.config.log_level = Some(log_level);
+        self
+    }
+
+    /// `autosave_interval` 自动保存间隔，未保存的修改会定期写入恢复目录，不会覆盖原文件。
+    pub fn autosave_interval(mut self, interval: Duration) -> Self {
+        self.config.autosave_interval = Some(interval.as_secs().max(1));
+        self
+    }
+
+    /// `backups` 保存时保留的备份数量，最近的备份为 `name.bak.xlsx`。
+    pub fn backups(mut self, backups: usize) -> Self {
+        self.config.backups = Some(backups);
+        self
+    }
+
+    /// `recovery_dir` 自动保存的目录。
+    pub fn recovery_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.config.recovery_dir = Some(dir.into());
+        self
+    }
+
+    pub fn build<R: Runtime>(self) -> TauriPlugin<R, Option<Config>> {
+        let config = self.config;
+        PluginBuilder::<R, Option<Config>>::new("spreadsheet")
+            .invoke_handler(tauri::generate_handler![
+                add_chart,
+                add_defined_name,
+                append_column,
+                append_row,
+                apply_filter,
+                batch,
+                check_xlsx,
+                clear_auto_filter,
+                close_all_xlsx,
+                close_xlsx,
+                copy_sheet,
+                diff_workbooks,
+                discard_recoverable,
+                find,
+                get_collection_by_column,
+                get_collection_by_row,
+                get_sheet_highest_column,
+                get_sheet_highest_column_and_row,
+                get_sheet_highest_row,
+                get_value_by_column_and_row,
+                history,
+                insert_column,
+                insert_new_column,
+                insert_new_column_by_index,
+                insert_new_row,
+                insert_row,
+                list_charts,
+                list_defined_names,
+                list_recoverable,
+                list_xlsx,
+                merge_workbooks,
+                new_sheet,
+                new_xlsx,
+                query,
+                read_xlsx,
+                recover,
+                redo,
+                reload_xlsx,
+                render_template,
+                remove_blank_rows,
+                remove_chart,
+                remove_column,
+                remove_column_by_index,
+                remove_defined_name,
+                remove_duplicates,
+                remove_row,
+                replace_all,
+                set_auto_filter,
+                set_filter_column,
+                set_history_limit,
+                set_value_by_column_and_row,
+                sort_range,
+                summarize,
+                undo,
+                update_defined_name,
+                write_xlsx,
+            ])
+            .setup_with_config(move |app, file_config| {
+                let config = config.merge(file_config.unwrap_or_default());
+                if let Some(log_level) = config.log_level {
+                    init_logging(log_level);
+                }
+                let registry = match config.max_workbooks {
+                    Some(max_workbooks) => WorkbookRegistry::with_max_workbooks(max_workbooks),
+                    None => WorkbookRegistry::new(),
+                }
+                .with_backups(config.backups.unwrap_or(0));
+                app.manage(ScopeMatcher::new(&config.scope)?);
+                app.manage(registry);
+                let recovery_dir = config
+                    .recovery_dir
+                    .clone()
+                    .unwrap_or_else(|| default_dir(app));
+                app.manage(Recovery::new(recovery_dir));
+                if let Some(interval) = config.autosave_interval.filter(|interval| *interval > 0) {
+                    spawn_autosave(app.clone(), Duration::from_secs(interval));
+                }
+                app.manage(config);
+                Ok(())
+            })
+            .build()
+    }
+}
+
+/// 使用默认配置初始化插件。
+pub fn init<R: Runtime>() -> TauriPlugin<R, Option<Config>> {
+    Builder::new().build()
 }
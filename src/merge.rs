@@ -0,0 +1,301 @@
+use crate::diff::{cell_contents, CellContent};
+use crate::error::Error;
+use crate::event::{emit_changed, ChangeKind};
+use crate::history::EditScope;
+use crate::range;
+use crate::workbook::{Workbook, WorkbookRegistry};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use tauri::{command, AppHandle, Runtime, State, Window};
+use tracing::{debug, instrument};
+use umya_spreadsheet::{Spreadsheet, Worksheet};
+
+/// 双方把同一单元格修改为不同内容，合并时保留 `ours` 的内容。
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeConflict {
+    pub sheet: String,
+    pub cell: String,
+    /// 共同版本中的内容，为空时该单元格原来是空的。
+    pub base: Option<CellContent>,
+    pub ours: Option<CellContent>,
+    pub theirs: Option<CellContent>,
+}
+
+/// 合并结果。
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeResult {
+    /// 从 `theirs` 合并的单元格数量。
+    pub applied: usize,
+    /// 从 `theirs` 复制的新 sheet。
+    pub added_sheets: Vec<String>,
+    /// `theirs` 删除且 `ours` 没有修改、因此被删除的 sheet。
+    pub removed_sheets: Vec<String>,
+    /// 一方删除、另一方修改过的 sheet，合并时保留 `ours` 的状态。
+    pub sheet_conflicts: Vec<String>,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+type Contents = HashMap<(u32, u32), CellContent>;
+
+fn contents(spreadsheet: &Spreadsheet, sheet_name: &str) -> Option<Contents> {
+    spreadsheet
+        .get_sheet_by_name(sheet_name)
+        .ok()
+        .map(cell_contents)
+}
+
+/// `write_content` 写入单元格内容，`None` 表示清空。
+fn write_content(
+    worksheet: &mut Worksheet,
+    (column, row): (u32, u32),
+    content: Option<&CellContent>,
+) {
+    let cell = worksheet.get_cell_by_column_and_row_mut(&column, &row);
+    match content {
+        Some(content) if !content.formula.is_empty() => {
+            cell.set_value(content.value.clone());
+            cell.set_formula(content.formula.clone());
+        }
+        Some(content) => {
+            cell.set_value(content.value.clone());
+        }
+        None => {
+            cell.set_value(String::new());
+        }
+    }
+}
+
+/// `merge_cells` 按坐标三方合并单元格，只有一方修改的单元格取修改后的内容。
+fn merge_cells(
+    worksheet: &mut Worksheet,
+    base: &Contents,
+    ours: &Contents,
+    theirs: &Contents,
+    result: &mut MergeResult,
+) {
+    let keys: BTreeSet<(u32, u32)> = base
+        .keys()
+        .chain(ours.keys())
+        .chain(theirs.keys())
+        .map(|(column, row)| (*row, *column))
+        .collect();
+    for (row, column) in keys {
+        let key = (column, row);
+        let (b, o, t) = (base.get(&key), ours.get(&key), theirs.get(&key));
+        if o == t || t == b {
+            continue;
+        }
+        if o == b {
+            write_content(worksheet, key, t);
+            result.applied += 1;
+            continue;
+        }
+        result.conflicts.push(MergeConflict {
+            sheet: worksheet.get_name().to_string(),
+            cell: range::coordinate(column, row),
+            base: b.cloned(),
+            ours: o.cloned(),
+            theirs: t.cloned(),
+        });
+    }
+}
+
+impl Workbook {
+    /// `merge` 把 `theirs` 相对 `base` 的修改合并到当前文件，当前文件视为 `ours`。
+    ///
+    /// 按 sheet 名称和单元格坐标比较值和公式，只有一方修改的单元格自动合并，
+    /// 双方修改为不同内容的单元格保留当前内容并作为冲突返回，由调用方处理后再保存。
+    /// 不合并样式，也不识别插入或删除的行。
+    pub fn merge(
+        &mut self,
+        base: &Spreadsheet,
+        theirs: &Spreadsheet,
+    ) -> Result<MergeResult, Error> {
+        let mut result = MergeResult::default();
+        let mut names: Vec<String> = Vec::new();
+        for spreadsheet in [base, &self.spreadsheet, theirs] {
+            for worksheet in spreadsheet.get_sheet_collection() {
+                if !names.iter().any(|name| name == worksheet.get_name()) {
+                    names.push(worksheet.get_name().to_string());
+                }
+            }
+        }
+        for name in names {
+            let base_cells = contents(base, &name);
+            let our_cells = contents(&self.spreadsheet, &name);
+            let their_cells = contents(theirs, &name);
+            match (base_cells, our_cells, their_cells) {
+                // 只有 `theirs` 新增的 sheet
+                (None, None, Some(_)) => {
+                    let worksheet = theirs
+                        .get_sheet_by_name(&name)
+                        .map_err(|error| {
+                            Error::String(format!("获取 sheet {} 失败! {}", &name, error))
+                        })?
+                        .clone();
+                    self.spreadsheet.add_sheet(worksheet).map_err(|error| {
+                        Error::String(format!("添加 sheet {} 失败! {}", &name, error))
+                    })?;
+                    result.added_sheets.push(name);
+                }
+                // `theirs` 删除的 sheet，`ours` 没有修改时一起删除
+                (Some(base_cells), Some(our_cells), None) => {
+                    if base_cells == our_cells {
+                        self.spreadsheet
+                            .remove_sheet_by_name(&name)
+                            .map_err(|error| {
+                                Error::String(format!("删除 sheet {} 失败! {}", &name, error))
+                            })?;
+                        result.removed_sheets.push(name);
+                    } else {
+                        result.sheet_conflicts.push(name);
+                    }
+                }
+                // `ours` 删除的 sheet，`theirs` 修改过时作为冲突
+                (Some(base_cells), None, Some(their_cells)) if base_cells != their_cells => {
+                    result.sheet_conflicts.push(name);
+                }
+                (base_cells, Some(our_cells), Some(their_cells)) => {
+                    let base_cells = base_cells.unwrap_or_default();
+                    let worksheet = self.worksheet_mut(&name)?;
+                    merge_cells(
+                        worksheet,
+                        &base_cells,
+                        &our_cells,
+                        &their_cells,
+                        &mut result,
+                    );
+                }
+                _ => {}
+            }
+        }
+        debug!(
+            "合并 {} 个单元格，{} 个冲突",
+            result.applied,
+            result.conflicts.len()
+        );
+        Ok(result)
+    }
+}
+
+/// `merge_workbooks` 三方合并，把 `theirs` 相对 `base` 的修改合并到 `ours`。
+///
+/// 三个文件都需要已经打开，合并结果写入 `ours` 且可以撤销，冲突的单元格保留 `ours` 的内容，
+/// 处理冲突后调用 `write_xlsx` 保存。
+#[command]
+#[instrument(skip_all, fields(workbook = %ours, base = %base, theirs = %theirs))]
+pub fn merge_workbooks<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    base: String,
+    ours: String,
+    theirs: String,
+) -> Result<MergeResult, Error> {
+    let result = state.with_workbooks(|workbooks| {
+        let spreadsheet = |path: &String| {
+            workbooks
+                .get(path)
+                .map(|workbook| workbook.spreadsheet().clone())
+                .ok_or_else(|| Error::NotFound(path.clone()))
+        };
+        let base_spreadsheet = spreadsheet(&base)?;
+        let their_spreadsheet = spreadsheet(&theirs)?;
+        let workbook = workbooks
+            .get_mut(&ours)
+            .ok_or_else(|| Error::NotFound(ours.clone()))?;
+        workbook.record(EditScope::Workbook, "merge_workbooks", |workbook| {
+            workbook.merge(&base_spreadsheet, &their_spreadsheet)
+        })
+    })?;
+    emit_changed(&app, &ours, None, None, ChangeKind::Merge);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `book` 生成包含 `sheets` 的文件，每个 sheet 按 (坐标, 值) 写入单元格。
+    fn book(sheets: &[(&str, &[(&str, &str)])]) -> Spreadsheet {
+        let mut spreadsheet = umya_spreadsheet::new_file_empty_worksheet();
+        for (name, cells) in sheets {
+            let worksheet = spreadsheet.new_sheet(*name).unwrap();
+            for (coordinate, value) in cells.iter() {
+                worksheet.get_cell_mut(coordinate).set_value(*value);
+            }
+        }
+        spreadsheet
+    }
+
+    fn value(workbook: &Workbook, sheet_name: &str, coordinate: &str) -> String {
+        workbook
+            .worksheet(sheet_name)
+            .unwrap()
+            .get_value(coordinate)
+    }
+
+    #[test]
+    fn one_sided_changes_are_applied() {
+        let base = book(&[("Sheet1", &[("A1", "a"), ("B1", "b"), ("C1", "c")])]);
+        let mut ours = Workbook::new(book(&[(
+            "Sheet1",
+            &[("A1", "a"), ("B1", "ours"), ("C1", "c")],
+        )]));
+        let theirs = book(&[("Sheet1", &[("A1", "theirs"), ("B1", "b"), ("D1", "new")])]);
+        let result = ours.merge(&base, &theirs).unwrap();
+        assert_eq!(result.applied, 3);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(value(&ours, "Sheet1", "A1"), "theirs");
+        assert_eq!(value(&ours, "Sheet1", "B1"), "ours");
+        // 只有 `theirs` 清空的单元格在 `ours` 中也被清空
+        assert_eq!(value(&ours, "Sheet1", "C1"), "");
+        assert_eq!(value(&ours, "Sheet1", "D1"), "new");
+    }
+
+    #[test]
+    fn changes_on_both_sides_conflict() {
+        let base = book(&[("Sheet1", &[("A1", "a"), ("B1", "b")])]);
+        let mut ours = Workbook::new(book(&[("Sheet1", &[("A1", "ours"), ("B1", "same")])]));
+        let theirs = book(&[("Sheet1", &[("A1", "theirs"), ("B1", "same")])]);
+        let result = ours.merge(&base, &theirs).unwrap();
+        assert_eq!(result.applied, 0);
+        assert_eq!(result.conflicts.len(), 1);
+        let conflict = &result.conflicts[0];
+        assert_eq!(
+            (conflict.sheet.as_str(), conflict.cell.as_str()),
+            ("Sheet1", "A1")
+        );
+        assert_eq!(conflict.base.as_ref().unwrap().value, "a");
+        assert_eq!(conflict.ours.as_ref().unwrap().value, "ours");
+        assert_eq!(conflict.theirs.as_ref().unwrap().value, "theirs");
+        assert_eq!(value(&ours, "Sheet1", "A1"), "ours");
+    }
+
+    #[test]
+    fn deleted_and_modified_sheets_conflict() {
+        let base = book(&[("Kept", &[("A1", "k")]), ("Gone", &[("A1", "g")])]);
+        // `ours` 修改了 `theirs` 删除的 Gone，`theirs` 修改了 `ours` 删除的 Kept
+        let mut ours = Workbook::new(book(&[("Gone", &[("A1", "changed")])]));
+        let theirs = book(&[("Kept", &[("A1", "changed")])]);
+        let result = ours.merge(&base, &theirs).unwrap();
+        assert_eq!(result.sheet_conflicts, vec!["Kept", "Gone"]);
+        assert!(result.removed_sheets.is_empty());
+        assert!(ours.worksheet("Kept").is_err());
+        assert_eq!(value(&ours, "Gone", "A1"), "changed");
+    }
+
+    #[test]
+    fn sheets_added_or_removed_only_in_theirs() {
+        let base = book(&[("Sheet1", &[("A1", "a")]), ("Old", &[("A1", "o")])]);
+        let mut ours = Workbook::new(book(&[("Sheet1", &[("A1", "a")]), ("Old", &[("A1", "o")])]));
+        let theirs = book(&[("Sheet1", &[("A1", "a")]), ("New", &[("B2", "n")])]);
+        let result = ours.merge(&base, &theirs).unwrap();
+        assert_eq!(result.added_sheets, vec!["New"]);
+        assert_eq!(result.removed_sheets, vec!["Old"]);
+        assert_eq!(value(&ours, "New", "B2"), "n");
+        assert!(ours.worksheet("Old").is_err());
+    }
+}
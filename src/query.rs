@@ -0,0 +1,458 @@
+use crate::error::Error;
+use crate::workbook::WorkbookRegistry;
+use rusqlite::hooks::{AuthAction, AuthContext, Authorization};
+use rusqlite::limits::Limit;
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::{params_from_iter, Connection};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path};
+use std::sync::{Arc, Mutex};
+use tauri::{command, AppHandle, Runtime, State, Window};
+use tracing::{debug, instrument};
+use umya_spreadsheet::Worksheet;
+
+/// 查询结果。
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+}
+
+/// `quote_identifier` 生成 SQLite 标识符。
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// `column_names` 读取首行作为列名，空白或重复的列名自动补全。
+fn column_names(worksheet: &Worksheet, highest_column: u32) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for column in 1..=highest_column {
+        let value = worksheet.get_value_by_column_and_row(&column, &1);
+        let mut name = if value.trim().is_empty() {
+            format!("Column{}", column)
+        } else {
+            value.trim().to_string()
+        };
+        let base = name.clone();
+        let mut suffix = 2;
+        while names.iter().any(|n| n.eq_ignore_ascii_case(&name)) {
+            name = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        names.push(name);
+    }
+    names
+}
+
+/// `cell_value` 将单元格的值转换为 SQLite 值，数字按数值保存以便比较和聚合。
+fn cell_value(value: String) -> Value {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return Value::Null;
+    }
+    if let Ok(integer) = trimmed.parse::<i64>() {
+        return Value::Integer(integer);
+    }
+    if let Ok(real) = trimmed.parse::<f64>() {
+        return Value::Real(real);
+    }
+    Value::Text(value)
+}
+
+/// 查询中的一张表，对应一个 sheet。
+struct Table {
+    /// 表名，如 `"sales.xlsx/Sheet1"`。
+    name: String,
+    /// sheet 名称在所有打开的文件中唯一时，以 sheet 名称创建的视图。
+    view: Option<String>,
+}
+
+impl Table {
+    /// `create` 创建表和视图，`columns` 为空时不创建。
+    fn create(&self, connection: &Connection, columns: &[String]) -> Result<(), Error> {
+        if columns.is_empty() {
+            return Ok(());
+        }
+        let definition: Vec<String> = columns.iter().map(|c| quote_identifier(c)).collect();
+        let mut statements = vec![format!(
+            "CREATE TABLE {} ({})",
+            quote_identifier(&self.name),
+            definition.join(", ")
+        )];
+        if let Some(view) = &self.view {
+            statements.push(format!(
+                "CREATE VIEW {} AS SELECT * FROM {}",
+                quote_identifier(view),
+                quote_identifier(&self.name)
+            ));
+        }
+        for statement in statements {
+            connection.execute(&statement, []).map_err(|error| {
+                Error::String(format!("加载 sheet {} 失败! {}", self.name, error))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// `sheet_columns` 读取 sheet 首行作为列名，空的 sheet 没有列。
+fn sheet_columns(worksheet: &Worksheet) -> Vec<String> {
+    column_names(worksheet, worksheet.get_highest_column())
+}
+
+/// `referenced_tables` 在只有表结构的数据库中编译 `sql`，返回语句读取的表名，小写。
+///
+/// 通过视图读取时同时记录视图引用的表，`count(*)` 这样不读取列的查询也会记录表名。
+fn referenced_tables(sql: &str, schema: &[(Table, Vec<String>)]) -> Result<HashSet<String>, Error> {
+    let connection = match Connection::open_in_memory() {
+        Ok(connection) => connection,
+        Err(error) => return Err(Error::String(format!("创建查询失败! {}", error))),
+    };
+    for (table, columns) in schema {
+        table.create(&connection, columns)?;
+    }
+    connection.set_limit(Limit::SQLITE_LIMIT_ATTACHED, 0);
+    let tables = Arc::new(Mutex::new(HashSet::new()));
+    let recorded = Arc::clone(&tables);
+    connection.authorizer(Some(move |context: AuthContext<'_>| {
+        if let AuthAction::Read { table_name, .. } = context.action {
+            if let Ok(mut tables) = recorded.lock() {
+                tables.insert(table_name.to_lowercase());
+            }
+        }
+        authorize(context)
+    }));
+    if let Err(error) = connection.prepare(sql) {
+        return Err(Error::String(format!("查询失败! {}", error)));
+    }
+    let tables = match tables.lock() {
+        Ok(tables) => tables.clone(),
+        Err(_) => return Err(Error::String(String::from("查询失败!"))),
+    };
+    Ok(tables)
+}
+
+/// `load_sheet` 将 sheet 写入 `table`，首行作为列名。
+fn load_sheet(connection: &Connection, table: &Table, worksheet: &Worksheet) -> Result<(), Error> {
+    let (highest_column, highest_row) = worksheet.get_highest_column_and_row();
+    let columns = column_names(worksheet, highest_column);
+    table.create(connection, &columns)?;
+    if columns.is_empty() {
+        return Ok(());
+    }
+    let insert = format!(
+        "INSERT INTO {} VALUES ({})",
+        quote_identifier(&table.name),
+        vec!["?"; columns.len()].join(", ")
+    );
+    let sqlite_error = |error: rusqlite::Error| {
+        Error::String(format!("加载 sheet {} 失败! {}", table.name, error))
+    };
+    let mut statement = connection.prepare(&insert).map_err(sqlite_error)?;
+    for row in 2..=highest_row {
+        let values = (1..=highest_column)
+            .map(|column| cell_value(worksheet.get_value_by_column_and_row(&column, &row)));
+        statement
+            .execute(params_from_iter(values))
+            .map_err(sqlite_error)?;
+    }
+    Ok(())
+}
+
+/// `workbook_aliases` 为每个文件生成查询用的别名，键为文件路径。
+///
+/// 别名默认为文件名，文件名相同时依次加上上级目录，直到各别名互不相同，如 `"2023/sales.xlsx"`。
+fn workbook_aliases<'a>(paths: impl Iterator<Item = &'a String>) -> HashMap<String, String> {
+    let components: Vec<(&String, Vec<String>)> = paths
+        .map(|path| {
+            let parts = Path::new(path)
+                .components()
+                .filter_map(|component| match component {
+                    Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+                    _ => None,
+                })
+                .collect();
+            (path, parts)
+        })
+        .collect();
+    let alias =
+        |parts: &[String], depth: usize| parts[parts.len() - depth.min(parts.len())..].join("/");
+    let mut depths = vec![1; components.len()];
+    loop {
+        let aliases: Vec<String> = components
+            .iter()
+            .zip(&depths)
+            .map(|((_, parts), depth)| alias(parts, *depth).to_lowercase())
+            .collect();
+        let mut changed = false;
+        for (i, (_, parts)) in components.iter().enumerate() {
+            let duplicated = aliases
+                .iter()
+                .enumerate()
+                .any(|(j, other)| i != j && *other == aliases[i]);
+            if duplicated && depths[i] < parts.len() {
+                depths[i] += 1;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    components
+        .iter()
+        .zip(&depths)
+        .map(|((path, parts), depth)| {
+            let name = if parts.is_empty() {
+                path.to_string()
+            } else {
+                alias(parts, *depth)
+            };
+            (path.to_string(), name)
+        })
+        .collect()
+}
+
+/// `authorize` 只允许读取数据的操作，拒绝 ATTACH、PRAGMA 和所有写入。
+fn authorize(context: AuthContext<'_>) -> Authorization {
+    match context.action {
+        AuthAction::Select
+        | AuthAction::Read { .. }
+        | AuthAction::Function { .. }
+        | AuthAction::Recursive => Authorization::Allow,
+        _ => Authorization::Deny,
+    }
+}
+
+/// `json_value` 将 SQLite 值转换为 JSON。
+fn json_value(value: ValueRef<'_>) -> serde_json::Value {
+    match value {
+        ValueRef::Null => serde_json::Value::Null,
+        ValueRef::Integer(integer) => serde_json::Value::from(integer),
+        ValueRef::Real(real) => serde_json::Number::from_f64(real)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        ValueRef::Text(text) | ValueRef::Blob(text) => {
+            serde_json::Value::String(String::from_utf8_lossy(text).to_string())
+        }
+    }
+}
+
+impl WorkbookRegistry {
+    /// `query` 使用 SQL 查询所有打开的 xlsx 文件。
+    ///
+    /// 每个 sheet 都是一张表，首行为列名，表名为 `"文件别名/sheet 名称"`，如 `"sales.xlsx/Sheet1"`；
+    /// 文件别名默认为文件名，不同目录下有同名文件时加上上级目录，如 `"2023/sales.xlsx/Sheet1"`。
+    /// sheet 名称在所有打开的文件中唯一时，也可以直接使用 `"Sheet1"`。
+    /// 支持 SQLite 的 SELECT/WHERE/GROUP BY/ORDER BY/LIMIT 和 JOIN，ATTACH、PRAGMA 和写入语句会被拒绝。
+    pub fn query(&self, sql: &str) -> Result<QueryResult, Error> {
+        // 在锁内用表结构找出查询读取的 sheet 并复制，释放锁后再加载数据。
+        let sheets = self.with_workbooks(|map| {
+            let mut sheet_counts: HashMap<String, usize> = HashMap::new();
+            for workbook in map.values() {
+                for worksheet in workbook.spreadsheet.get_sheet_collection() {
+                    *sheet_counts
+                        .entry(worksheet.get_name().to_lowercase())
+                        .or_insert(0) += 1;
+                }
+            }
+            let aliases = workbook_aliases(map.keys());
+            let mut schema = Vec::new();
+            let mut worksheets = Vec::new();
+            for (path, workbook) in map.iter() {
+                for worksheet in workbook.spreadsheet.get_sheet_collection() {
+                    let sheet_name = worksheet.get_name();
+                    let table = Table {
+                        name: format!("{}/{}", aliases[path], sheet_name),
+                        view: Some(sheet_name.to_string())
+                            .filter(|_| sheet_counts[&sheet_name.to_lowercase()] == 1),
+                    };
+                    schema.push((table, sheet_columns(worksheet)));
+                    worksheets.push(worksheet);
+                }
+            }
+            let referenced = referenced_tables(sql, &schema)?;
+            Ok(schema
+                .into_iter()
+                .zip(worksheets)
+                .filter(|((table, _), _)| referenced.contains(&table.name.to_lowercase()))
+                .map(|((table, _), worksheet)| (table, worksheet.clone()))
+                .collect::<Vec<_>>())
+        })?;
+
+        let connection = match Connection::open_in_memory() {
+            Ok(connection) => connection,
+            Err(error) => return Err(Error::String(format!("创建查询失败! {}", error))),
+        };
+        for (table, worksheet) in &sheets {
+            load_sheet(&connection, table, worksheet)?;
+        }
+
+        // 数据加载完成后禁止附加数据库，并且只授权读取操作，授权在编译语句时检查。
+        connection.set_limit(Limit::SQLITE_LIMIT_ATTACHED, 0);
+        connection.authorizer(Some(authorize));
+        let query_error = |error: rusqlite::Error| Error::String(format!("查询失败! {}", error));
+        let mut statement = connection.prepare(sql).map_err(query_error)?;
+        let columns: Vec<String> = statement
+            .column_names()
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        let mut rows = Vec::new();
+        let mut result = statement.query([]).map_err(query_error)?;
+        while let Some(row) = result.next().map_err(query_error)? {
+            let mut values = Vec::new();
+            for i in 0..columns.len() {
+                values.push(json_value(row.get_ref(i).map_err(query_error)?));
+            }
+            rows.push(values);
+        }
+        debug!("查询 {} 共 {} 行", sql, rows.len());
+        Ok(QueryResult { columns, rows })
+    }
+}
+
+/// `query` 使用 SQL 查询所有打开的 xlsx 文件。
+///
+/// 表名为 `"文件别名/sheet 名称"`，文件别名为文件名，同名文件加上上级目录以示区分，
+/// sheet 名称在所有打开的文件中唯一时，也可以直接使用 sheet 名称。
+#[command]
+#[instrument(level = "debug", skip_all, fields(sql = %sql))]
+pub fn query<R: Runtime>(
+    _app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    sql: String,
+) -> Result<QueryResult, Error> {
+    state.query(&sql)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aliases_are_unique() {
+        let paths = [
+            String::from("/data/2023/sales.xlsx"),
+            String::from("/data/2024/sales.xlsx"),
+            String::from("/data/2024/costs.xlsx"),
+        ];
+        let aliases = workbook_aliases(paths.iter());
+        assert_eq!(aliases[&paths[0]], "2023/sales.xlsx");
+        assert_eq!(aliases[&paths[1]], "2024/sales.xlsx");
+        assert_eq!(aliases[&paths[2]], "costs.xlsx");
+    }
+
+    #[test]
+    fn referenced_tables_resolve_views_and_ignore_literals() {
+        let table = |name: &str, view: Option<&str>| {
+            (
+                Table {
+                    name: name.to_string(),
+                    view: view.map(str::to_string),
+                },
+                vec![String::from("id"), String::from("name")],
+            )
+        };
+        let schema = [
+            table("a.xlsx/Sheet1", Some("Sheet1")),
+            table("a.xlsx/Other", Some("Other")),
+            table("b.xlsx/Data", None),
+            table("c.xlsx/Data", None),
+        ];
+        let cases = [
+            (
+                "SELECT count(*) FROM Sheet1",
+                vec!["a.xlsx/sheet1"],
+            ),
+            (
+                "SELECT s.name FROM sheet1 s JOIN \"b.xlsx/Data\" d USING (id) WHERE d.name = 'Other'",
+                vec!["a.xlsx/sheet1", "b.xlsx/data", "sheet1"],
+            ),
+            (
+                "WITH t AS (SELECT id FROM \"c.xlsx/Data\") SELECT * FROM t",
+                vec!["c.xlsx/data"],
+            ),
+        ];
+        for (sql, expected) in cases.iter() {
+            let mut tables: Vec<String> = referenced_tables(sql, &schema)
+                .unwrap()
+                .into_iter()
+                .collect();
+            tables.sort();
+            assert_eq!(tables, *expected, "{}", sql);
+        }
+        assert!(referenced_tables("SELECT * FROM Missing", &schema).is_err());
+        assert!(referenced_tables("DELETE FROM Sheet1", &schema).is_err());
+    }
+
+    #[test]
+    fn query_open_workbooks() {
+        let registry = WorkbookRegistry::new();
+        for path in ["/data/sales.xlsx", "/data/costs.xlsx"].iter() {
+            registry.create(path).unwrap();
+        }
+        registry
+            .with_workbook("/data/sales.xlsx", |workbook| {
+                let worksheet = workbook.worksheet_mut("Sheet1")?;
+                for (row, values) in [
+                    ["region", "amount"],
+                    ["east", "10"],
+                    ["west", "5"],
+                    ["east", "2.5"],
+                ]
+                .iter()
+                .enumerate()
+                {
+                    for (column, value) in values.iter().enumerate() {
+                        worksheet
+                            .get_cell_by_column_and_row_mut(&(column as u32 + 1), &(row as u32 + 1))
+                            .set_value(*value);
+                    }
+                }
+                Ok(())
+            })
+            .unwrap();
+        let result = registry
+            .query(
+                "SELECT region, sum(amount) AS total FROM \"sales.xlsx/Sheet1\" \
+                 GROUP BY region ORDER BY region",
+            )
+            .unwrap();
+        assert_eq!(result.columns, vec!["region", "total"]);
+        assert_eq!(
+            result.rows,
+            vec![
+                vec![serde_json::json!("east"), serde_json::json!(12.5)],
+                vec![serde_json::json!("west"), serde_json::json!(5)],
+            ]
+        );
+        // 两个文件都有 Sheet1，不能直接使用 sheet 名称
+        assert!(registry.query("SELECT * FROM Sheet1").is_err());
+    }
+
+    #[test]
+    fn only_reads_are_authorized() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .execute("CREATE TABLE items (name TEXT)", [])
+            .unwrap();
+        connection.set_limit(Limit::SQLITE_LIMIT_ATTACHED, 0);
+        connection.authorizer(Some(authorize));
+        assert!(connection.prepare("SELECT upper(name) FROM items").is_ok());
+        for sql in [
+            "ATTACH DATABASE 'other.db' AS other",
+            "INSERT INTO items VALUES ('a')",
+            "DELETE FROM items",
+            "CREATE TABLE copy (name TEXT)",
+            "PRAGMA journal_mode = WAL",
+        ]
+        .iter()
+        {
+            assert!(connection.prepare(sql).is_err(), "{}", sql);
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use crate::error::Error;
+use std::fmt;
+use std::ops::RangeInclusive;
+use umya_spreadsheet::Worksheet;
+
+/// Excel 最大列数。
+pub const MAX_COLUMN: u32 = 16384;
+/// Excel 最大行数。
+pub const MAX_ROW: u32 = 1048576;
+
+/// `CellRange` 单元格区域，坐标为 `(column, row)`，均从 1 开始。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CellRange {
+    pub start: (u32, u32),
+    pub end: (u32, u32),
+}
+
+impl CellRange {
+    /// `new` 根据两个角的坐标创建区域，坐标顺序不限。
+    pub fn new(start: (u32, u32), end: (u32, u32)) -> Self {
+        CellRange {
+            start: (start.0.min(end.0), start.1.min(end.1)),
+            end: (start.0.max(end.0), start.1.max(end.1)),
+        }
+    }
+
+    /// `parse` 解析 `"A1:C10"` 形式的区域字符串。
+    ///
+    /// 支持 `$A$1`、`Sheet1!A1:B2` 以及省略行或列的写法，如 `"A:C"`、`"A1:F"`、`"2:5"`，
+    /// 省略的结束行列使用 `highest` (column, row) 补齐。
+    pub fn parse(range: &str, highest: (u32, u32)) -> Result<Self, Error> {
+        let range = match range.rfind('!') {
+            Some(index) => &range[index + 1..],
+            None => range,
+        };
+        let invalid = || Error::String(format!("区域 {} 格式错误!", range));
+        let mut parts = range.split(':');
+        let start = parts.next().ok_or_else(invalid)?;
+        let end = parts.next().unwrap_or(start);
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        let (start_column, start_row) = parse_reference(start).ok_or_else(invalid)?;
+        let (end_column, end_row) = parse_reference(end).ok_or_else(invalid)?;
+        let start = (start_column.unwrap_or(1), start_row.unwrap_or(1));
+        let end = (
+            end_column.unwrap_or_else(|| highest.0.max(start.0)),
+            end_row.unwrap_or_else(|| highest.1.max(start.1)),
+        );
+        Ok(CellRange::new(start, end))
+    }
+
+    /// `columns` 区域包含的列索引。
+    pub fn columns(&self) -> RangeInclusive<u32> {
+        self.start.0..=self.end.0
+    }
+
+    /// `rows` 区域包含的行索引。
+    pub fn rows(&self) -> RangeInclusive<u32> {
+        self.start.1..=self.end.1
+    }
+
+    /// `width` 区域列数。
+    pub fn width(&self) -> u32 {
+        self.end.0 - self.start.0 + 1
+    }
+
+    /// `height` 区域行数。
+    pub fn height(&self) -> u32 {
+        self.end.1 - self.start.1 + 1
+    }
+
+    /// `contains` 判断 `(column, row)` 是否在区域内。
+    pub fn contains(&self, column: u32, row: u32) -> bool {
+        self.columns().contains(&column) && self.rows().contains(&row)
+    }
+
+    /// `to_absolute` 生成带 sheet 名称的绝对引用，如 `Sheet1!$A$1:$C$10`。
+    ///
+    /// umya 按 `!` 拆分引用后直接用引号内的文本查找 sheet，因此 sheet 名称不加引号，
+    /// 写入文件时 umya 会为含空格的名称加上引号。
+    pub fn to_absolute(self, sheet_name: &str) -> String {
+        format!(
+            "{}!${}${}:${}${}",
+            sheet_name,
+            string_from_column_index(self.start.0),
+            self.start.1,
+            string_from_column_index(self.end.0),
+            self.end.1
+        )
+    }
+}
+
+impl fmt::Display for CellRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", coordinate(self.start.0, self.start.1))
+        } else {
+            write!(
+                f,
+                "{}:{}",
+                coordinate(self.start.0, self.start.1),
+                coordinate(self.end.0, self.end.1)
+            )
+        }
+    }
+}
+
+/// `split_sheet` 拆分 `Sheet1!A1:B2` 形式的引用为 (sheet 名称, 区域)，sheet 名称去掉引号。
+pub fn split_sheet(reference: &str) -> (Option<String>, &str) {
+    let reference = reference.trim().trim_start_matches('=');
+    match reference.rfind('!') {
+        Some(index) => (
+            Some(reference[..index].trim_matches('\'').replace("''", "'")),
+            &reference[index + 1..],
+        ),
+        None => (None, reference),
+    }
+}
+
+/// `resolve` 按 `worksheet` 的实际大小解析区域字符串，`range` 也可以是保存在该 sheet 上的名称。
+///
+/// 只能访问单个 sheet 时使用，`Workbook::resolve_range` 会按作用范围在整个文件中查找名称。
+pub fn resolve(worksheet: &Worksheet, range: &str) -> Result<CellRange, Error> {
+    let highest = worksheet.get_highest_column_and_row();
+    let defined_name = worksheet
+        .get_defined_names()
+        .iter()
+        .find(|defined_name| defined_name.get_name().eq_ignore_ascii_case(range.trim()));
+    match defined_name {
+        Some(defined_name) => CellRange::parse(&defined_name.get_address(), highest),
+        None => CellRange::parse(range, highest),
+    }
+}
+
+/// `column_index_from_string` 列字母转列索引，如 `"AB"` -> `28`。
+pub fn column_index_from_string(column: &str) -> Option<u32> {
+    if column.is_empty() {
+        return None;
+    }
+    let mut index: u32 = 0;
+    for c in column.chars() {
+        if !c.is_ascii_alphabetic() {
+            return None;
+        }
+        let value = c.to_ascii_uppercase() as u32 - 'A' as u32 + 1;
+        index = index.checked_mul(26)?.checked_add(value)?;
+    }
+    Some(index)
+}
+
+/// `string_from_column_index` 列索引转列字母，如 `28` -> `"AB"`。
+pub fn string_from_column_index(index: u32) -> String {
+    let mut index = index;
+    let mut column = Vec::new();
+    while index > 0 {
+        let rem = (index - 1) % 26;
+        column.push((b'A' + rem as u8) as char);
+        index = (index - 1) / 26;
+    }
+    column.iter().rev().collect()
+}
+
+/// `coordinate` 生成单元格坐标字符串，如 `(2, 3)` -> `"B3"`。
+pub fn coordinate(column: u32, row: u32) -> String {
+    format!("{}{}", string_from_column_index(column), row)
+}
+
+/// `parse_coordinate` 解析单元格坐标字符串，如 `"B3"` -> `(2, 3)`。
+pub fn parse_coordinate(value: &str) -> Option<(u32, u32)> {
+    match parse_reference(value)? {
+        (Some(column), Some(row)) => Some((column, row)),
+        _ => None,
+    }
+}
+
+/// `parse_reference` 解析单个引用，列或行可以省略。
+fn parse_reference(value: &str) -> Option<(Option<u32>, Option<u32>)> {
+    let value = value.trim().replace('$', "");
+    let split = value
+        .find(|c: char| c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (letters, digits) = value.split_at(split);
+    let column = if letters.is_empty() {
+        None
+    } else {
+        match column_index_from_string(letters)? {
+            column if column <= MAX_COLUMN => Some(column),
+            _ => return None,
+        }
+    };
+    let row = if digits.is_empty() {
+        None
+    } else {
+        match digits.parse::<u32>() {
+            Ok(row) if row > 0 && row <= MAX_ROW => Some(row),
+            _ => return None,
+        }
+    };
+    if column.is_none() && row.is_none() {
+        return None;
+    }
+    Some((column, row))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ranges() {
+        let highest = (6, 20);
+        let cases = [
+            ("A1:C10", (1, 1), (3, 10)),
+            ("C10:A1", (1, 1), (3, 10)),
+            ("$A$1:$B$2", (1, 1), (2, 2)),
+            ("B3", (2, 3), (2, 3)),
+            ("Sheet1!A1:B2", (1, 1), (2, 2)),
+            ("'My Sheet'!A1:B2", (1, 1), (2, 2)),
+            ("A:C", (1, 1), (3, 20)),
+            ("A1:F", (1, 1), (6, 20)),
+            ("2:5", (1, 2), (6, 5)),
+            ("H:H", (8, 1), (8, 20)),
+            ("XFD1048576", (16384, 1048576), (16384, 1048576)),
+        ];
+        for (range, start, end) in cases.iter() {
+            let parsed = CellRange::parse(range, highest).unwrap();
+            assert_eq!((parsed.start, parsed.end), (*start, *end), "{}", range);
+        }
+        for range in ["", "A1:B2:C3", "XFE1", "A0", "A1048577", "1A", "A1:?"].iter() {
+            assert!(CellRange::parse(range, highest).is_err(), "{}", range);
+        }
+    }
+
+    #[test]
+    fn format_ranges() {
+        let range = CellRange::new((28, 10), (1, 1));
+        assert_eq!(range.to_string(), "A1:AB10");
+        assert_eq!(CellRange::new((2, 3), (2, 3)).to_string(), "B3");
+        assert_eq!(range.to_absolute("My Sheet"), "My Sheet!$A$1:$AB$10");
+        assert_eq!((range.width(), range.height()), (28, 10));
+        assert!(range.contains(28, 10) && !range.contains(29, 1));
+    }
+
+    #[test]
+    fn columns_and_coordinates() {
+        for (index, letters) in [(1, "A"), (26, "Z"), (27, "AA"), (16384, "XFD")].iter() {
+            assert_eq!(string_from_column_index(*index), *letters);
+            assert_eq!(column_index_from_string(letters), Some(*index));
+        }
+        assert_eq!(column_index_from_string("ab"), Some(28));
+        assert_eq!(column_index_from_string("A1"), None);
+        assert_eq!(parse_coordinate("$B$3"), Some((2, 3)));
+        assert_eq!(parse_coordinate("B"), None);
+    }
+
+    #[test]
+    fn split_sheet_names() {
+        assert_eq!(split_sheet("A1:B2"), (None, "A1:B2"));
+        assert_eq!(
+            split_sheet("=Sheet1!$A$1"),
+            (Some(String::from("Sheet1")), "$A$1")
+        );
+        assert_eq!(
+            split_sheet("'Bob''s Data'!A1:C3"),
+            (Some(String::from("Bob's Data")), "A1:C3")
+        );
+    }
+
+    #[test]
+    fn resolve_names_on_worksheet() {
+        let mut worksheet = Worksheet::default();
+        worksheet
+            .get_cell_by_column_and_row_mut(&4, &12)
+            .set_value("x");
+        worksheet
+            .add_defined_name("Items", "Sheet1!$B$2:$C$5")
+            .unwrap();
+        let range = resolve(&worksheet, "items").unwrap();
+        assert_eq!((range.start, range.end), ((2, 2), (3, 5)));
+        let range = resolve(&worksheet, "A:B").unwrap();
+        assert_eq!((range.start, range.end), ((1, 1), (2, 12)));
+    }
+}
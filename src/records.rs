@@ -0,0 +1,640 @@
+use crate::error::Error;
+use crate::range::{self, coordinate};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, Visitor};
+use serde::ser::{self, Impossible, SerializeMap, SerializeStruct};
+use serde::{forward_to_deserialize_any, Serialize};
+use std::fmt;
+use umya_spreadsheet::Worksheet;
+
+/// `Records` 以表头为键，在 sheet 的行和 serde 类型之间转换。
+///
+/// ```ignore
+/// use tauri_plugin_spreadsheet::Records;
+///
+/// let invoices: Vec<Invoice> = worksheet.read_records("A1:F")?;
+/// worksheet.write_records(&invoices)?;
+/// ```
+pub trait Records {
+    /// `read_records` 读取区域中的记录，区域首行为表头，所有单元格都为空的行会被跳过。
+    ///
+    /// 空白单元格对应 `Option` 字段的 `None`，类型不匹配时返回 [`Error::Cell`]。
+    fn read_records<T: DeserializeOwned>(&self, range: &str) -> Result<Vec<T>, Error>;
+
+    /// `write_records` 从 A1 开始写入表头和记录。
+    fn write_records<T: Serialize>(&mut self, records: &[T]) -> Result<(), Error>;
+
+    /// `write_records_at` 从 `start` (column, row) 开始写入表头和记录。
+    ///
+    /// 表头为所有记录字段按首次出现的顺序合并，`None` 写入空白单元格。
+    fn write_records_at<T: Serialize>(
+        &mut self,
+        start: (u32, u32),
+        records: &[T],
+    ) -> Result<(), Error>;
+}
+
+impl Records for Worksheet {
+    fn read_records<T: DeserializeOwned>(&self, range: &str) -> Result<Vec<T>, Error> {
+        let range = range::resolve(self, range)?;
+        let headers: Vec<(u32, String)> = range
+            .columns()
+            .map(|column| {
+                let header = self.get_value_by_column_and_row(&column, &range.start.1);
+                (column, header.trim().to_string())
+            })
+            .filter(|(_, header)| !header.is_empty())
+            .collect();
+        let mut records = Vec::new();
+        for row in range.start.1 + 1..=range.end.1 {
+            let values: Vec<String> = headers
+                .iter()
+                .map(|(column, _)| self.get_value_by_column_and_row(column, &row))
+                .collect();
+            if values.iter().all(|value| value.trim().is_empty()) {
+                continue;
+            }
+            let deserializer = RowDeserializer {
+                headers: &headers,
+                values: &values,
+                row,
+                index: 0,
+            };
+            match T::deserialize(deserializer) {
+                Ok(record) => records.push(record),
+                Err(error) => {
+                    return Err(Error::Cell {
+                        cell: error.cell.unwrap_or_else(|| format!("第 {} 行", row)),
+                        message: error.message,
+                    })
+                }
+            }
+        }
+        Ok(records)
+    }
+
+    fn write_records<T: Serialize>(&mut self, records: &[T]) -> Result<(), Error> {
+        self.write_records_at((1, 1), records)
+    }
+
+    fn write_records_at<T: Serialize>(
+        &mut self,
+        start: (u32, u32),
+        records: &[T],
+    ) -> Result<(), Error> {
+        let (start_column, start_row) = start;
+        let mut headers: Vec<String> = Vec::new();
+        let mut rows: Vec<Fields> = Vec::new();
+        for (i, record) in records.iter().enumerate() {
+            let row = start_row + 1 + i as u32;
+            let fields = record
+                .serialize(RecordSerializer)
+                .map_err(|error| Error::Cell {
+                    cell: format!("第 {} 行", row),
+                    message: error.message,
+                })?;
+            for (key, _) in &fields {
+                if !headers.contains(key) {
+                    headers.push(key.clone());
+                }
+            }
+            rows.push(fields);
+        }
+
+        for (offset, header) in headers.iter().enumerate() {
+            let column = start_column + offset as u32;
+            self.get_cell_by_column_and_row_mut(&column, &start_row)
+                .set_value(header);
+            for (i, fields) in rows.iter().enumerate() {
+                let row = start_row + 1 + i as u32;
+                let field = fields.iter().find(|(key, _)| key == header);
+                let value = match field.map(|(_, value)| value) {
+                    None | Some(serde_json::Value::Null) => String::new(),
+                    Some(serde_json::Value::String(value)) => value.clone(),
+                    Some(serde_json::Value::Bool(value)) => {
+                        String::from(if *value { "TRUE" } else { "FALSE" })
+                    }
+                    Some(serde_json::Value::Number(value)) => value.to_string(),
+                    Some(_) => {
+                        return Err(Error::Cell {
+                            cell: coordinate(column, row),
+                            message: format!("字段 `{}` 不是单个值!", header),
+                        })
+                    }
+                };
+                self.get_cell_by_column_and_row_mut(&column, &row)
+                    .set_value(value);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 记录转换错误，`cell` 为出错的单元格坐标。
+#[derive(Debug)]
+struct RecordError {
+    cell: Option<String>,
+    message: String,
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+impl de::Error for RecordError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        RecordError {
+            cell: None,
+            message: msg.to_string(),
+        }
+    }
+}
+
+impl ser::Error for RecordError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        RecordError {
+            cell: None,
+            message: msg.to_string(),
+        }
+    }
+}
+
+/// 一条记录的字段和值，按序列化的顺序保存，决定写入时表头的顺序。
+type Fields = Vec<(String, serde_json::Value)>;
+
+/// 将结构体或 map 序列化为 [`Fields`]，其他类型返回错误。
+struct RecordSerializer;
+
+/// `not_record` 记录不是结构体或 map 时的错误。
+fn not_record() -> RecordError {
+    RecordError {
+        cell: None,
+        message: String::from("记录必须序列化为结构体或 map!"),
+    }
+}
+
+macro_rules! serialize_not_record {
+    ($($method:ident($($arg:ident: $ty:ty),*),)*) => {
+        $(
+            fn $method(self, $(_: $ty),*) -> Result<Self::Ok, Self::Error> {
+                Err(not_record())
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for RecordSerializer {
+    type Ok = Fields;
+    type Error = RecordError;
+    type SerializeSeq = Impossible<Fields, RecordError>;
+    type SerializeTuple = Impossible<Fields, RecordError>;
+    type SerializeTupleStruct = Impossible<Fields, RecordError>;
+    type SerializeTupleVariant = Impossible<Fields, RecordError>;
+    type SerializeMap = FieldsSerializer;
+    type SerializeStruct = FieldsSerializer;
+    type SerializeStructVariant = Impossible<Fields, RecordError>;
+
+    serialize_not_record! {
+        serialize_bool(v: bool),
+        serialize_i8(v: i8),
+        serialize_i16(v: i16),
+        serialize_i32(v: i32),
+        serialize_i64(v: i64),
+        serialize_u8(v: u8),
+        serialize_u16(v: u16),
+        serialize_u32(v: u32),
+        serialize_u64(v: u64),
+        serialize_f32(v: f32),
+        serialize_f64(v: f64),
+        serialize_char(v: char),
+        serialize_str(v: &str),
+        serialize_bytes(v: &[u8]),
+        serialize_none(),
+        serialize_unit(),
+        serialize_unit_struct(name: &'static str),
+        serialize_unit_variant(name: &'static str, index: u32, variant: &'static str),
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Fields, RecordError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Fields, RecordError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Fields, RecordError> {
+        Err(not_record())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, RecordError> {
+        Err(not_record())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, RecordError> {
+        Err(not_record())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, RecordError> {
+        Err(not_record())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, RecordError> {
+        Err(not_record())
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<FieldsSerializer, RecordError> {
+        Ok(FieldsSerializer::new(len.unwrap_or(0)))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<FieldsSerializer, RecordError> {
+        Ok(FieldsSerializer::new(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, RecordError> {
+        Err(not_record())
+    }
+}
+
+/// 按顺序收集结构体字段或 map 的键值。
+struct FieldsSerializer {
+    fields: Fields,
+    key: Option<String>,
+}
+
+impl FieldsSerializer {
+    fn new(len: usize) -> Self {
+        FieldsSerializer {
+            fields: Vec::with_capacity(len),
+            key: None,
+        }
+    }
+
+    fn push<T: ?Sized + Serialize>(&mut self, key: String, value: &T) -> Result<(), RecordError> {
+        let value = serde_json::to_value(value).map_err(|error| RecordError {
+            cell: None,
+            message: format!("字段 `{}`: {}", key, error),
+        })?;
+        self.fields.push((key, value));
+        Ok(())
+    }
+}
+
+impl SerializeMap for FieldsSerializer {
+    type Ok = Fields;
+    type Error = RecordError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), RecordError> {
+        let key = match serde_json::to_value(key) {
+            Ok(serde_json::Value::String(key)) => key,
+            Ok(serde_json::Value::Number(key)) => key.to_string(),
+            Ok(serde_json::Value::Bool(key)) => key.to_string(),
+            _ => {
+                return Err(RecordError {
+                    cell: None,
+                    message: String::from("map 的键必须是字符串或数字!"),
+                })
+            }
+        };
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), RecordError> {
+        let key = self.key.take().unwrap_or_default();
+        self.push(key, value)
+    }
+
+    fn end(self) -> Result<Fields, RecordError> {
+        Ok(self.fields)
+    }
+}
+
+impl SerializeStruct for FieldsSerializer {
+    type Ok = Fields;
+    type Error = RecordError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), RecordError> {
+        self.push(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Fields, RecordError> {
+        Ok(self.fields)
+    }
+}
+
+/// 一行记录，按表头名称作为 map 反序列化。
+struct RowDeserializer<'a> {
+    headers: &'a [(u32, String)],
+    values: &'a [String],
+    row: u32,
+    index: usize,
+}
+
+impl<'de, 'a> de::Deserializer<'de> for RowDeserializer<'a> {
+    type Error = RecordError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de, 'a> MapAccess<'de> for RowDeserializer<'a> {
+    type Error = RecordError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.headers.get(self.index) {
+            Some((_, header)) => seed
+                .deserialize(header.as_str().into_deserializer())
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (column, header) = &self.headers[self.index];
+        let value = &self.values[self.index];
+        self.index += 1;
+        seed.deserialize(CellDeserializer { value })
+            .map_err(|error| RecordError {
+                cell: Some(error.cell.unwrap_or_else(|| coordinate(*column, self.row))),
+                message: format!("字段 `{}`: {}", header, error.message),
+            })
+    }
+}
+
+/// 单个单元格的值，数字和布尔类型从文本解析。
+struct CellDeserializer<'a> {
+    value: &'a str,
+}
+
+impl<'a> CellDeserializer<'a> {
+    fn parse<T: std::str::FromStr>(&self, expected: &str) -> Result<T, RecordError> {
+        self.value.trim().parse::<T>().map_err(|_| RecordError {
+            cell: None,
+            message: format!("无法将 {:?} 转换为 {}", self.value, expected),
+        })
+    }
+}
+
+macro_rules! deserialize_number {
+    ($($method:ident => $visit:ident: $ty:ty,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                visitor.$visit(self.parse::<$ty>(stringify!($ty))?)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> de::Deserializer<'de> for CellDeserializer<'a> {
+    type Error = RecordError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_str(self.value)
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value.trim().to_lowercase().as_str() {
+            "true" | "1" | "yes" => visitor.visit_bool(true),
+            "false" | "0" | "no" => visitor.visit_bool(false),
+            _ => Err(RecordError {
+                cell: None,
+                message: format!("无法将 {:?} 转换为 bool", self.value),
+            }),
+        }
+    }
+
+    deserialize_number! {
+        deserialize_i8 => visit_i8: i8,
+        deserialize_i16 => visit_i16: i16,
+        deserialize_i32 => visit_i32: i32,
+        deserialize_i64 => visit_i64: i64,
+        deserialize_u8 => visit_u8: u8,
+        deserialize_u16 => visit_u16: u16,
+        deserialize_u32 => visit_u32: u32,
+        deserialize_u64 => visit_u64: u64,
+        deserialize_f32 => visit_f32: f32,
+        deserialize_f64 => visit_f64: f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        if self.value.trim().is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_enum(self.value.trim().into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Item {
+        name: String,
+        quantity: u32,
+        price: f64,
+        paid: bool,
+        note: Option<String>,
+    }
+
+    fn sheet(rows: &[&[&str]]) -> Worksheet {
+        let mut worksheet = Worksheet::default();
+        for (row, values) in rows.iter().enumerate() {
+            for (column, value) in values.iter().enumerate() {
+                if !value.is_empty() {
+                    worksheet
+                        .get_cell_by_column_and_row_mut(&(column as u32 + 1), &(row as u32 + 1))
+                        .set_value(*value);
+                }
+            }
+        }
+        worksheet
+    }
+
+    #[test]
+    fn read_typed_rows() {
+        let worksheet = sheet(&[
+            &["name", "quantity", "price", "paid", "note", "extra"],
+            &["Widget", " 3 ", "1.5", "yes", "", "ignored"],
+            &["", "", "", "", "", ""],
+            &["Gadget", "10", "2", "FALSE", "rush", ""],
+        ]);
+        let items: Vec<Item> = worksheet.read_records("A1:F").unwrap();
+        assert_eq!(
+            items,
+            vec![
+                Item {
+                    name: String::from("Widget"),
+                    quantity: 3,
+                    price: 1.5,
+                    paid: true,
+                    note: None,
+                },
+                Item {
+                    name: String::from("Gadget"),
+                    quantity: 10,
+                    price: 2.0,
+                    paid: false,
+                    note: Some(String::from("rush")),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn read_reports_cell_errors() {
+        let worksheet = sheet(&[
+            &["name", "quantity", "price", "paid"],
+            &["Widget", "three", "1.5", "yes"],
+        ]);
+        match worksheet.read_records::<Item>("A1:D") {
+            Err(Error::Cell { cell, message }) => {
+                assert_eq!(cell, "B2");
+                assert!(message.contains("quantity"), "{}", message);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let worksheet = sheet(&[&["name", "paid"], &["Widget", "maybe"]]);
+        let result = worksheet.read_records::<BTreeMap<String, bool>>("B1:B");
+        assert!(matches!(result, Err(Error::Cell { cell, .. }) if cell == "B2"));
+
+        let worksheet = sheet(&[&["name", "quantity"], &["Widget", "3"]]);
+        let result = worksheet.read_records::<Item>("A1:B");
+        assert!(matches!(result, Err(Error::Cell { cell, .. }) if cell == "第 2 行"));
+    }
+
+    #[test]
+    fn write_keeps_field_order() {
+        #[derive(Serialize)]
+        struct Row {
+            zeta: &'static str,
+            alpha: Option<i32>,
+            paid: bool,
+        }
+
+        let mut worksheet = Worksheet::default();
+        let rows = [
+            Row {
+                zeta: "a",
+                alpha: Some(2),
+                paid: true,
+            },
+            Row {
+                zeta: "b",
+                alpha: None,
+                paid: false,
+            },
+        ];
+        worksheet.write_records_at((2, 3), &rows).unwrap();
+        let value = |column: u32, row: u32| worksheet.get_value_by_column_and_row(&column, &row);
+        assert_eq!(
+            (value(2, 3), value(3, 3), value(4, 3)),
+            (
+                String::from("zeta"),
+                String::from("alpha"),
+                String::from("paid")
+            )
+        );
+        assert_eq!(value(3, 4), "2");
+        assert_eq!(value(4, 4), "TRUE");
+        assert_eq!(value(3, 5), "");
+    }
+
+    #[test]
+    fn write_merges_headers_and_rejects_non_records() {
+        let mut first = BTreeMap::new();
+        first.insert("b", 1);
+        let mut second = BTreeMap::new();
+        second.insert("a", 2);
+        second.insert("b", 3);
+        let mut worksheet = Worksheet::default();
+        worksheet.write_records(&[first, second]).unwrap();
+        assert_eq!(worksheet.get_value_by_column_and_row(&1, &1), "b");
+        assert_eq!(worksheet.get_value_by_column_and_row(&2, &1), "a");
+        assert_eq!(worksheet.get_value_by_column_and_row(&2, &3), "2");
+
+        let result = worksheet.write_records(&[1, 2]);
+        assert!(matches!(result, Err(Error::Cell { cell, .. }) if cell == "第 2 行"));
+    }
+}
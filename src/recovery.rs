@@ -0,0 +1,331 @@
+use crate::error::Error;
+use crate::event::{emit_workbook, OPENED_EVENT};
+use crate::save::write_atomic;
+use crate::scope::check_path;
+use crate::workbook::WorkbookRegistry;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{command, AppHandle, Manager, Runtime, State, Window};
+use tracing::{debug, info, instrument, warn};
+
+/// 恢复目录的默认名称，位于应用数据目录下。
+pub(crate) const RECOVERY_DIR_NAME: &str = "spreadsheet-recovery";
+
+/// 可以恢复的文件。
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoverableWorkbook {
+    /// 原文件路径。
+    pub path: String,
+    /// 自动保存的时间，Unix 时间戳 (毫秒)。
+    pub saved_at: u64,
+}
+
+/// `Recovery` 管理自动保存的快照。
+///
+/// 快照写入恢复目录，不会覆盖原文件。文件保存或关闭后删除对应的快照，
+/// 因此下次启动时恢复目录中剩下的都是崩溃前未保存的修改。
+pub struct Recovery {
+    dir: PathBuf,
+    /// 各文件已自动保存的 `revision`。
+    autosaved: Mutex<HashMap<String, u64>>,
+}
+
+impl Recovery {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Recovery {
+            dir: dir.into(),
+            autosaved: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, HashMap<String, u64>>, Error> {
+        self.autosaved
+            .lock()
+            .map_err(|error| Error::String(format!("获取自动保存锁失败! {} ", error)))
+    }
+
+    /// `snapshot_paths` 快照和描述文件的路径，文件名为原路径的哈希值。
+    fn snapshot_paths(&self, path: &str) -> (PathBuf, PathBuf) {
+        let id = format!("{:016x}", fnv1a(path.as_bytes()));
+        (
+            self.dir.join(format!("{}.xlsx", id)),
+            self.dir.join(format!("{}.json", id)),
+        )
+    }
+
+    /// `autosave` 把有未保存修改且上次自动保存后又修改过的文件写入恢复目录。
+    ///
+    /// 加密的文件不会自动保存，避免明文内容写入恢复目录。
+    pub fn autosave(&self, registry: &WorkbookRegistry) -> Result<usize, Error> {
+        let pending = registry.with_workbooks(|workbooks| {
+            let autosaved = self.lock()?;
+            Ok(workbooks
+                .iter()
+                .filter(|(path, workbook)| {
+                    workbook.is_dirty()
+                        && workbook.password.is_none()
+                        && autosaved.get(*path) != Some(&workbook.revision)
+                })
+                .map(|(path, workbook)| {
+                    (
+                        path.clone(),
+                        workbook.revision,
+                        workbook.spreadsheet.clone(),
+                    )
+                })
+                .collect::<Vec<_>>())
+        })?;
+        // 写文件时不持有文件锁，避免阻塞前端的操作
+        let mut count = 0;
+        for (path, revision, spreadsheet) in pending {
+            let (snapshot, meta) = self.snapshot_paths(&path);
+            fs::create_dir_all(&self.dir)?;
+            write_atomic(&spreadsheet, &snapshot, None)?;
+            let info = RecoverableWorkbook {
+                path: path.clone(),
+                saved_at: now_millis(),
+            };
+            fs::write(&meta, serde_json::to_vec(&info)?)?;
+            self.lock()?.insert(path.clone(), revision);
+            debug!("自动保存文件 {} 到 {}", path, snapshot.display());
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// `list` 列出恢复目录中的快照，按保存时间从新到旧排列。
+    pub fn list(&self) -> Result<Vec<RecoverableWorkbook>, Error> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(error.into()),
+        };
+        let mut list = Vec::new();
+        for entry in entries {
+            let meta = entry?.path();
+            if meta
+                .extension()
+                .map_or(true, |extension| extension != "json")
+            {
+                continue;
+            }
+            match fs::read(&meta).map(|data| serde_json::from_slice::<RecoverableWorkbook>(&data)) {
+                Ok(Ok(info)) if meta.with_extension("xlsx").exists() => list.push(info),
+                _ => warn!("忽略无效的恢复文件 {}", meta.display()),
+            }
+        }
+        list.sort_by_key(|info| std::cmp::Reverse(info.saved_at));
+        Ok(list)
+    }
+
+    /// `recover` 把 `path` 的快照作为 `path` 的文件打开，保存前不会修改原文件。
+    pub fn recover(&self, registry: &WorkbookRegistry, path: &str) -> Result<(), Error> {
+        self.recover_at(registry, path, Path::new(path))
+    }
+
+    /// `recover_at` 同 [`Recovery::recover`]，保存时写入 `file`。
+    pub fn recover_at(
+        &self,
+        registry: &WorkbookRegistry,
+        path: &str,
+        file: &Path,
+    ) -> Result<(), Error> {
+        let (snapshot, _) = self.snapshot_paths(path);
+        if !snapshot.exists() {
+            return Err(Error::NotFound(path.to_string()));
+        }
+        registry.open_snapshot_at(path, file, &snapshot)
+    }
+
+    /// `discard` 删除 `path` 的快照，文件保存或关闭后调用。
+    pub fn discard(&self, path: &str) -> Result<(), Error> {
+        self.lock()?.remove(path);
+        let (snapshot, meta) = self.snapshot_paths(path);
+        for file in [&meta, &snapshot] {
+            match fs::remove_file(file) {
+                Err(error) if error.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(error.into())
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `spawn_autosave` 启动后台线程，每隔 `interval` 自动保存一次。
+pub(crate) fn spawn_autosave<R: Runtime>(app: AppHandle<R>, interval: Duration) {
+    info!("自动保存间隔 {:?}", interval);
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+        let recovery = app.state::<Recovery>();
+        let registry = app.state::<WorkbookRegistry>();
+        if let Err(error) = recovery.autosave(&registry) {
+            warn!("自动保存失败! {}", error);
+        }
+    });
+}
+
+/// `discard_snapshot` 删除快照，失败时只记录日志，不影响保存或关闭的结果。
+pub(crate) fn discard_snapshot<R: Runtime>(app: &AppHandle<R>, path: &str) {
+    if let Err(error) = app.state::<Recovery>().discard(path) {
+        warn!("删除 {} 的恢复文件失败! {}", path, error);
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as u64)
+}
+
+/// FNV-1a 哈希，快照文件名需要在不同版本之间保持不变。
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// `default_dir` 默认恢复目录，无法获取应用数据目录时使用系统临时目录。
+///
+/// 替代的 `app_data_dir` 从 tauri 1.2 才有，为兼容 1.0 继续使用 `app_dir`。
+#[allow(deprecated)]
+pub(crate) fn default_dir<R: Runtime>(app: &AppHandle<R>) -> PathBuf {
+    app.path_resolver()
+        .app_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join(RECOVERY_DIR_NAME)
+}
+
+/// `list_recoverable` 列出上次未保存、可以恢复的文件。
+#[command]
+#[instrument(level = "debug", skip_all)]
+pub fn list_recoverable<R: Runtime>(
+    _app: AppHandle<R>,
+    _window: Window<R>,
+    recovery: State<'_, Recovery>,
+) -> Result<Vec<RecoverableWorkbook>, Error> {
+    recovery.list()
+}
+
+/// `recover` 打开 `path` 自动保存的内容，需要调用 `write_xlsx` 才会写回原文件。
+#[command]
+#[instrument(skip_all, fields(workbook = %path))]
+pub fn recover<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    recovery: State<'_, Recovery>,
+    path: String,
+) -> Result<(), Error> {
+    let file = check_path(&app, &path)?;
+    recovery.recover_at(&state, &path, &file)?;
+    emit_workbook(&app, OPENED_EVENT, &path);
+    Ok(())
+}
+
+/// `discard_recoverable` 删除 `path` 自动保存的内容。
+#[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path))]
+pub fn discard_recoverable<R: Runtime>(
+    _app: AppHandle<R>,
+    _window: Window<R>,
+    recovery: State<'_, Recovery>,
+    path: String,
+) -> Result<(), Error> {
+    recovery.discard(&path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(registry: &WorkbookRegistry, path: &str, value: &str) {
+        registry
+            .with_workbook(path, |workbook| {
+                workbook
+                    .worksheet_mut("Sheet1")?
+                    .get_cell_mut("A1")
+                    .set_value(value);
+                workbook.mark_dirty();
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn autosave_list_recover_and_discard() {
+        let dir = tempfile::tempdir().unwrap();
+        let recovery = Recovery::new(dir.path().join("recovery"));
+        assert!(recovery.list().unwrap().is_empty());
+
+        let registry = WorkbookRegistry::new();
+        let path = dir.path().join("a.xlsx").display().to_string();
+        registry.create(&path).unwrap();
+        registry.create("secret.xlsx").unwrap();
+        registry
+            .with_workbook("secret.xlsx", |workbook| {
+                workbook.set_password(Some(String::from("secret")));
+                workbook.mark_dirty();
+                Ok(())
+            })
+            .unwrap();
+        // 新建的文件没有修改，加密的文件不会自动保存
+        assert_eq!(recovery.autosave(&registry).unwrap(), 0);
+
+        edit(&registry, &path, "draft");
+        assert_eq!(recovery.autosave(&registry).unwrap(), 1);
+        assert_eq!(recovery.autosave(&registry).unwrap(), 0);
+        edit(&registry, &path, "draft 2");
+        assert_eq!(recovery.autosave(&registry).unwrap(), 1);
+        let list = recovery.list().unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].path, path);
+
+        // 已经打开的文件不能恢复
+        assert!(recovery.recover(&registry, &path).is_err());
+        registry.close(&path).unwrap();
+        recovery.recover(&registry, &path).unwrap();
+        registry
+            .with_workbook(&path, |workbook| {
+                assert!(workbook.is_dirty());
+                assert_eq!(workbook.worksheet("Sheet1")?.get_value("A1"), "draft 2");
+                Ok(())
+            })
+            .unwrap();
+        assert!(!Path::new(&path).exists());
+        assert!(matches!(
+            recovery.recover(&registry, "missing.xlsx"),
+            Err(Error::NotFound(_))
+        ));
+
+        recovery.discard(&path).unwrap();
+        assert!(recovery.list().unwrap().is_empty());
+        recovery.discard(&path).unwrap();
+        // 丢弃后同一 revision 需要重新自动保存
+        assert_eq!(recovery.autosave(&registry).unwrap(), 1);
+    }
+
+    #[test]
+    fn list_skips_invalid_snapshots() {
+        let dir = tempfile::tempdir().unwrap();
+        let recovery = Recovery::new(dir.path());
+        fs::write(dir.path().join("broken.json"), b"{").unwrap();
+        let orphan = RecoverableWorkbook {
+            path: String::from("orphan.xlsx"),
+            saved_at: 1,
+        };
+        fs::write(
+            dir.path().join("orphan.json"),
+            serde_json::to_vec(&orphan).unwrap(),
+        )
+        .unwrap();
+        assert!(recovery.list().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,108 @@
+use crate::error::Error;
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+use umya_spreadsheet::{
+    writer::xlsx::{write, write_with_password},
+    Spreadsheet,
+};
+
+/// `write_atomic` 先写入同一目录下的临时文件并同步到磁盘，再重命名为 `path`。
+///
+/// 写入过程中崩溃或磁盘已满时原文件保持不变，`path` 是符号链接时替换链接指向的文件。
+/// `password` 不为空时使用 ECMA-376 agile 加密。
+pub(crate) fn write_atomic(
+    spreadsheet: &Spreadsheet,
+    path: &Path,
+    password: Option<&str>,
+) -> Result<(), Error> {
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let temp = temp_path(&target);
+    if let Err(error) = write_temp(spreadsheet, &target, &temp, password) {
+        if let Err(remove_error) = fs::remove_file(&temp) {
+            debug!("删除临时文件 {} 失败! {}", temp.display(), remove_error);
+        }
+        return Err(error);
+    }
+    fs::rename(&temp, &target)?;
+    sync_dir(&target);
+    Ok(())
+}
+
+fn write_temp(
+    spreadsheet: &Spreadsheet,
+    target: &Path,
+    temp: &Path,
+    password: Option<&str>,
+) -> Result<(), Error> {
+    match password {
+        Some(password) => write_with_password(spreadsheet, temp, password),
+        None => write(spreadsheet, temp),
+    }
+    .map_err(|_| Error::String(format!("保存文件 {} 失败!", target.display())))?;
+    OpenOptions::new().write(true).open(temp)?.sync_all()?;
+    // 保留原文件的权限，新建的文件使用默认权限
+    if let Ok(metadata) = fs::metadata(target) {
+        fs::set_permissions(temp, metadata.permissions())?;
+    }
+    Ok(())
+}
+
+/// `temp_path` 临时文件与目标文件在同一目录，保证重命名不会跨文件系统。
+fn temp_path(target: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(target.file_name().unwrap_or_default());
+    name.push(format!(".{}.tmp", std::process::id()));
+    target.with_file_name(name)
+}
+
+/// `sync_dir` 同步目录，确保重命名写入磁盘，失败时只记录日志。
+#[cfg(unix)]
+fn sync_dir(target: &Path) {
+    let dir = match target.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    if let Err(error) = fs::File::open(dir).and_then(|dir| dir.sync_all()) {
+        tracing::warn!("同步目录 {} 失败! {}", dir.display(), error);
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_target: &Path) {}
+
+/// `backup_path` 第 `index` 个备份的路径，`index` 从 1 开始，越小越新。
+///
+/// `data.xlsx` 的备份依次为 `data.bak.xlsx`、`data.bak.2.xlsx`、`data.bak.3.xlsx`。
+fn backup_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.file_stem().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    if index > 1 {
+        name.push(format!(".{}", index));
+    }
+    if let Some(extension) = path.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    path.with_file_name(name)
+}
+
+/// `backup` 保存前把 `path` 的当前内容复制为备份，最多保留 `count` 个，更早的备份依次后移。
+///
+/// 文件不存在或 `count` 为 0 时不备份。
+pub(crate) fn backup(path: &Path, count: usize) -> Result<(), Error> {
+    if count == 0 || !path.exists() {
+        return Ok(());
+    }
+    for index in (1..count).rev() {
+        let from = backup_path(path, index);
+        if from.exists() {
+            fs::rename(&from, backup_path(path, index + 1))?;
+        }
+    }
+    let target = backup_path(path, 1);
+    fs::copy(path, &target)?;
+    debug!("备份 {} 到 {}", path.display(), target.display());
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use crate::error::Error;
+use glob::{MatchOptions, Pattern};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, Runtime};
+use tracing::warn;
+
+/// 允许读写的路径范围，在 `tauri.conf.json` 的 `plugins.spreadsheet.scope` 中配置。
+///
+/// `allow` 和 `deny` 可以是目录或 glob 模式，如 `"/home/user/reports"`、`"/data/**/*.xlsx"`。
+/// 路径在 `allow` 或 Tauri fs scope 中允许且不在 `deny` 中时才能读写，`deny` 优先。
+///
+/// ```json
+/// {
+///   "plugins": {
+///     "spreadsheet": {
+///       "scope": { "allow": ["/data/reports"], "deny": ["/data/reports/private"] }
+///     }
+///   }
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PathScope {
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+}
+
+/// 编译后的路径范围。
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ScopeMatcher {
+    allow: Vec<Pattern>,
+    deny: Vec<Pattern>,
+}
+
+impl ScopeMatcher {
+    pub(crate) fn new(scope: &PathScope) -> Result<Self, Error> {
+        Ok(ScopeMatcher {
+            allow: compile(&scope.allow)?,
+            deny: compile(&scope.deny)?,
+        })
+    }
+
+    fn allows(&self, path: &Path) -> bool {
+        self.allow.iter().any(|pattern| matches(pattern, path))
+    }
+
+    fn denies(&self, path: &Path) -> bool {
+        self.deny.iter().any(|pattern| matches(pattern, path))
+    }
+}
+
+/// `compile` 编译 glob 模式，不含通配符的目录同时匹配目录下的所有文件。
+fn compile(entries: &[String]) -> Result<Vec<Pattern>, Error> {
+    let mut patterns = Vec::new();
+    for entry in entries {
+        let is_glob = entry.contains(|c: char| matches!(c, '*' | '?' | '['));
+        let entry = if is_glob {
+            entry.clone()
+        } else {
+            // 目录本身可能是符号链接，与请求路径一样先解析
+            let path = Path::new(entry);
+            let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+            let path = path.to_string_lossy().trim_end_matches('/').to_string();
+            let path = Pattern::escape(&path);
+            patterns.push(parse(&path)?);
+            format!("{}/**", path)
+        };
+        patterns.push(parse(&entry)?);
+    }
+    Ok(patterns)
+}
+
+fn parse(pattern: &str) -> Result<Pattern, Error> {
+    Pattern::new(pattern)
+        .map_err(|error| Error::String(format!("路径范围 {} 格式错误! {}", pattern, error)))
+}
+
+fn matches(pattern: &Pattern, path: &Path) -> bool {
+    let options = MatchOptions {
+        require_literal_separator: true,
+        ..MatchOptions::default()
+    };
+    pattern.matches_path_with(path, options)
+}
+
+/// `resolve` 解析路径中的 `..` 和符号链接，文件不存在时解析其所在目录。
+fn resolve(path: &Path) -> Result<PathBuf, Error> {
+    if !path.is_absolute() {
+        return Err(Error::Forbidden(path.display().to_string()));
+    }
+    if path.exists() {
+        return Ok(path.canonicalize()?);
+    }
+    // 目标不存在的符号链接，写入时会创建链接指向的文件
+    if path.symlink_metadata().is_ok() {
+        return Err(Error::Forbidden(path.display().to_string()));
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(file_name)) => Ok(parent.canonicalize()?.join(file_name)),
+        _ => Err(Error::Forbidden(path.display().to_string())),
+    }
+}
+
+/// `check_path` 检查前端传入的路径是否在允许范围内，返回解析后的路径，调用方应读写返回的路径。
+///
+/// 没有配置插件的 `allow` 时只按 Tauri fs scope 检查。符号链接会先解析为实际路径再检查，
+/// 链接到范围外的文件同样会被拒绝。
+pub(crate) fn check_path<R: Runtime>(app: &AppHandle<R>, path: &str) -> Result<PathBuf, Error> {
+    let resolved = resolve(Path::new(path))?;
+    let matcher = app.state::<ScopeMatcher>();
+    let allowed = matcher.allows(&resolved) || app.fs_scope().is_allowed(&resolved);
+    if !allowed || matcher.denies(&resolved) {
+        warn!(
+            "拒绝访问范围外的路径 {}，请在 plugins.spreadsheet.scope 或 fs scope 中允许",
+            resolved.display()
+        );
+        return Err(Error::Forbidden(path.to_string()));
+    }
+    Ok(resolved)
+}
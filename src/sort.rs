@@ -0,0 +1,429 @@
+use crate::error::Error;
+use crate::event::{emit_changed, ChangeKind};
+use crate::formula;
+use crate::history::EditScope;
+use crate::range::CellRange;
+use crate::workbook::{Workbook, WorkbookRegistry};
+use serde::Deserialize;
+use std::cmp::Ordering;
+use tauri::{command, AppHandle, Runtime, State, Window};
+use tracing::{debug, instrument};
+use umya_spreadsheet::{Cell, Worksheet};
+
+/// 排序方向。
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl Default for SortOrder {
+    fn default() -> Self {
+        SortOrder::Asc
+    }
+}
+
+/// 排序时值的比较方式。
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SortType {
+    /// 数字排在文本前面，数字按数值比较，文本按字符串比较。
+    Auto,
+    Number,
+    Text,
+    /// 支持 Excel 日期序列号和 `YYYY-MM-DD`、`YYYY/MM/DD` 格式 (可带时间)。
+    Date,
+}
+
+impl Default for SortType {
+    fn default() -> Self {
+        SortType::Auto
+    }
+}
+
+/// 排序关键字。
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SortKey {
+    /// 列索引，从 1 开始。
+    pub column: u32,
+    pub order: SortOrder,
+    pub data_type: SortType,
+    pub case_sensitive: bool,
+    /// 自定义顺序，如 `["High", "Medium", "Low"]`，不在列表中的值排在后面。
+    pub custom_order: Option<Vec<String>>,
+}
+
+/// 排序用的值，空白单元格无论升序降序都排在最后。
+#[derive(Clone, Debug, PartialEq)]
+enum SortValue {
+    Number(f64),
+    Text(String),
+    Blank,
+}
+
+/// `parse_date` 将日期转换为自 1970-01-01 起的天数。
+fn parse_date(value: &str) -> Option<f64> {
+    if let Ok(serial) = value.parse::<f64>() {
+        // Excel 日期序列号 25569 对应 1970-01-01。
+        return Some(serial - 25569.0);
+    }
+    let (date, time) = match value.split_once(|c| c == ' ' || c == 'T') {
+        Some((date, time)) => (date, Some(time)),
+        None => (value, None),
+    };
+    let parts: Vec<&str> = date.split(|c| c == '-' || c == '/').collect();
+    if parts.len() != 3 {
+        return None;
+    }
+    let year: i64 = parts[0].parse().ok()?;
+    let month: i64 = parts[1].parse().ok()?;
+    let day: i64 = parts[2].parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // 公历日期转天数。
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = (if y >= 0 { y } else { y - 399 }) / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = (era * 146097 + doe - 719468) as f64;
+    let fraction = match time {
+        Some(time) => {
+            let mut seconds = 0.0;
+            for (i, part) in time.split(':').take(3).enumerate() {
+                let value: f64 = part.parse().ok()?;
+                seconds += value * [3600.0, 60.0, 1.0][i];
+            }
+            seconds / 86400.0
+        }
+        None => 0.0,
+    };
+    Some(days + fraction)
+}
+
+/// `sort_value` 根据关键字的比较方式转换单元格的值。
+fn sort_value(value: &str, key: &SortKey) -> SortValue {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return SortValue::Blank;
+    }
+    let text = if key.case_sensitive {
+        value.to_string()
+    } else {
+        value.to_lowercase()
+    };
+    if let Some(custom_order) = &key.custom_order {
+        let position = custom_order.iter().position(|item| {
+            if key.case_sensitive {
+                item == value
+            } else {
+                item.to_lowercase() == text
+            }
+        });
+        return match position {
+            Some(position) => SortValue::Number(position as f64),
+            None => SortValue::Text(text),
+        };
+    }
+    match key.data_type {
+        SortType::Text => SortValue::Text(text),
+        SortType::Date => match parse_date(trimmed) {
+            Some(days) => SortValue::Number(days),
+            None => SortValue::Text(text),
+        },
+        SortType::Auto | SortType::Number => match trimmed.parse::<f64>() {
+            Ok(number) => SortValue::Number(number),
+            Err(_) => SortValue::Text(text),
+        },
+    }
+}
+
+/// `compare_values` 比较两个值，空白值始终排在最后。
+fn compare_values(left: &SortValue, right: &SortValue, order: SortOrder) -> Ordering {
+    let ordering = match (left, right) {
+        (SortValue::Blank, SortValue::Blank) => return Ordering::Equal,
+        (SortValue::Blank, _) => return Ordering::Greater,
+        (_, SortValue::Blank) => return Ordering::Less,
+        (SortValue::Number(left), SortValue::Number(right)) => {
+            left.partial_cmp(right).unwrap_or(Ordering::Equal)
+        }
+        (SortValue::Number(_), SortValue::Text(_)) => Ordering::Less,
+        (SortValue::Text(_), SortValue::Number(_)) => Ordering::Greater,
+        (SortValue::Text(left), SortValue::Text(right)) => left.cmp(right),
+    };
+    match order {
+        SortOrder::Asc => ordering,
+        SortOrder::Desc => ordering.reverse(),
+    }
+}
+
+/// `sort_rows` 按关键字对区域中的行排序，单元格的样式和公式随行移动。
+///
+/// 公式中的相对行引用会按移动的行数调整，返回排序后各行对应的原行索引。
+pub(crate) fn sort_rows(
+    worksheet: &mut Worksheet,
+    range: &CellRange,
+    keys: &[SortKey],
+    has_header: bool,
+) -> Result<Vec<u32>, Error> {
+    if keys.is_empty() {
+        return Err(Error::String(String::from("排序关键字不能为空!")));
+    }
+    for key in keys {
+        if !range.columns().contains(&key.column) {
+            return Err(Error::String(format!(
+                "排序列 {} 不在区域 {} 内!",
+                key.column, range
+            )));
+        }
+    }
+    let first_row = if has_header {
+        range.start.1 + 1
+    } else {
+        range.start.1
+    };
+    if first_row >= range.end.1 {
+        return Ok((first_row..=range.end.1).collect());
+    }
+
+    let rows: Vec<u32> = (first_row..=range.end.1).collect();
+    let values: Vec<Vec<SortValue>> = rows
+        .iter()
+        .map(|row| {
+            keys.iter()
+                .map(|key| {
+                    sort_value(
+                        &worksheet.get_value_by_column_and_row(&key.column, row),
+                        key,
+                    )
+                })
+                .collect()
+        })
+        .collect();
+    let mut order: Vec<usize> = (0..rows.len()).collect();
+    order.sort_by(|a, b| {
+        for (i, key) in keys.iter().enumerate() {
+            let ordering = compare_values(&values[*a][i], &values[*b][i], key.order);
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    });
+
+    let cells: Vec<Vec<Option<Cell>>> = rows
+        .iter()
+        .map(|row| {
+            range
+                .columns()
+                .map(|column| worksheet.get_cell_by_column_and_row(&column, row).cloned())
+                .collect()
+        })
+        .collect();
+    for (target, source) in order.iter().enumerate() {
+        if target == *source {
+            continue;
+        }
+        let row = rows[target];
+        let delta = row as i64 - rows[*source] as i64;
+        for (offset, column) in range.columns().enumerate() {
+            let mut cell = match &cells[*source][offset] {
+                Some(cell) => cell.clone(),
+                None => Cell::default(),
+            };
+            cell.get_coordinate_mut().set_col_num(column);
+            cell.get_coordinate_mut().set_row_num(row);
+            let cell_formula = cell.get_formula().to_string();
+            if !cell_formula.is_empty() {
+                cell.set_formula(formula::shift_rows(&cell_formula, delta));
+            }
+            *worksheet.get_cell_by_column_and_row_mut(&column, &row) = cell;
+        }
+    }
+    Ok(order.iter().map(|source| rows[*source]).collect())
+}
+
+impl Workbook {
+    /// `sort_range` 按一个或多个关键字对区域内的行排序，返回排序后各行对应的原行索引。
+    ///
+    /// # Arguments
+    ///
+    /// - `range` 排序区域或名称，如: "A1:F100"
+    /// - `keys` 排序关键字，依次比较
+    /// - `has_header` 区域首行是否为表头，表头不参与排序
+    pub fn sort_range(
+        &mut self,
+        sheet_name: &str,
+        range: &str,
+        keys: &[SortKey],
+        has_header: bool,
+    ) -> Result<Vec<u32>, Error> {
+        let range = self.resolve_range(sheet_name, range)?;
+        let worksheet = self.worksheet_mut(sheet_name)?;
+        let order = sort_rows(worksheet, &range, keys, has_header)?;
+        debug!("排序区域 {} 共 {} 行", range, order.len());
+        Ok(order)
+    }
+}
+
+/// `sort_range` 按一个或多个关键字对区域内的行排序。
+///
+/// # Return value
+///
+/// `Vec<u32>` - 排序后各行对应的原行索引
+#[command]
+#[instrument(
+    level = "debug",
+    skip_all,
+    fields(workbook = %path, sheet = %sheet_name, range = %range)
+)]
+pub fn sort_range<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+    sheet_name: String,
+    range: String,
+    keys: Vec<SortKey>,
+    has_header: bool,
+) -> Result<Vec<u32>, Error> {
+    let result = state.with_workbook(&path, |workbook| {
+        let cells = workbook.resolve_range(&sheet_name, &range)?;
+        workbook.record(
+            EditScope::Cells(&sheet_name, cells),
+            "sort_range",
+            |workbook| workbook.sort_range(&sheet_name, &range, &keys, has_header),
+        )
+    })?;
+    emit_changed(
+        &app,
+        &path,
+        Some(&sheet_name),
+        Some(range),
+        ChangeKind::Sort,
+    );
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(data_type: SortType) -> SortKey {
+        SortKey {
+            column: 1,
+            data_type,
+            ..SortKey::default()
+        }
+    }
+
+    #[test]
+    fn parse_date_formats() {
+        assert_eq!(parse_date("1970-01-01"), Some(0.0));
+        assert_eq!(parse_date("2000/03/01"), Some(11017.0));
+        assert_eq!(parse_date("25570"), Some(1.0));
+        assert_eq!(parse_date("1970-01-02 12:00"), Some(1.5));
+        assert_eq!(parse_date("1970-01-01T06:00:00"), Some(0.25));
+        assert_eq!(parse_date("2024-13-01"), None);
+        assert_eq!(parse_date("2024-01"), None);
+        assert_eq!(parse_date("next week"), None);
+    }
+
+    #[test]
+    fn sort_value_by_type() {
+        assert_eq!(sort_value("  ", &key(SortType::Auto)), SortValue::Blank);
+        assert_eq!(
+            sort_value(" 42 ", &key(SortType::Auto)),
+            SortValue::Number(42.0)
+        );
+        assert_eq!(
+            sort_value("Apple", &key(SortType::Auto)),
+            SortValue::Text(String::from("apple"))
+        );
+        assert_eq!(
+            sort_value("42", &key(SortType::Text)),
+            SortValue::Text(String::from("42"))
+        );
+        assert_eq!(
+            sort_value("1970-01-03", &key(SortType::Date)),
+            SortValue::Number(2.0)
+        );
+
+        let mut case_sensitive = key(SortType::Text);
+        case_sensitive.case_sensitive = true;
+        assert_eq!(
+            sort_value("Apple", &case_sensitive),
+            SortValue::Text(String::from("Apple"))
+        );
+
+        let mut custom = key(SortType::Auto);
+        custom.custom_order = Some(vec![String::from("High"), String::from("Low")]);
+        assert_eq!(sort_value("low", &custom), SortValue::Number(1.0));
+        assert_eq!(
+            sort_value("Other", &custom),
+            SortValue::Text(String::from("other"))
+        );
+    }
+
+    #[test]
+    fn compare_values_keeps_blanks_last() {
+        let one = SortValue::Number(1.0);
+        let two = SortValue::Number(2.0);
+        let text = SortValue::Text(String::from("a"));
+        assert_eq!(compare_values(&one, &two, SortOrder::Asc), Ordering::Less);
+        assert_eq!(
+            compare_values(&one, &two, SortOrder::Desc),
+            Ordering::Greater
+        );
+        assert_eq!(compare_values(&one, &text, SortOrder::Asc), Ordering::Less);
+        assert_eq!(
+            compare_values(&SortValue::Blank, &one, SortOrder::Asc),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare_values(&SortValue::Blank, &one, SortOrder::Desc),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare_values(&one, &SortValue::Blank, SortOrder::Desc),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn sort_rows_moves_formulas() {
+        let mut worksheet = Worksheet::default();
+        let rows = [("Name", ""), ("b", "2"), ("", "3"), ("a", "1")];
+        for (index, (name, value)) in rows.iter().enumerate() {
+            let row = index as u32 + 1;
+            worksheet
+                .get_cell_by_column_and_row_mut(&1, &row)
+                .set_value(*name);
+            worksheet
+                .get_cell_by_column_and_row_mut(&2, &row)
+                .set_value(*value);
+        }
+        worksheet
+            .get_cell_by_column_and_row_mut(&3, &4)
+            .set_formula("B4*2");
+
+        let range = CellRange::new((1, 1), (3, 4));
+        let order = sort_rows(&mut worksheet, &range, &[key(SortType::Auto)], true).unwrap();
+        assert_eq!(order, vec![4, 2, 3]);
+        assert_eq!(worksheet.get_value_by_column_and_row(&1, &1), "Name");
+        assert_eq!(worksheet.get_value_by_column_and_row(&1, &2), "a");
+        assert_eq!(worksheet.get_value_by_column_and_row(&2, &4), "3");
+        assert_eq!(
+            worksheet
+                .get_cell_by_column_and_row(&3, &2)
+                .unwrap()
+                .get_formula(),
+            "B2*2"
+        );
+        assert!(sort_rows(&mut worksheet, &range, &[], true).is_err());
+    }
+}
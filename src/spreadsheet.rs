@@ -1,229 +1,250 @@
+use crate::config::{Config, NEW_FILE_SHEET_NAME};
 use crate::error::Error;
-use crate::state::{SpreadsheetInfo, SpreadsheetState};
-use std::path::Path;
+use crate::event::{
+    emit_changed, emit_workbook, ChangeKind, CLOSED_EVENT, MODIFIED_EVENT, OPENED_EVENT,
+    SAVED_EVENT,
+};
+use crate::history::EditScope;
+use crate::recovery::discard_snapshot;
+use crate::scope::check_path;
+use crate::workbook::{Workbook, WorkbookRegistry};
 use tauri::State;
 use tauri::{command, AppHandle, Runtime, Window};
-use umya_spreadsheet::{new_file, reader::xlsx::read, writer::xlsx::write};
+use tracing::{debug, instrument};
 
-/// `get_spreadsheet` 将根据指定 **path** 获取 spreadsheet 实例。
-///
-/// # Example
-///
-/// ```
-/// get_spreadsheet(state, path, |spreadsheet| {
-///   // spreadsheet 操作
-/// })
-/// ```
-pub fn get_spreadsheet<T, F: FnOnce(&mut SpreadsheetInfo) -> Result<T, Error>>(
-    state: State<'_, SpreadsheetState>,
-    path: String,
-    f: F,
-) -> Result<T, Error> {
-    match state.spreadsheets.lock() {
-        Ok(mut map) => {
-            if !map.contains_key(&path) {
-                return Err(Error::NotFound(path.clone()));
-            }
-            match map.get_mut(&path) {
-                Some(spreadsheet) => return f(spreadsheet),
-                None => return Err(Error::String(format!("未获取 {} 文件!", &path))),
+impl Workbook {
+    /// `copy_sheet` 复制 `source_sheet_name` 为新的 `target_sheet_name`。
+    pub fn copy_sheet(
+        &mut self,
+        source_sheet_name: &str,
+        target_sheet_name: &str,
+    ) -> Result<(), Error> {
+        match self.spreadsheet.get_sheet_by_name(source_sheet_name) {
+            Ok(worksheet) => {
+                let mut clone_sheet = worksheet.clone();
+                clone_sheet.set_name(target_sheet_name);
+                match self.spreadsheet.add_sheet(clone_sheet) {
+                    Ok(_) => {
+                        debug!(
+                            "复制 sheet {} 到 sheet {}",
+                            source_sheet_name, target_sheet_name
+                        );
+                        Ok(())
+                    }
+                    Err(error) => Err(Error::String(format!(
+                        "添加 sheet {} 失败! {}",
+                        target_sheet_name, error
+                    ))),
+                }
             }
+            Err(error) => Err(Error::String(format!(
+                "获取源 sheet {} 失败! {}",
+                source_sheet_name, error
+            ))),
+        }
+    }
+
+    /// `new_sheet` 创建名为 `sheet_name` 的 sheet。
+    pub fn new_sheet(&mut self, sheet_name: &str) -> Result<(), Error> {
+        if self.spreadsheet.new_sheet(sheet_name).is_ok() {
+            debug!("创建新 sheet {}", sheet_name);
+            Ok(())
+        } else {
+            Err(Error::String(format!("新建 {} sheet 失败!", sheet_name)))
         }
-        Err(error) => return Err(Error::String(format!("获取锁失败! {}", error))),
     }
 }
 
+/// `check_xlsx` 检查文件读取或保存后是否被其他程序修改或删除过，是时发送 `spreadsheet://modified` 事件。
+#[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path))]
+pub fn check_xlsx<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+) -> Result<bool, Error> {
+    let modified = state.is_modified(&path)?;
+    if modified {
+        emit_workbook(&app, MODIFIED_EVENT, &path);
+    }
+    Ok(modified)
+}
+
 /// `close_all_xlsx` 关闭所有 xlsx 文件。
 #[command]
+#[instrument(skip_all)]
 pub fn close_all_xlsx<R: Runtime>(
-    _app: AppHandle<R>,
+    app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
 ) -> Result<(), Error> {
-    let mut map = state.spreadsheets.lock().expect("mutex poisoned!");
-    map.clear();
-    println!("删除所有 xlsx 文件");
+    let paths = state.list()?;
+    state.close_all()?;
+    for path in paths {
+        discard_snapshot(&app, &path);
+        emit_workbook(&app, CLOSED_EVENT, &path);
+    }
     Ok(())
 }
 
 /// `close_xlsx` 关闭指定 xlsx 文件。
 #[command]
+#[instrument(skip_all, fields(workbook = %path))]
 pub fn close_xlsx<R: Runtime>(
-    _app: AppHandle<R>,
+    app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
     path: String,
 ) -> Result<(), Error> {
-    let mut map = state.spreadsheets.lock().expect("mutex poisoned!");
-    map.remove(&path);
-    println!("删除 xlsx 文件 {}!", &path);
+    state.close(&path)?;
+    discard_snapshot(&app, &path);
+    emit_workbook(&app, CLOSED_EVENT, &path);
     Ok(())
 }
 
 /// `copy_sheet` 复制 sheet
 #[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, sheet = %source_sheet_name))]
 pub fn copy_sheet<R: Runtime>(
-    _app: AppHandle<R>,
+    app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
     path: String,
     source_sheet_name: String,
     target_sheet_name: String,
 ) -> Result<(), Error> {
-    get_spreadsheet(state, path, |spreadsheet| {
-        match spreadsheet
-            .spreadsheet
-            .get_sheet_by_name(&source_sheet_name)
-        {
-            Ok(worksheet) => {
-                let mut clone_sheet = worksheet.clone();
-                clone_sheet.set_name(&target_sheet_name);
-                match spreadsheet.spreadsheet.add_sheet(clone_sheet) {
-                    Ok(_) => {
-                        println!(
-                            "复制 sheet {} 到 sheet {}",
-                            &source_sheet_name, &target_sheet_name
-                        );
-                        return Ok(());
-                    }
-                    Err(error) => {
-                        return Err(Error::String(format!(
-                            "添加 sheet {} 失败! {}",
-                            &target_sheet_name, error
-                        )))
-                    }
-                }
-            }
-            Err(error) => {
-                return Err(Error::String(format!(
-                    "获取源 sheet {} 失败! {}",
-                    &source_sheet_name, error
-                )));
-            }
-        }
-    })
+    state.edit(&path, EditScope::Workbook, "copy_sheet", |workbook| {
+        workbook.copy_sheet(&source_sheet_name, &target_sheet_name)
+    })?;
+    emit_changed(
+        &app,
+        &path,
+        Some(&target_sheet_name),
+        None,
+        ChangeKind::AddSheet,
+    );
+    Ok(())
 }
 
 /// `list_xlsx` 列出所有打开的 xlsx 文件。
 #[command]
+#[instrument(level = "debug", skip_all)]
 pub fn list_xlsx<R: Runtime>(
     _app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
 ) -> Result<Vec<String>, Error> {
-    let map = state.spreadsheets.lock().expect("mutex poisoned!");
-    let list = map.keys();
-    let mut keys = Vec::new();
-    for key in list {
-        keys.push(key.clone());
-    }
-    println!("列表内容: {:?}", &keys);
-    Ok(keys)
+    state.list()
 }
 
 /// `create_sheet` 创建 sheet。
 #[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, sheet = %sheet_name))]
 pub fn new_sheet<R: Runtime>(
-    _app: AppHandle<R>,
+    app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
     path: String,
     sheet_name: String,
 ) -> Result<String, Error> {
-    get_spreadsheet(state, path, |spreadsheet| {
-        if let Ok(_) = spreadsheet.spreadsheet.new_sheet(&sheet_name) {
-            println!("创建新 sheet {}", &sheet_name);
-            return Ok(sheet_name);
-        } else {
-            return Err(Error::String(format!("新建 {} sheet 失败!", &sheet_name)));
-        }
-    })
+    state.edit(&path, EditScope::Workbook, "new_sheet", |workbook| {
+        workbook.new_sheet(&sheet_name)
+    })?;
+    emit_changed(&app, &path, Some(&sheet_name), None, ChangeKind::AddSheet);
+    Ok(sheet_name)
 }
 
 /// `new_xlsx` 创建指定 **path** 的 xlsx 文件。
 #[command]
+#[instrument(skip_all, fields(workbook = %path))]
 pub fn new_xlsx<R: Runtime>(
-    _app: AppHandle<R>,
+    app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
+    config: State<'_, Config>,
     path: String,
 ) -> Result<String, Error> {
-    match state.spreadsheets.lock() {
-        Ok(mut map) => {
-            if map.contains_key(&path) {
-                let message = format!("新建文件 {} 已存在", &path);
-                println!("{}", message);
-                return Err(Error::String(message));
-            }
-            let path = Path::new(&path);
-            let book = new_file();
-            let data = SpreadsheetInfo { spreadsheet: book };
-            map.insert(path.display().to_string(), data);
-            let message = format!("新建 xlsx 文件: {}", path.display().to_string());
-            return Ok(message);
+    let file = check_path(&app, &path)?;
+    state.create_at(&path, &file)?;
+    if let Some(sheet_name) = &config.default_sheet_name {
+        let renamed = state.with_worksheet(&path, NEW_FILE_SHEET_NAME, |worksheet| {
+            worksheet.set_name(sheet_name);
+            Ok(())
+        });
+        if let Err(error) = renamed {
+            state.close(&path)?;
+            return Err(error);
         }
-        Err(error) => return Err(Error::String(format!("获取文件锁失败! {} ", error))),
     }
+    emit_workbook(&app, OPENED_EVENT, &path);
+    Ok(format!("新建 xlsx 文件: {}", path))
 }
 
-/// `read_xlsx` 读取指定 *path* 的 xlsx 文件。
+/// `read_xlsx` 读取指定 *path* 的 xlsx 文件，加密的文件需要提供 `password`。
 #[command]
+#[instrument(skip_all, fields(workbook = %path))]
 pub fn read_xlsx<R: Runtime>(
-    _app: AppHandle<R>,
+    app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
     path: String,
+    password: Option<String>,
 ) -> Result<String, Error> {
-    println!("读取 xlsx 文件!");
-    match state.spreadsheets.lock() {
-        Ok(mut map) => {
-            if map.contains_key(&path) {
-                println!("读取过文件!");
-                return Ok(path.clone());
-            }
-            let path = Path::new(&path);
-            match read(path.clone()) {
-                Ok(spreadsheet) => {
-                    let data = SpreadsheetInfo { spreadsheet };
-                    map.insert(path.display().to_string(), data);
-                    println!("读取 {} 文件成功!", path.display().to_string());
-                    return Ok(format!("读取 {} 文件成功!", path.display().to_string()));
-                }
-                Err(error) => {
-                    println!("读取 {} 文件失败! {:?}", path.display().to_string(), error);
-                    return Err(Error::String(format!(
-                        "读取 {} 文件失败!",
-                        path.display().to_string(),
-                    )));
-                }
-            }
-        }
-        Err(error) => return Err(Error::String(format!("获取文件锁失败! {} ", error))),
+    let file = check_path(&app, &path)?;
+    if state.open_at(&path, &file, password.as_deref())? {
+        emit_workbook(&app, OPENED_EVENT, &path);
+        Ok(format!("读取 {} 文件成功!", path))
+    } else {
+        Ok(path)
     }
 }
 
+/// `reload_xlsx` 重新读取 xlsx 文件，丢弃未保存的修改。
+#[command]
+#[instrument(skip_all, fields(workbook = %path))]
+pub fn reload_xlsx<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+) -> Result<(), Error> {
+    let file = check_path(&app, &path)?;
+    state.reload_at(&path, &file)?;
+    discard_snapshot(&app, &path);
+    emit_changed(&app, &path, None, None, ChangeKind::Reload);
+    Ok(())
+}
+
 /// `write_xlsx` 写入 xlsx 文件。
+///
+/// 文件读取后被其他程序修改或删除过时拒绝写入并发送 `spreadsheet://modified` 事件，
+/// `overwrite` 为 `true` 时直接覆盖。
+///
+/// 设置 `password` 后保存为加密文件，之后的保存沿用该密码，为空字符串时取消加密。
 #[command]
+#[instrument(skip_all, fields(workbook = %path))]
 pub fn write_xlsx<R: Runtime>(
-    _app: AppHandle<R>,
+    app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
     path: String,
+    overwrite: Option<bool>,
+    password: Option<String>,
 ) -> Result<(), Error> {
-    get_spreadsheet(state, path.clone(), |spreadsheet| {
-        let path = Path::new(&path);
-        match write(&spreadsheet.spreadsheet, path) {
-            Ok(_) => {
-                let message = format!("保存 xlsx 文件 {}", path.display().to_string());
-                println!("{}", message);
-                return Ok(());
-            }
-            Err(_) => {
-                return Err(Error::String(format!(
-                    "保存文件 {} 失败!",
-                    path.display().to_string()
-                )));
-            }
-        }
-    })
+    let file = check_path(&app, &path)?;
+    if let Some(password) = password {
+        state.with_workbook(&path, |workbook| {
+            workbook.set_password(Some(password));
+            Ok(())
+        })?;
+    }
+    let result = state.save_at(&path, &file, overwrite.unwrap_or(false));
+    if let Err(Error::Modified(_)) = &result {
+        emit_workbook(&app, MODIFIED_EVENT, &path);
+    }
+    result?;
+    discard_snapshot(&app, &path);
+    emit_workbook(&app, SAVED_EVENT, &path);
+    Ok(())
 }
@@ -0,0 +1,446 @@
+use crate::error::Error;
+use crate::event::{emit_changed, ChangeKind};
+use crate::history::EditScope;
+use crate::range::CellRange;
+use crate::workbook::{Workbook, WorkbookRegistry};
+use crate::worksheet::write_rows;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tauri::{command, AppHandle, Runtime, State, Window};
+use tracing::{debug, instrument};
+use umya_spreadsheet::Worksheet;
+
+/// 聚合函数。
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AggregateFunction {
+    Sum,
+    /// 非空单元格数量。
+    Count,
+    Avg,
+    Min,
+    Max,
+    /// 不重复的非空值数量。
+    DistinctCount,
+}
+
+/// 聚合列。
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Aggregate {
+    /// 表头名称。
+    pub column: String,
+    pub function: AggregateFunction,
+    /// 结果列名称，默认为 `function(column)`。
+    pub alias: Option<String>,
+}
+
+/// 汇总结果的输出方式。
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryOutput {
+    /// 写入的新 sheet 名称。
+    pub sheet_name: Option<String>,
+    /// 交叉表的列分组表头名称，该列的每个值生成一组结果列。
+    pub column_group: Option<String>,
+}
+
+/// 汇总结果。
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryResult {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// 单个分组的聚合状态。
+#[derive(Clone, Debug, Default)]
+struct Accumulator {
+    count: usize,
+    numbers: usize,
+    sum: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+    distinct: HashSet<String>,
+}
+
+impl Accumulator {
+    fn push(&mut self, value: &str) {
+        let value = value.trim();
+        if value.is_empty() {
+            return;
+        }
+        self.count += 1;
+        self.distinct.insert(value.to_string());
+        if let Ok(number) = value.parse::<f64>() {
+            self.numbers += 1;
+            self.sum += number;
+            self.min = Some(self.min.map_or(number, |min| min.min(number)));
+            self.max = Some(self.max.map_or(number, |max| max.max(number)));
+        }
+    }
+
+    fn result(&self, function: AggregateFunction) -> String {
+        match function {
+            AggregateFunction::Sum => format_number(self.sum),
+            AggregateFunction::Count => self.count.to_string(),
+            AggregateFunction::Avg if self.numbers > 0 => {
+                format_number(self.sum / self.numbers as f64)
+            }
+            AggregateFunction::Avg => String::new(),
+            AggregateFunction::Min => self.min.map(format_number).unwrap_or_default(),
+            AggregateFunction::Max => self.max.map(format_number).unwrap_or_default(),
+            AggregateFunction::DistinctCount => self.distinct.len().to_string(),
+        }
+    }
+}
+
+/// `format_number` 整数不带小数部分输出。
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        value.to_string()
+    }
+}
+
+impl Aggregate {
+    fn name(&self) -> String {
+        match &self.alias {
+            Some(alias) => alias.clone(),
+            None => {
+                let function = match self.function {
+                    AggregateFunction::Sum => "sum",
+                    AggregateFunction::Count => "count",
+                    AggregateFunction::Avg => "avg",
+                    AggregateFunction::Min => "min",
+                    AggregateFunction::Max => "max",
+                    AggregateFunction::DistinctCount => "distinctCount",
+                };
+                format!("{}({})", function, self.column)
+            }
+        }
+    }
+}
+
+/// `header_column` 根据表头名称获取列索引。
+fn header_column(worksheet: &Worksheet, range: &CellRange, name: &str) -> Result<u32, Error> {
+    range
+        .columns()
+        .find(|column| {
+            worksheet
+                .get_value_by_column_and_row(column, &range.start.1)
+                .trim()
+                == name.trim()
+        })
+        .ok_or_else(|| Error::String(format!("区域 {} 中不存在表头 {}!", range, name)))
+}
+
+/// `summarize_range` 按表头分组汇总区域数据，区域首行为表头。
+///
+/// 指定 `column_group` 时生成交叉表，行分组为 `group_by`，列分组为 `column_group` 的每个值。
+pub(crate) fn summarize_range(
+    worksheet: &Worksheet,
+    range: &CellRange,
+    group_by: &[String],
+    aggregates: &[Aggregate],
+    column_group: Option<&str>,
+) -> Result<SummaryResult, Error> {
+    if aggregates.is_empty() {
+        return Err(Error::String(String::from("聚合列不能为空!")));
+    }
+    let mut group_columns = Vec::new();
+    for name in group_by {
+        group_columns.push(header_column(worksheet, range, name)?);
+    }
+    let mut aggregate_columns = Vec::new();
+    for aggregate in aggregates {
+        aggregate_columns.push(header_column(worksheet, range, &aggregate.column)?);
+    }
+    let pivot_column = match column_group {
+        Some(name) => Some(header_column(worksheet, range, name)?),
+        None => None,
+    };
+
+    // 分组按首次出现的顺序输出。
+    let mut groups: Vec<Vec<String>> = Vec::new();
+    let mut group_index: HashMap<Vec<String>, usize> = HashMap::new();
+    let mut pivots: Vec<String> = Vec::new();
+    let mut accumulators: HashMap<(usize, usize), Vec<Accumulator>> = HashMap::new();
+    for row in range.start.1 + 1..=range.end.1 {
+        let key: Vec<String> = group_columns
+            .iter()
+            .map(|column| worksheet.get_value_by_column_and_row(column, &row))
+            .collect();
+        let group = match group_index.get(&key) {
+            Some(index) => *index,
+            None => {
+                groups.push(key.clone());
+                group_index.insert(key, groups.len() - 1);
+                groups.len() - 1
+            }
+        };
+        let pivot = match pivot_column {
+            Some(column) => {
+                let value = worksheet.get_value_by_column_and_row(&column, &row);
+                match pivots.iter().position(|pivot| *pivot == value) {
+                    Some(index) => index,
+                    None => {
+                        pivots.push(value);
+                        pivots.len() - 1
+                    }
+                }
+            }
+            None => 0,
+        };
+        let cells = accumulators
+            .entry((group, pivot))
+            .or_insert_with(|| vec![Accumulator::default(); aggregates.len()]);
+        for (i, column) in aggregate_columns.iter().enumerate() {
+            cells[i].push(&worksheet.get_value_by_column_and_row(column, &row));
+        }
+    }
+    if pivot_column.is_none() {
+        pivots.push(String::new());
+    }
+
+    let mut headers: Vec<String> = group_by.to_vec();
+    for pivot in &pivots {
+        for aggregate in aggregates {
+            headers.push(match (pivot_column, aggregates.len()) {
+                (None, _) => aggregate.name(),
+                (Some(_), 1) => pivot.clone(),
+                (Some(_), _) => format!("{} - {}", pivot, aggregate.name()),
+            });
+        }
+    }
+    let empty = vec![Accumulator::default(); aggregates.len()];
+    let rows = groups
+        .iter()
+        .enumerate()
+        .map(|(group, key)| {
+            let mut row = key.clone();
+            for pivot in 0..pivots.len() {
+                let cells = accumulators.get(&(group, pivot)).unwrap_or(&empty);
+                for (i, aggregate) in aggregates.iter().enumerate() {
+                    row.push(cells[i].result(aggregate.function));
+                }
+            }
+            row
+        })
+        .collect();
+    Ok(SummaryResult { headers, rows })
+}
+
+impl Workbook {
+    /// `summarize` 按分组汇总区域数据，不修改文件。
+    ///
+    /// # Arguments
+    ///
+    /// - `range` 数据区域或名称，首行为表头，如: "A1:F200"
+    /// - `group_by` 分组的表头名称，如: ["Region"]
+    /// - `aggregates` 聚合列，支持 sum/count/avg/min/max/distinctCount
+    /// - `column_group` 交叉表的列分组表头名称
+    pub fn summarize(
+        &self,
+        sheet_name: &str,
+        range: &str,
+        group_by: &[String],
+        aggregates: &[Aggregate],
+        column_group: Option<&str>,
+    ) -> Result<SummaryResult, Error> {
+        let range = self.resolve_range(sheet_name, range)?;
+        let worksheet = self.worksheet(sheet_name)?;
+        let result = summarize_range(worksheet, &range, group_by, aggregates, column_group)?;
+        debug!("汇总区域 {} 共 {} 组", range, result.rows.len());
+        Ok(result)
+    }
+
+    /// `write_summary` 新建名为 `target` 的 sheet 并写入汇总结果，首行为表头。
+    pub fn write_summary(&mut self, target: &str, result: &SummaryResult) -> Result<(), Error> {
+        let target_sheet = match self.spreadsheet.new_sheet(target) {
+            Ok(worksheet) => worksheet,
+            Err(error) => {
+                return Err(Error::String(format!(
+                    "新建 {} sheet 失败! {}",
+                    target, error
+                )))
+            }
+        };
+        let mut data = vec![result.headers.clone()];
+        data.extend(result.rows.iter().cloned());
+        write_rows(target_sheet, 1, 1, &data);
+        debug!("写入汇总结果到 sheet {}", target);
+        Ok(())
+    }
+}
+
+/// `summarize` 按分组汇总区域数据，可以写入新的 sheet。
+#[command]
+#[instrument(
+    level = "debug",
+    skip_all,
+    fields(workbook = %path, sheet = %sheet_name, range = %range)
+)]
+pub fn summarize<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+    sheet_name: String,
+    range: String,
+    group_by: Vec<String>,
+    aggregates: Vec<Aggregate>,
+    output: Option<SummaryOutput>,
+) -> Result<SummaryResult, Error> {
+    let column_group = output
+        .as_ref()
+        .and_then(|output| output.column_group.as_deref());
+    let summarize = |workbook: &Workbook| {
+        workbook.summarize(&sheet_name, &range, &group_by, &aggregates, column_group)
+    };
+    // 只有写入 sheet 时才会修改文件
+    let target = output
+        .as_ref()
+        .and_then(|output| output.sheet_name.as_ref());
+    let result = match target {
+        Some(target) => state.edit(&path, EditScope::Workbook, "summarize", |workbook| {
+            let result = summarize(workbook)?;
+            workbook.write_summary(target, &result)?;
+            Ok(result)
+        })?,
+        None => state.with_workbook(&path, |workbook| summarize(workbook))?,
+    };
+    if let Some(target) = target {
+        emit_changed(&app, &path, Some(target), None, ChangeKind::AddSheet);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workbook() -> Workbook {
+        let mut workbook = Workbook::new(umya_spreadsheet::new_file());
+        let rows = [
+            ["Region", "Product", "Qty", "Customer"],
+            ["East", "A", "10", "c1"],
+            ["West", "A", "5", "c2"],
+            ["East", "B", "3", "c1"],
+            ["East", "A", "7", "c3"],
+            ["West", "B", "", "c2"],
+        ];
+        let worksheet = workbook.worksheet_mut("Sheet1").unwrap();
+        for (row, values) in rows.iter().enumerate() {
+            for (column, value) in values.iter().enumerate() {
+                worksheet
+                    .get_cell_by_column_and_row_mut(&(column as u32 + 1), &(row as u32 + 1))
+                    .set_value(*value);
+            }
+        }
+        workbook
+    }
+
+    fn aggregates(value: serde_json::Value) -> Vec<Aggregate> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn summarize_aggregates() {
+        let workbook = workbook();
+        let aggregates = aggregates(serde_json::json!([
+            { "column": "Qty", "function": "sum", "alias": "total" },
+            { "column": "Qty", "function": "count" },
+            { "column": "Qty", "function": "avg" },
+            { "column": "Qty", "function": "min" },
+            { "column": "Qty", "function": "max" },
+            { "column": "Customer", "function": "distinctCount" },
+        ]));
+        let result = workbook
+            .summarize(
+                "Sheet1",
+                "A1:D6",
+                &[String::from("Region")],
+                &aggregates,
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            result.headers,
+            vec![
+                "Region",
+                "total",
+                "count(Qty)",
+                "avg(Qty)",
+                "min(Qty)",
+                "max(Qty)",
+                "distinctCount(Customer)"
+            ]
+        );
+        assert_eq!(
+            result.rows,
+            vec![
+                vec!["East", "20", "3", "6.666666666666667", "3", "10", "2"],
+                vec!["West", "5", "1", "5", "5", "5", "1"],
+            ]
+        );
+    }
+
+    #[test]
+    fn summarize_crosstab() {
+        let workbook = workbook();
+        let group_by = [String::from("Region")];
+        let sum = aggregates(serde_json::json!([{ "column": "Qty", "function": "sum" }]));
+        let result = workbook
+            .summarize("Sheet1", "A1:D6", &group_by, &sum, Some("Product"))
+            .unwrap();
+        assert_eq!(result.headers, vec!["Region", "A", "B"]);
+        assert_eq!(
+            result.rows,
+            vec![vec!["East", "17", "3"], vec!["West", "5", "0"]]
+        );
+
+        let two = aggregates(serde_json::json!([
+            { "column": "Qty", "function": "sum" },
+            { "column": "Customer", "function": "distinctCount" },
+        ]));
+        let result = workbook
+            .summarize("Sheet1", "A1:D6", &group_by, &two, Some("Product"))
+            .unwrap();
+        assert_eq!(
+            result.headers,
+            vec![
+                "Region",
+                "A - sum(Qty)",
+                "A - distinctCount(Customer)",
+                "B - sum(Qty)",
+                "B - distinctCount(Customer)"
+            ]
+        );
+        assert_eq!(result.rows[0], vec!["East", "17", "2", "3", "1"]);
+    }
+
+    #[test]
+    fn write_summary_to_new_sheet() {
+        let mut workbook = workbook();
+        let sum = aggregates(serde_json::json!([{ "column": "Qty", "function": "sum" }]));
+        assert!(workbook
+            .summarize("Sheet1", "A1:D6", &[String::from("Missing")], &sum, None)
+            .is_err());
+        assert!(workbook
+            .summarize("Sheet1", "A1:D6", &[String::from("Region")], &[], None)
+            .is_err());
+
+        let result = workbook
+            .summarize("Sheet1", "A1:D6", &[String::from("Region")], &sum, None)
+            .unwrap();
+        workbook.write_summary("Summary", &result).unwrap();
+        let summary = workbook.worksheet("Summary").unwrap();
+        assert_eq!(summary.get_value("A1"), "Region");
+        assert_eq!(summary.get_value("B1"), "sum(Qty)");
+        assert_eq!(summary.get_value("A3"), "West");
+        assert_eq!(summary.get_value("B3"), "5");
+        assert!(workbook.write_summary("Summary", &result).is_err());
+    }
+}
@@ -0,0 +1,572 @@
+use crate::encryption::read_file;
+use crate::error::Error;
+use crate::formula;
+use crate::range::CellRange;
+use crate::scope::check_path;
+use crate::workbook::{Workbook, WorkbookRegistry};
+use crate::worksheet::remove_rows;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use tauri::{command, AppHandle, Runtime, State, Window};
+use tracing::{debug, info, instrument};
+use umya_spreadsheet::{Cell, Worksheet};
+
+/// 占位符，如 `{{ customer.name | upper }}`。
+const PLACEHOLDER_PATTERN: &str = r"\{\{\s*([^{}]+?)\s*\}\}";
+/// 重复区域的开始行，如 `{{#each items}}`。
+const EACH_PATTERN: &str = r"^\{\{\s*#each\s+([^\s{}]+)\s*\}\}$";
+/// 重复区域的结束行。
+const END_EACH_PATTERN: &str = r"^\{\{\s*/each\s*\}\}$";
+
+/// 模板填充结果。
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateResult {
+    /// 替换的占位符数量。
+    pub placeholders: usize,
+    /// 重复区域生成的行数。
+    pub rows: usize,
+    /// 数据中不存在的字段，对应的占位符替换为空。
+    pub missing: Vec<String>,
+}
+
+/// 重复区域，`start` 和 `end` 为标记所在的行，填充后删除。
+#[derive(Clone, Debug)]
+struct Block {
+    start: u32,
+    end: u32,
+    key: String,
+}
+
+/// 查找占位符的值时的上下文，重复区域内先查找当前项，再查找根数据。
+#[derive(Clone, Copy)]
+struct Context<'a> {
+    root: &'a Value,
+    item: Option<(&'a Value, usize)>,
+}
+
+impl Context<'_> {
+    fn lookup(&self, path: &str) -> Option<Value> {
+        if let Some((item, index)) = self.item {
+            match path {
+                "this" => return Some(item.clone()),
+                "@index" => return Some(Value::from(index + 1)),
+                _ => {}
+            }
+            let relative = path.strip_prefix("this.").unwrap_or(path);
+            if let Some(value) = resolve(item, relative) {
+                return Some(value.clone());
+            }
+        }
+        resolve(self.root, path).cloned()
+    }
+}
+
+/// `resolve` 按 `customer.name`、`items.0.price` 形式的路径查找值。
+fn resolve<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, key| match value {
+        Value::Object(map) => map.get(key),
+        Value::Array(items) => key.parse::<usize>().ok().and_then(|index| items.get(index)),
+        _ => None,
+    })
+}
+
+/// `to_text` 把 JSON 值转换为单元格内容，空值为 `None`。
+fn to_text(value: Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(text) => Some(text),
+        Value::Bool(true) => Some("TRUE".to_string()),
+        Value::Bool(false) => Some("FALSE".to_string()),
+        Value::Number(number) => Some(number.to_string()),
+        value => Some(value.to_string()),
+    }
+}
+
+/// `apply_filter` 格式化占位符的值，支持的过滤器:
+///
+/// - `upper`、`lower`、`trim`
+/// - `default:文本` 值为空时使用的内容
+/// - `fixed:2` 保留小数位数
+/// - `percent:1` 转换为百分比，如 `0.125` -> `12.5%`
+/// - `thousands` 添加千位分隔符，如 `1234567.5` -> `1,234,567.5`
+fn apply_filter(text: Option<String>, filter: &str) -> Result<Option<String>, Error> {
+    let (name, argument) = match filter.split_once(':') {
+        Some((name, argument)) => (name.trim(), Some(argument.trim().trim_matches('"'))),
+        None => (filter.trim(), None),
+    };
+    let decimals = || -> Result<usize, Error> {
+        argument.map_or(Ok(0), |argument| {
+            argument
+                .parse()
+                .map_err(|_| Error::String(format!("过滤器 {} 的参数 {} 无效!", name, argument)))
+        })
+    };
+    let number = text
+        .as_deref()
+        .and_then(|text| text.trim().parse::<f64>().ok());
+    let text = match (name, text) {
+        ("default", text) => match text {
+            Some(text) if !text.is_empty() => Some(text),
+            _ => argument.map(str::to_string),
+        },
+        ("upper", text) => text.map(|text| text.to_uppercase()),
+        ("lower", text) => text.map(|text| text.to_lowercase()),
+        ("trim", text) => text.map(|text| text.trim().to_string()),
+        ("fixed", text) => match number {
+            Some(number) => Some(format!("{:.*}", decimals()?, number)),
+            None => text,
+        },
+        ("percent", text) => match number {
+            Some(number) => Some(format!("{:.*}%", decimals()?, number * 100.0)),
+            None => text,
+        },
+        ("thousands", text) => match number {
+            Some(_) => text.map(|text| group_thousands(text.trim())),
+            None => text,
+        },
+        (name, _) => return Err(Error::String(format!("不支持的模板过滤器 {}!", name))),
+    };
+    Ok(text)
+}
+
+/// `group_thousands` 为数字的整数部分添加千位分隔符，科学计数法等其他写法原样返回。
+fn group_thousands(number: &str) -> String {
+    let (sign, unsigned) = match number.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", number),
+    };
+    let (integer, fraction) = match unsigned.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (unsigned, None),
+    };
+    let digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    if integer.is_empty() || !digits(integer) || !fraction.map_or(true, digits) {
+        return number.to_string();
+    }
+    let number = unsigned;
+    let mut grouped = String::with_capacity(number.len() + integer.len() / 3);
+    for (index, digit) in integer.chars().enumerate() {
+        if index > 0 && (integer.len() - index) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    match fraction {
+        Some(fraction) => format!("{}{}.{}", sign, grouped, fraction),
+        None => format!("{}{}", sign, grouped),
+    }
+}
+
+/// 模板渲染器，记录替换的数量和缺失的字段。
+struct Renderer<'a> {
+    root: &'a Value,
+    placeholder: Regex,
+    each: Regex,
+    end_each: Regex,
+    result: TemplateResult,
+    missing: BTreeSet<String>,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(root: &'a Value) -> Result<Self, Error> {
+        let compile =
+            |pattern: &str| Regex::new(pattern).map_err(|error| Error::String(error.to_string()));
+        Ok(Renderer {
+            root,
+            placeholder: compile(PLACEHOLDER_PATTERN)?,
+            each: compile(EACH_PATTERN)?,
+            end_each: compile(END_EACH_PATTERN)?,
+            result: TemplateResult::default(),
+            missing: BTreeSet::new(),
+        })
+    }
+
+    /// `render_text` 替换 `text` 中的占位符，没有占位符时返回 `None`。
+    fn render_text(&mut self, text: &str, context: Context<'_>) -> Result<Option<String>, Error> {
+        if !text.contains("{{") {
+            return Ok(None);
+        }
+        let mut rendered = String::with_capacity(text.len());
+        let mut last = 0;
+        let mut found = false;
+        for captures in self.placeholder.captures_iter(text) {
+            let whole = match captures.get(0) {
+                Some(whole) => whole,
+                None => continue,
+            };
+            let mut parts = captures[1].split('|');
+            let path = parts.next().unwrap_or_default().trim();
+            let filters: Vec<&str> = parts.collect();
+            let value = context.lookup(path);
+            if value.is_none() && !filters.iter().any(|f| f.trim().starts_with("default")) {
+                self.missing.insert(path.to_string());
+            }
+            let mut value = value.and_then(to_text);
+            for filter in filters {
+                value = apply_filter(value, filter)?;
+            }
+            rendered.push_str(&text[last..whole.start()]);
+            rendered.push_str(value.as_deref().unwrap_or_default());
+            last = whole.end();
+            found = true;
+            self.result.placeholders += 1;
+        }
+        if !found {
+            return Ok(None);
+        }
+        rendered.push_str(&text[last..]);
+        Ok(Some(rendered))
+    }
+
+    /// `find_blocks` 查找 sheet 中的重复区域，按行排序，不支持嵌套。
+    fn find_blocks(&self, worksheet: &Worksheet) -> Result<Vec<Block>, Error> {
+        let mut starts = Vec::new();
+        let mut ends = Vec::new();
+        for cell in worksheet.get_cell_collection() {
+            let value = cell.get_value();
+            let row = *cell.get_coordinate().get_row_num();
+            if let Some(captures) = self.each.captures(value.trim()) {
+                starts.push((row, captures[1].to_string()));
+            } else if self.end_each.is_match(value.trim()) {
+                ends.push(row);
+            }
+        }
+        starts.sort();
+        ends.sort_unstable();
+        let mismatch = || {
+            Error::String(format!(
+                "sheet {} 中的 #each 和 /each 不匹配!",
+                worksheet.get_name()
+            ))
+        };
+        if starts.len() != ends.len() {
+            return Err(mismatch());
+        }
+        let blocks: Vec<Block> = starts
+            .into_iter()
+            .zip(ends)
+            .map(|((start, key), end)| Block { start, end, key })
+            .collect();
+        for (index, block) in blocks.iter().enumerate() {
+            let next = blocks.get(index + 1).map_or(u32::MAX, |next| next.start);
+            if block.start >= block.end || block.end >= next {
+                return Err(mismatch());
+            }
+        }
+        Ok(blocks)
+    }
+
+    /// `render_sheet` 先替换重复区域以外的占位符，再从下往上展开重复区域，已展开的行号不受影响。
+    fn render_sheet(&mut self, worksheet: &mut Worksheet) -> Result<(), Error> {
+        let blocks = self.find_blocks(worksheet)?;
+        let context = Context {
+            root: self.root,
+            item: None,
+        };
+        let cells: Vec<(u32, u32, String)> = worksheet
+            .get_cell_collection()
+            .iter()
+            .filter(|cell| cell.get_formula().is_empty())
+            .map(|cell| {
+                let coordinate = cell.get_coordinate();
+                (
+                    *coordinate.get_col_num(),
+                    *coordinate.get_row_num(),
+                    cell.get_value().to_string(),
+                )
+            })
+            .filter(|(_, row, _)| {
+                !blocks
+                    .iter()
+                    .any(|block| (block.start..=block.end).contains(row))
+            })
+            .collect();
+        for (column, row, value) in cells {
+            if let Some(rendered) = self.render_text(&value, context)? {
+                worksheet
+                    .get_cell_by_column_and_row_mut(&column, &row)
+                    .set_value(rendered);
+            }
+        }
+        for block in blocks.iter().rev() {
+            self.expand_block(worksheet, block)?;
+        }
+        Ok(())
+    }
+
+    /// `expand_block` 为每一项复制一次区域内的行，包括样式、合并单元格和行高，公式中的相对引用随之平移。
+    fn expand_block(&mut self, worksheet: &mut Worksheet, block: &Block) -> Result<(), Error> {
+        let items = match resolve(self.root, &block.key) {
+            Some(Value::Array(items)) => items.as_slice(),
+            Some(Value::Null) | None => {
+                self.missing.insert(block.key.clone());
+                &[]
+            }
+            Some(_) => return Err(Error::String(format!("模板数据 {} 不是数组!", &block.key))),
+        };
+        let first = block.start + 1;
+        let height = block.end - first;
+        let template: Vec<Cell> = worksheet
+            .get_cell_collection()
+            .into_iter()
+            .filter(|cell| (first..block.end).contains(cell.get_coordinate().get_row_num()))
+            .cloned()
+            .collect();
+        let highest = worksheet.get_highest_column_and_row();
+        let merges: Vec<CellRange> = worksheet
+            .get_merge_cells()
+            .iter()
+            .filter_map(|merge| CellRange::parse(&merge.get_range(), highest).ok())
+            .filter(|merge| merge.start.1 >= first && merge.end.1 < block.end)
+            .collect();
+        let heights: Vec<(u32, f64)> = (first..block.end)
+            .filter_map(|row| {
+                worksheet
+                    .get_row_dimension(&row)
+                    .map(|dimension| (row, *dimension.get_height()))
+            })
+            .filter(|(_, height)| *height > 0.0)
+            .collect();
+
+        let copies = items.len() as u32;
+        if copies > 1 && height > 0 {
+            worksheet.insert_new_row(&block.end, &((copies - 1) * height));
+        }
+        for (index, item) in items.iter().enumerate() {
+            let offset = index as u32 * height;
+            let context = Context {
+                root: self.root,
+                item: Some((item, index)),
+            };
+            for source in &template {
+                let mut cell = source.clone();
+                let column = *cell.get_coordinate().get_col_num();
+                let row = *cell.get_coordinate().get_row_num() + offset;
+                cell.get_coordinate_mut().set_row_num(row);
+                let cell_formula = cell.get_formula().to_string();
+                let value = cell.get_value().to_string();
+                if !cell_formula.is_empty() {
+                    cell.set_formula(formula::shift_rows(&cell_formula, offset as i64));
+                } else if let Some(rendered) = self.render_text(&value, context)? {
+                    cell.set_value(rendered);
+                }
+                *worksheet.get_cell_by_column_and_row_mut(&column, &row) = cell;
+            }
+            if offset == 0 {
+                continue;
+            }
+            for merge in &merges {
+                let range = CellRange::new(
+                    (merge.start.0, merge.start.1 + offset),
+                    (merge.end.0, merge.end.1 + offset),
+                );
+                worksheet.add_merge_cells(range.to_string());
+            }
+            for (row, row_height) in &heights {
+                worksheet
+                    .get_row_dimension_mut(&(row + offset))
+                    .set_height(*row_height);
+            }
+        }
+        self.result.rows += (copies * height) as usize;
+
+        // 删除标记行，没有数据时删除整个区域
+        if copies == 0 {
+            remove_rows(worksheet, block.start, height + 2);
+        } else {
+            remove_rows(worksheet, block.end + (copies - 1) * height, 1);
+            remove_rows(worksheet, block.start, 1);
+        }
+        debug!(
+            "展开重复区域 {} 共 {} 项，每项 {} 行",
+            &block.key, copies, height
+        );
+        Ok(())
+    }
+}
+
+impl Workbook {
+    /// `render_template` 使用 `data` 填充所有 sheet 中的占位符。
+    ///
+    /// - 占位符: `{{customer.name}}`，可以使用过滤器，如 `{{total | fixed:2 | thousands}}`
+    /// - 重复区域: `{{#each items}}` 和 `{{/each}}` 所在行之间的行按 `items` 的每一项复制一次，
+    ///   区域内的占位符先查找当前项的字段，`{{@index}}` 为从 1 开始的序号
+    pub fn render_template(&mut self, data: &Value) -> Result<TemplateResult, Error> {
+        let mut renderer = Renderer::new(data)?;
+        for worksheet in self.spreadsheet.get_sheet_collection_mut() {
+            renderer.render_sheet(worksheet)?;
+        }
+        let mut result = renderer.result;
+        result.missing = renderer.missing.into_iter().collect();
+        self.mark_dirty();
+        debug!(
+            "填充模板 {} 个占位符，重复区域 {} 行",
+            result.placeholders, result.rows
+        );
+        Ok(result)
+    }
+}
+
+/// `render_template` 使用 `data` 填充 `template_path` 模板并保存到 `out_path`，模板文件不会被修改。
+///
+/// # Arguments
+///
+/// - `template_path` 模板文件路径
+/// - `data` 填充的数据，如: `{ "customer": { "name": "ACME" }, "items": [...] }`
+/// - `out_path` 保存的文件路径，不能是已经打开的文件
+#[command]
+#[instrument(skip_all, fields(template = %template_path, workbook = %out_path))]
+pub fn render_template<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    template_path: String,
+    data: Value,
+    out_path: String,
+) -> Result<TemplateResult, Error> {
+    let template_file = check_path(&app, &template_path)?;
+    let out_file = check_path(&app, &out_path)?;
+    if state.list()?.contains(&out_path) {
+        return Err(Error::String(format!(
+            "文件 {} 已经打开，请先关闭!",
+            out_path
+        )));
+    }
+    let mut workbook = Workbook::new(read_file(&template_file, None)?);
+    let result = workbook.render_template(&data)?;
+    workbook.save(&out_file)?;
+    info!("使用模板 {} 生成 {}", template_path, out_path);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_thousands_table() {
+        let cases = [
+            ("0", "0"),
+            ("999", "999"),
+            ("1000", "1,000"),
+            ("1234567.5", "1,234,567.5"),
+            ("-1234567", "-1,234,567"),
+            ("-0.25", "-0.25"),
+            ("123456.789", "123,456.789"),
+            ("1e21", "1e21"),
+            ("inf", "inf"),
+        ];
+        for (number, expected) in cases.iter() {
+            assert_eq!(group_thousands(number), *expected, "{}", number);
+        }
+    }
+
+    #[test]
+    fn apply_filter_table() {
+        let text = |value: &str| Some(value.to_string());
+        let cases = [
+            (text("Acme"), "upper", text("ACME")),
+            (text("Acme"), "lower", text("acme")),
+            (text("  a b "), "trim", text("a b")),
+            (None, "default:N/A", text("N/A")),
+            (text(""), "default: \"none\"", text("none")),
+            (text("x"), "default:N/A", text("x")),
+            (text("3.14159"), "fixed:2", text("3.14")),
+            (text("2"), "fixed", text("2")),
+            (text("abc"), "fixed:2", text("abc")),
+            (text("0.125"), "percent:1", text("12.5%")),
+            (text("0.5"), " percent ", text("50%")),
+            (text(" 1234567.5 "), "thousands", text("1,234,567.5")),
+            (text("n/a"), "thousands", text("n/a")),
+            (None, "upper", None),
+        ];
+        for (value, filter, expected) in cases.iter() {
+            assert_eq!(
+                apply_filter(value.clone(), filter).unwrap(),
+                *expected,
+                "{:?} | {}",
+                value,
+                filter
+            );
+        }
+        assert!(apply_filter(text("1"), "fixed:x").is_err());
+        assert!(apply_filter(text("1"), "reverse").is_err());
+    }
+
+    #[test]
+    fn render_text_with_context() {
+        let data = serde_json::json!({
+            "customer": { "name": "acme" },
+            "items": [{ "name": "Widget", "price": 1200 }],
+        });
+        let mut renderer = Renderer::new(&data).unwrap();
+        let root = Context {
+            root: &data,
+            item: None,
+        };
+        assert_eq!(
+            renderer
+                .render_text("Dear {{ customer.name | upper }},", root)
+                .unwrap(),
+            Some(String::from("Dear ACME,"))
+        );
+        assert_eq!(renderer.render_text("plain", root).unwrap(), None);
+        let item = Context {
+            root: &data,
+            item: Some((&data["items"][0], 0)),
+        };
+        assert_eq!(
+            renderer
+                .render_text(
+                    "{{@index}}. {{name}} {{ price | thousands }} {{customer.name}}",
+                    item
+                )
+                .unwrap(),
+            Some(String::from("1. Widget 1,200 acme"))
+        );
+        assert_eq!(
+            renderer.render_text("{{ missing.field }}!", root).unwrap(),
+            Some(String::from("!"))
+        );
+        assert_eq!(
+            renderer.missing.iter().collect::<Vec<_>>(),
+            vec!["missing.field"]
+        );
+        assert_eq!(renderer.result.placeholders, 6);
+    }
+
+    #[test]
+    fn render_sheet_expands_blocks_and_removes_markers() {
+        let data = serde_json::json!({
+            "title": "Order",
+            "items": [{ "name": "a" }, { "name": "b" }],
+        });
+        let mut worksheet = Worksheet::default();
+        let rows = [
+            "{{title}}",
+            "{{#each items}}",
+            "{{name}}",
+            "{{/each}}",
+            "{{#each empty}}",
+            "{{name}}",
+            "{{/each}}",
+            "end",
+        ];
+        for (row, value) in rows.iter().enumerate() {
+            worksheet
+                .get_cell_by_column_and_row_mut(&1, &(row as u32 + 1))
+                .set_value(*value);
+        }
+        let mut renderer = Renderer::new(&data).unwrap();
+        renderer.render_sheet(&mut worksheet).unwrap();
+        let values: Vec<String> = (1..=rows.len() as u32)
+            .map(|row| worksheet.get_value_by_column_and_row(&1, &row))
+            .collect();
+        assert_eq!(values, vec!["Order", "a", "b", "end", "", "", "", ""]);
+        assert_eq!(worksheet.get_cell_collection().len(), 4);
+        assert_eq!(renderer.result.rows, 2);
+    }
+}
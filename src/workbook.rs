@@ -0,0 +1,463 @@
+use crate::encryption::read_file;
+use crate::error::Error;
+use crate::filter::FilterColumn;
+use crate::history::{EditScope, History};
+use crate::recovery::fnv1a;
+use crate::save::{backup, write_atomic};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Instant, SystemTime};
+use tracing::{debug, info, warn};
+use umya_spreadsheet::{new_file, reader::xlsx::read, Spreadsheet, Worksheet};
+
+/// 文件读取或保存时在磁盘上的状态，用于检测其他程序对文件的修改。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+    hash: u64,
+}
+
+impl FileStamp {
+    pub(crate) fn read(path: &Path) -> Result<Self, Error> {
+        let data = fs::read(path)?;
+        Ok(FileStamp {
+            modified: fs::metadata(path)?.modified().ok(),
+            len: data.len() as u64,
+            hash: fnv1a(&data),
+        })
+    }
+
+    /// `changed` 磁盘上的文件是否与记录的状态不同，文件已被删除或无法读取时也视为修改。
+    ///
+    /// 修改时间和大小都相同时认为没有修改，否则再比较内容的哈希值。
+    pub(crate) fn changed(&self, path: &Path) -> bool {
+        match fs::metadata(path) {
+            Ok(metadata)
+                if metadata.len() == self.len && metadata.modified().ok() == self.modified =>
+            {
+                false
+            }
+            Ok(_) => match FileStamp::read(path) {
+                Ok(current) => current.len != self.len || current.hash != self.hash,
+                Err(_) => true,
+            },
+            Err(_) => true,
+        }
+    }
+}
+
+/// `Workbook` 打开的 xlsx 文件。
+///
+/// 各功能模块在 `impl Workbook` 中实现对应的操作，Tauri 命令只是这些方法的包装。
+pub struct Workbook {
+    pub(crate) spreadsheet: Spreadsheet,
+    /// 各 sheet 的筛选条件，键为 sheet 名称。只在内存中保存，不会写入文件。
+    pub(crate) auto_filters: HashMap<String, Vec<FilterColumn>>,
+    /// 撤销和重做记录。
+    pub(crate) history: History,
+    /// 每次修改加一，用于判断是否有未保存的修改。
+    pub(crate) revision: u64,
+    /// 最近一次保存时的 `revision`。
+    pub(crate) saved_revision: u64,
+    /// 最近一次读取或保存时文件在磁盘上的状态，新建的文件为空。
+    pub(crate) disk: Option<FileStamp>,
+    /// 打开密码，不为空时保存为加密文件。
+    pub(crate) password: Option<String>,
+    /// 读写的实际文件，为前端路径经过范围检查后解析的路径，为空时直接使用打开时的路径。
+    pub(crate) file: Option<PathBuf>,
+}
+
+impl Workbook {
+    pub fn new(spreadsheet: Spreadsheet) -> Self {
+        Workbook {
+            spreadsheet,
+            auto_filters: HashMap::new(),
+            history: History::default(),
+            revision: 0,
+            saved_revision: 0,
+            disk: None,
+            password: None,
+            file: None,
+        }
+    }
+
+    /// `location` 文件在磁盘上的位置，`path` 为打开时的路径。
+    pub(crate) fn location<'a>(&'a self, path: &'a str) -> &'a Path {
+        self.file.as_deref().unwrap_or_else(|| Path::new(path))
+    }
+
+    /// `reload_from` 从 `file` 重新读取，丢弃未保存的修改和撤销记录，保留打开密码。
+    fn reload_from(&mut self, path: &str, file: PathBuf) -> Result<(), Error> {
+        let password = self.password.clone();
+        let spreadsheet = read_file(&file, password.as_deref())?;
+        *self = Workbook::new(spreadsheet);
+        self.disk = FileStamp::read(&file).ok();
+        self.file = Some(file);
+        self.password = password;
+        info!("重新读取 {} 文件", path);
+        Ok(())
+    }
+
+    /// `is_dirty` 是否有未保存的修改。
+    pub fn is_dirty(&self) -> bool {
+        self.revision != self.saved_revision
+    }
+
+    /// `mark_dirty` 标记文件已修改，通过 [`Workbook::spreadsheet_mut`] 直接修改后需要调用，
+    /// 自动保存才会写入这些修改。
+    pub fn mark_dirty(&mut self) {
+        self.revision += 1;
+    }
+
+    /// `spreadsheet` 获取 umya-spreadsheet 实例。
+    pub fn spreadsheet(&self) -> &Spreadsheet {
+        &self.spreadsheet
+    }
+
+    /// `spreadsheet_mut` 获取可修改的 umya-spreadsheet 实例。
+    pub fn spreadsheet_mut(&mut self) -> &mut Spreadsheet {
+        &mut self.spreadsheet
+    }
+
+    /// `worksheet` 根据名称获取 sheet。
+    pub fn worksheet(&self, sheet_name: &str) -> Result<&Worksheet, Error> {
+        self.spreadsheet
+            .get_sheet_by_name(sheet_name)
+            .map_err(|error| Error::String(format!("获取 sheet {} 失败! {}", sheet_name, error)))
+    }
+
+    /// `worksheet_mut` 根据名称获取可修改的 sheet。
+    pub fn worksheet_mut(&mut self, sheet_name: &str) -> Result<&mut Worksheet, Error> {
+        self.spreadsheet
+            .get_sheet_by_name_mut(sheet_name)
+            .map_err(|error| Error::String(format!("获取 sheet {} 失败! {}", sheet_name, error)))
+    }
+
+    /// `is_encrypted` 保存时是否加密。
+    pub fn is_encrypted(&self) -> bool {
+        self.password.is_some()
+    }
+
+    /// `set_password` 设置保存时使用的打开密码，为空时保存为不加密的文件。
+    pub fn set_password(&mut self, password: Option<String>) {
+        self.password = password.filter(|password| !password.is_empty());
+    }
+
+    /// `save` 将文件写入 `path`，先写入临时文件再替换，写入失败时原文件保持不变。
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        write_atomic(&self.spreadsheet, path, self.password.as_deref())?;
+        info!("保存 xlsx 文件 {}", path.display());
+        Ok(())
+    }
+}
+
+/// `WorkbookRegistry` 按文件路径管理打开的 xlsx 文件。
+///
+/// 插件会把同一个实例注册为 Tauri state，Rust 代码可以通过 [`crate::SpreadsheetExt`]
+/// 访问前端正在编辑的文件，也可以不依赖 Tauri 单独创建。
+#[derive(Default)]
+pub struct WorkbookRegistry {
+    workbooks: Mutex<HashMap<String, Workbook>>,
+    /// 同时打开的文件数量上限，为空时不限制。
+    max_workbooks: Option<usize>,
+    /// 保存时保留的备份数量，为 0 时不备份。
+    backups: usize,
+}
+
+impl WorkbookRegistry {
+    pub fn new() -> Self {
+        WorkbookRegistry::default()
+    }
+
+    /// `with_max_workbooks` 创建最多同时打开 `max_workbooks` 个文件的实例。
+    pub fn with_max_workbooks(max_workbooks: usize) -> Self {
+        WorkbookRegistry {
+            max_workbooks: Some(max_workbooks),
+            ..WorkbookRegistry::default()
+        }
+    }
+
+    /// `with_backups` 保存时把原文件备份为 `name.bak.xlsx`，最多保留 `backups` 个，
+    /// 更早的备份为 `name.bak.2.xlsx`、`name.bak.3.xlsx` 等。
+    pub fn with_backups(mut self, backups: usize) -> Self {
+        self.backups = backups;
+        self
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, HashMap<String, Workbook>>, Error> {
+        self.workbooks
+            .lock()
+            .map_err(|error| Error::String(format!("获取文件锁失败! {} ", error)))
+    }
+
+    fn check_capacity(&self, open: usize) -> Result<(), Error> {
+        match self.max_workbooks {
+            Some(max_workbooks) if open >= max_workbooks => Err(Error::String(format!(
+                "最多同时打开 {} 个文件，请先关闭其他文件!",
+                max_workbooks
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// `create` 新建 `path` 的 xlsx 文件，保存前只存在于内存中。
+    pub fn create(&self, path: &str) -> Result<(), Error> {
+        self.create_at(path, Path::new(path))
+    }
+
+    /// `create_at` 以 `path` 新建 xlsx 文件，保存时写入 `file`。
+    pub fn create_at(&self, path: &str, file: &Path) -> Result<(), Error> {
+        let mut map = self.lock()?;
+        if map.contains_key(path) {
+            let message = format!("新建文件 {} 已存在", path);
+            warn!("{}", message);
+            return Err(Error::String(message));
+        }
+        self.check_capacity(map.len())?;
+        let mut workbook = Workbook::new(new_file());
+        workbook.file = Some(file.to_path_buf());
+        map.insert(path.to_string(), workbook);
+        Ok(())
+    }
+
+    /// `open` 读取 `path` 的 xlsx 文件，已经打开的文件不会重新读取，此时返回 `false`。
+    pub fn open(&self, path: &str) -> Result<bool, Error> {
+        self.open_with_password(path, None)
+    }
+
+    /// `open_with_password` 读取 `path` 的 xlsx 文件，文件加密时使用 `password` 解密，
+    /// 保存时使用同一密码加密。
+    ///
+    /// 文件加密但没有提供密码时返回 [`Error::Encrypted`]，密码错误时返回 [`Error::Password`]。
+    pub fn open_with_password(&self, path: &str, password: Option<&str>) -> Result<bool, Error> {
+        self.open_at(path, Path::new(path), password)
+    }
+
+    /// `open_at` 读取 `file` 作为 `path` 的文件打开，之后的保存和重新读取都使用 `file`。
+    ///
+    /// 其他参数和返回值同 [`WorkbookRegistry::open_with_password`]。
+    pub fn open_at(&self, path: &str, file: &Path, password: Option<&str>) -> Result<bool, Error> {
+        let mut map = self.lock()?;
+        if map.contains_key(path) {
+            debug!("读取过文件!");
+            return Ok(false);
+        }
+        self.check_capacity(map.len())?;
+        let spreadsheet = read_file(file, password)?;
+        let mut workbook = Workbook::new(spreadsheet);
+        workbook.disk = FileStamp::read(file).ok();
+        workbook.file = Some(file.to_path_buf());
+        workbook.set_password(password.map(str::to_string));
+        map.insert(path.to_string(), workbook);
+        info!("读取 {} 文件成功!", path);
+        Ok(true)
+    }
+
+    /// `open_snapshot` 读取 `snapshot` 的内容作为 `path` 的文件打开，视为未保存的修改。
+    ///
+    /// 原文件存在时记录其当前状态，恢复后其他程序对原文件的修改同样会被检测到。
+    pub fn open_snapshot(&self, path: &str, snapshot: &Path) -> Result<(), Error> {
+        self.open_snapshot_at(path, Path::new(path), snapshot)
+    }
+
+    /// `open_snapshot_at` 同 [`WorkbookRegistry::open_snapshot`]，保存时写入 `file`。
+    pub fn open_snapshot_at(&self, path: &str, file: &Path, snapshot: &Path) -> Result<(), Error> {
+        let mut map = self.lock()?;
+        if map.contains_key(path) {
+            return Err(Error::String(format!("文件 {} 已经打开!", path)));
+        }
+        self.check_capacity(map.len())?;
+        match read(snapshot) {
+            Ok(spreadsheet) => {
+                let mut workbook = Workbook::new(spreadsheet);
+                workbook.disk = FileStamp::read(file).ok();
+                workbook.file = Some(file.to_path_buf());
+                workbook.mark_dirty();
+                map.insert(path.to_string(), workbook);
+                info!("从 {} 恢复文件 {}", snapshot.display(), path);
+                Ok(())
+            }
+            Err(error) => {
+                warn!("读取 {} 文件失败! {:?}", snapshot.display(), error);
+                Err(Error::String(format!(
+                    "读取 {} 文件失败!",
+                    snapshot.display()
+                )))
+            }
+        }
+    }
+
+    /// `save` 保存 `path` 的 xlsx 文件，文件读取后被其他程序修改过时返回 [`Error::Modified`]。
+    pub fn save(&self, path: &str) -> Result<(), Error> {
+        self.write(path, false)
+    }
+
+    /// `overwrite` 保存 `path` 的 xlsx 文件，覆盖其他程序的修改。
+    pub fn overwrite(&self, path: &str) -> Result<(), Error> {
+        self.write(path, true)
+    }
+
+    /// `save_at` 保存 `path` 的 xlsx 文件到 `file`，之后的保存也使用 `file`。
+    ///
+    /// `overwrite` 为 `false` 时同 [`WorkbookRegistry::save`]，否则同 [`WorkbookRegistry::overwrite`]。
+    pub fn save_at(&self, path: &str, file: &Path, overwrite: bool) -> Result<(), Error> {
+        self.with_workbook(path, |workbook| {
+            workbook.file = Some(file.to_path_buf());
+            Ok(())
+        })?;
+        self.write(path, overwrite)
+    }
+
+    fn write(&self, path: &str, overwrite: bool) -> Result<(), Error> {
+        self.with_workbook(path, |workbook| {
+            let target = workbook.location(path).to_path_buf();
+            let target = target.as_path();
+            let modified = workbook
+                .disk
+                .as_ref()
+                .map_or(false, |stamp| stamp.changed(target));
+            if modified && !overwrite {
+                warn!("文件 {} 已被其他程序修改，拒绝覆盖", path);
+                return Err(Error::Modified(path.to_string()));
+            }
+            backup(target, self.backups)?;
+            workbook.save(target)?;
+            workbook.saved_revision = workbook.revision;
+            workbook.disk = FileStamp::read(target).ok();
+            Ok(())
+        })
+    }
+
+    /// `is_modified` 文件读取或保存后是否被其他程序修改或删除过。
+    pub fn is_modified(&self, path: &str) -> Result<bool, Error> {
+        self.with_workbook(path, |workbook| {
+            Ok(workbook
+                .disk
+                .as_ref()
+                .map_or(false, |stamp| stamp.changed(workbook.location(path))))
+        })
+    }
+
+    /// `reload` 重新读取 `path` 的 xlsx 文件，丢弃未保存的修改和撤销记录，加密的文件使用打开时的密码。
+    pub fn reload(&self, path: &str) -> Result<(), Error> {
+        self.with_workbook(path, |workbook| {
+            let file = workbook.location(path).to_path_buf();
+            workbook.reload_from(path, file)
+        })
+    }
+
+    /// `reload_at` 从 `file` 重新读取 `path` 的 xlsx 文件，之后的保存也使用 `file`，其他同 [`WorkbookRegistry::reload`]。
+    pub fn reload_at(&self, path: &str, file: &Path) -> Result<(), Error> {
+        self.with_workbook(path, |workbook| {
+            workbook.reload_from(path, file.to_path_buf())
+        })
+    }
+
+    /// `close` 关闭 `path` 的 xlsx 文件，未保存的修改会丢失。
+    pub fn close(&self, path: &str) -> Result<(), Error> {
+        self.lock()?.remove(path);
+        info!("删除 xlsx 文件 {}!", path);
+        Ok(())
+    }
+
+    /// `close_all` 关闭所有 xlsx 文件。
+    pub fn close_all(&self) -> Result<(), Error> {
+        self.lock()?.clear();
+        info!("删除所有 xlsx 文件");
+        Ok(())
+    }
+
+    /// `list` 列出所有打开的 xlsx 文件路径。
+    pub fn list(&self) -> Result<Vec<String>, Error> {
+        let keys: Vec<String> = self.lock()?.keys().cloned().collect();
+        debug!("列表内容: {:?}", &keys);
+        Ok(keys)
+    }
+
+    /// `with_workbook` 获取 `path` 的文件并执行 `f`，执行期间持有文件锁。
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// registry.with_workbook("data.xlsx", |workbook| {
+    ///     workbook.new_sheet("Summary")
+    /// })?;
+    /// ```
+    pub fn with_workbook<T, F: FnOnce(&mut Workbook) -> Result<T, Error>>(
+        &self,
+        path: &str,
+        f: F,
+    ) -> Result<T, Error> {
+        let start = Instant::now();
+        let mut map = self.lock()?;
+        let result = match map.get_mut(path) {
+            Some(workbook) => f(workbook),
+            None => Err(Error::NotFound(path.to_string())),
+        };
+        debug!(elapsed = ?start.elapsed(), ok = result.is_ok(), "文件 {} 操作完成", path);
+        result
+    }
+
+    /// `with_worksheet` 获取 `path` 文件中的 `sheet_name` 并执行 `f`。
+    pub fn with_worksheet<T, F: FnOnce(&mut Worksheet) -> Result<T, Error>>(
+        &self,
+        path: &str,
+        sheet_name: &str,
+        f: F,
+    ) -> Result<T, Error> {
+        self.with_workbook(path, |workbook| f(workbook.worksheet_mut(sheet_name)?))
+    }
+
+    /// `edit` 获取 `path` 的文件并执行修改 `f`，修改会记录到撤销记录中，失败时恢复修改前的内容。
+    pub fn edit<T, F: FnOnce(&mut Workbook) -> Result<T, Error>>(
+        &self,
+        path: &str,
+        scope: EditScope<'_>,
+        label: &str,
+        f: F,
+    ) -> Result<T, Error> {
+        self.with_workbook(path, |workbook| workbook.record(scope, label, f))
+    }
+
+    /// `edit_worksheet` 获取 `path` 文件中的 `sheet_name` 并执行修改 `f`，同 [`WorkbookRegistry::edit`]。
+    pub fn edit_worksheet<T, F: FnOnce(&mut Worksheet) -> Result<T, Error>>(
+        &self,
+        path: &str,
+        sheet_name: &str,
+        label: &str,
+        f: F,
+    ) -> Result<T, Error> {
+        self.edit(path, EditScope::Sheet(sheet_name), label, |workbook| {
+            f(workbook.worksheet_mut(sheet_name)?)
+        })
+    }
+
+    /// `with_workbooks` 获取所有打开的文件并执行 `f`，键为文件路径。
+    pub fn with_workbooks<T, F: FnOnce(&mut HashMap<String, Workbook>) -> Result<T, Error>>(
+        &self,
+        f: F,
+    ) -> Result<T, Error> {
+        let mut workbooks = self.lock()?;
+        f(&mut workbooks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_stamp_detects_changes_and_deletion() {
+        let path = std::env::temp_dir().join(format!("stamp-{}.xlsx", std::process::id()));
+        fs::write(&path, b"first").unwrap();
+        let stamp = FileStamp::read(&path).unwrap();
+        assert!(!stamp.changed(&path));
+        fs::write(&path, b"second").unwrap();
+        assert!(stamp.changed(&path));
+        fs::remove_file(&path).unwrap();
+        assert!(stamp.changed(&path));
+    }
+}
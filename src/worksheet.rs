@@ -1,98 +1,372 @@
+use crate::config::Config;
 use crate::error::Error;
-use crate::state::SpreadsheetState;
+use crate::event::{column_span, emit_changed, row_span, ChangeKind};
+use crate::history::EditScope;
+use crate::range::{self, CellRange};
+use crate::workbook::{Workbook, WorkbookRegistry};
+use serde::Deserialize;
 use tauri::{command, AppHandle, Runtime, State, Window};
+use tracing::{debug, instrument, trace};
 use umya_spreadsheet::Worksheet;
 
-/// `get_worksheet` 根据 `path` 和 `sheet_name` 获取文件 sheet 实例。
-fn get_worksheet<T, F: FnOnce(&mut Worksheet) -> Result<T, Error>>(
-    state: State<'_, SpreadsheetState>,
-    path: String,
-    sheet_name: String,
-    f: F,
-) -> Result<T, Error> {
-    match state.spreadsheets.lock() {
-        Ok(mut map) => match map.get_mut(&path) {
-            Some(spreadsheet) => match spreadsheet.spreadsheet.get_sheet_by_name_mut(&sheet_name) {
-                Ok(worksheet) => {
-                    return f(worksheet);
-                }
-                Err(error) => {
-                    return Err(Error::String(format!(
-                        "获取 {} 文件 {} sheet 失败! {}",
-                        &path, &sheet_name, error
-                    )))
-                }
-            },
-            None => return Err(Error::String(format!("未获取 {} 文件!", &path))),
-        },
-        Err(error) => return Err(Error::String(format!("获取文件锁失败! {} ", error))),
+/// 行列范围参数，可以是 `[start, end]` 索引范围，也可以是区域字符串或名称。
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum IndexRange {
+    Index((u32, u32)),
+    Name(String),
+}
+
+/// `write_rows` 从 `(column_index, row_index)` 开始逐行写入 `data`。
+pub(crate) fn write_rows(
+    worksheet: &mut Worksheet,
+    row_index: u32,
+    column_index: u32,
+    data: &[Vec<String>],
+) {
+    for (j, row) in data.iter().enumerate() {
+        let row_index = row_index + j as u32;
+        for (i, v) in row.iter().enumerate() {
+            let column_index = column_index + i as u32;
+            worksheet
+                .get_cell_by_column_and_row_mut(&column_index, &row_index)
+                .set_value(v);
+        }
     }
+    debug!(cells = cell_count(data), "写入 {} 行数据", data.len());
 }
 
-/// `append_column` 附加指定内容的列到表格最后一列后面。
-#[command]
-pub fn append_column<R: Runtime>(
-    _app: AppHandle<R>,
-    _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
-    path: String,
-    sheet_name: String,
-    data: Vec<Vec<String>>,
-) -> Result<(), Error> {
-    get_worksheet(state, path, sheet_name, |worksheet| {
+/// `remove_rows` 删除从 `row_index` 开始的 `num_rows` 行。
+///
+/// umya-spreadsheet 0.8 删除行时只移动单元格坐标，不会删除这些行中的单元格，需要先删除。
+pub(crate) fn remove_rows(worksheet: &mut Worksheet, row_index: u32, num_rows: u32) {
+    let end = row_index.saturating_add(num_rows);
+    clear_cells(worksheet, |_, row| row >= row_index && row < end);
+    worksheet.remove_row(&row_index, &num_rows);
+}
+
+/// `remove_columns` 删除从 `column_index` 开始的 `num_columns` 列，同 [`remove_rows`]。
+pub(crate) fn remove_columns(worksheet: &mut Worksheet, column_index: u32, num_columns: u32) {
+    let end = column_index.saturating_add(num_columns);
+    clear_cells(worksheet, |column, _| {
+        column >= column_index && column < end
+    });
+    worksheet.remove_column_by_index(&column_index, &num_columns);
+}
+
+/// `clear_cells` 删除坐标满足 `f(column, row)` 的单元格。
+fn clear_cells<F: Fn(u32, u32) -> bool>(worksheet: &mut Worksheet, f: F) {
+    let coordinates: Vec<(u32, u32)> = worksheet
+        .get_cell_collection()
+        .iter()
+        .map(|cell| {
+            let coordinate = cell.get_coordinate();
+            (*coordinate.get_col_num(), *coordinate.get_row_num())
+        })
+        .filter(|(column, row)| f(*column, *row))
+        .collect();
+    for (column, row) in coordinates {
+        worksheet.remove_cell_by_column_and_row_mut(&column, &row);
+    }
+}
+
+/// `data_range` 从 `start` 开始写入 `width` 列、`height` 行内容时覆盖的区域。
+fn data_range(start: (u32, u32), width: u32, height: u32) -> CellRange {
+    CellRange::new(
+        start,
+        (start.0 + width.max(1) - 1, start.1 + height.max(1) - 1),
+    )
+}
+
+/// `cell_count` 统计 `data` 中的单元格数量。
+pub(crate) fn cell_count(data: &[Vec<String>]) -> usize {
+    data.iter().map(|row| row.len()).sum()
+}
+
+impl Workbook {
+    /// `append_column` 附加指定内容的列到表格最后一列后面。
+    pub fn append_column(&mut self, sheet_name: &str, data: &[Vec<String>]) -> Result<(), Error> {
+        let worksheet = self.worksheet_mut(sheet_name)?;
         let mut column_index = worksheet.get_highest_column();
         for column in data {
             column_index += 1;
             for (i, v) in column.iter().enumerate() {
                 let row_index: u32 = i as u32 + 1;
-                println!("插入数据: {}, {}, {}", &row_index, &column_index, &v);
                 worksheet
                     .get_cell_by_column_and_row_mut(&column_index, &row_index)
                     .set_value(v);
             }
         }
+        debug!(cells = cell_count(data), "附加 {} 列数据", data.len());
         Ok(())
-    })
-}
+    }
 
-/// `append_row` 附加内容行到表格末尾。
-#[command]
-pub fn append_row<R: Runtime>(
-    _app: AppHandle<R>,
-    _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
-    path: String,
-    sheet_name: String,
-    data: Vec<Vec<String>>,
-) -> Result<(), Error> {
-    get_worksheet(state, path, sheet_name.clone(), |worksheet| {
-        let mut row_index = worksheet.get_highest_row();
+    /// `append_row` 附加内容行到表格末尾。
+    pub fn append_row(&mut self, sheet_name: &str, data: &[Vec<String>]) -> Result<(), Error> {
+        let worksheet = self.worksheet_mut(sheet_name)?;
+        let row_index = worksheet.get_highest_row() + 1;
+        write_rows(worksheet, row_index, 1, data);
+        Ok(())
+    }
+
+    /// `get_collection_by_column` 获取 `range` 范围内各列的值。
+    ///
+    /// `range` 为 `[start, end]` 时按列索引读取整列，为区域字符串或名称时只读取区域内的单元格。
+    pub fn get_collection_by_column(
+        &self,
+        sheet_name: &str,
+        range: &IndexRange,
+    ) -> Result<Vec<Vec<String>>, Error> {
+        let worksheet = self.worksheet(sheet_name)?;
+        let (start, end) = match range {
+            IndexRange::Index(range) => *range,
+            IndexRange::Name(name) => {
+                let range = self.resolve_range(sheet_name, name)?;
+                return Ok(range
+                    .columns()
+                    .map(|column| {
+                        range
+                            .rows()
+                            .map(|row| worksheet.get_value_by_column_and_row(&column, &row))
+                            .collect()
+                    })
+                    .collect());
+            }
+        };
+        let mut data: Vec<Vec<String>> = Vec::new();
+        for i in start..=end {
+            let collection = worksheet.get_collection_by_column(&i);
+            let mut temp = vec![String::from(""); collection.len()];
+            for (_, item) in collection.iter().enumerate() {
+                if let Ok(row) = item
+                    .get_coordinate()
+                    .get_row_num()
+                    .to_string()
+                    .parse::<usize>()
+                {
+                    if row - 1 < temp.len() {
+                        temp[row - 1] = item.get_value().to_string();
+                    }
+                }
+            }
+            data.push(temp)
+        }
+        Ok(data)
+    }
+
+    /// `get_collection_by_row` 获取 `range` 范围内各行的值。
+    ///
+    /// `range` 为 `[start, end]` 时按行索引读取整行，为区域字符串或名称时只读取区域内的单元格。
+    pub fn get_collection_by_row(
+        &self,
+        sheet_name: &str,
+        range: &IndexRange,
+    ) -> Result<Vec<Vec<String>>, Error> {
+        let worksheet = self.worksheet(sheet_name)?;
+        let (start, end) = match range {
+            IndexRange::Index(range) => *range,
+            IndexRange::Name(name) => {
+                let range = self.resolve_range(sheet_name, name)?;
+                return Ok(range
+                    .rows()
+                    .map(|row| {
+                        range
+                            .columns()
+                            .map(|column| worksheet.get_value_by_column_and_row(&column, &row))
+                            .collect()
+                    })
+                    .collect());
+            }
+        };
+        let mut data: Vec<Vec<String>> = Vec::new();
+        for i in start..=end {
+            let collection = worksheet.get_collection_by_row(&i);
+            let mut temp = vec![String::from(""); collection.len()];
+            for (_, item) in collection.iter().enumerate() {
+                if let Ok(column) = item
+                    .get_coordinate()
+                    .get_col_num()
+                    .to_string()
+                    .parse::<usize>()
+                {
+                    if column - 1 < temp.len() {
+                        temp[column - 1] = item.get_value().to_string();
+                    }
+                }
+            }
+            data.push(temp)
+        }
+        Ok(data)
+    }
+
+    /// `get_value_by_column_and_row` 根据 (column, row) 获取值。
+    pub fn get_value_by_column_and_row(
+        &self,
+        sheet_name: &str,
+        local: (u32, u32),
+    ) -> Result<String, Error> {
+        let worksheet = self.worksheet(sheet_name)?;
+        let data = worksheet.get_value_by_column_and_row(&local.0, &local.1);
+        trace!("获取 sheet 位置 {:?} 的值: {}", &local, &data);
+        Ok(data)
+    }
+
+    /// `insert_column` 在 `column_index` 位置写入指定内容的列，`is_add` 为 `true` 时先插入空白列。
+    pub fn insert_column(
+        &mut self,
+        sheet_name: &str,
+        column_index: u32,
+        data: &[Vec<String>],
+        is_add: bool,
+    ) -> Result<(), Error> {
+        let worksheet = self.worksheet_mut(sheet_name)?;
+        if is_add {
+            let num_columns: u32 = data.len().try_into().unwrap();
+            worksheet.insert_new_column_by_index(&column_index, &num_columns);
+            debug!("插入 sheet 列 {}, {}", &column_index, &num_columns);
+        }
+        let mut column_index = column_index;
+        for column in data {
+            for (i, v) in column.iter().enumerate() {
+                let row_index: u32 = i as u32 + 1;
+                worksheet
+                    .get_cell_by_column_and_row_mut(&column_index, &row_index)
+                    .set_value(v);
+            }
+            column_index += 1;
+        }
+        debug!(cells = cell_count(data), "写入 {} 列数据", data.len());
+        Ok(())
+    }
+
+    /// `insert_row` 在 `row_index` 位置写入指定内容的行，`is_add` 为 `true` 时先插入空白行。
+    pub fn insert_row(
+        &mut self,
+        sheet_name: &str,
+        row_index: u32,
+        data: &[Vec<String>],
+        is_add: bool,
+    ) -> Result<(), Error> {
+        let worksheet = self.worksheet_mut(sheet_name)?;
+        if is_add {
+            let num_rows: u32 = data.len().try_into().unwrap();
+            worksheet.insert_new_row(&row_index, &num_rows);
+            debug!("插入 sheet 行 {}, {}", &row_index, &num_rows);
+        }
+        let mut row_index = row_index;
         for row in data {
-            row_index += 1;
             for (i, v) in row.iter().enumerate() {
                 let column_index: u32 = i as u32 + 1;
-                println!("插入数据: {}, {}, {}", &row_index, &column_index, &v);
                 worksheet
                     .get_cell_by_column_and_row_mut(&column_index, &row_index)
                     .set_value(v);
             }
+            row_index += 1;
         }
+        debug!(cells = cell_count(data), "写入 {} 行数据", data.len());
         Ok(())
-    })
+    }
+
+    /// `remove_row` 删除从 `row_index` 开始的 `num_rows` 行，超出最大行数的部分忽略。
+    pub fn remove_row(
+        &mut self,
+        sheet_name: &str,
+        row_index: u32,
+        num_rows: u32,
+    ) -> Result<(), Error> {
+        let worksheet = self.worksheet_mut(sheet_name)?;
+        let highest_row = worksheet.get_highest_row();
+        let mut num_rows = num_rows;
+        if row_index < 1 {
+            return Err(Error::String(String::from("row_index 参数不能小于 1!")));
+        }
+        if num_rows < 1 {
+            return Err(Error::String(String::from("num_rows 参数不能小于 1!")));
+        }
+        if row_index >= highest_row {
+            return Err(Error::String(String::from(
+                "row_index 参数大小超过最大行数!",
+            )));
+        } else if row_index + num_rows > highest_row {
+            num_rows = highest_row - row_index;
+        }
+
+        debug!("移除 sheet 行 {}, {}", &row_index, &num_rows);
+        remove_rows(worksheet, row_index, num_rows);
+        Ok(())
+    }
+
+    /// `set_value_by_column_and_row` 根据 (column, row) 设置值。
+    pub fn set_value_by_column_and_row(
+        &mut self,
+        sheet_name: &str,
+        local: (u32, u32),
+        value: &str,
+    ) -> Result<(), Error> {
+        let worksheet = self.worksheet_mut(sheet_name)?;
+        worksheet
+            .get_cell_by_column_and_row_mut(&local.0, &local.1)
+            .set_value(value);
+        trace!("设置 sheet 的位置 {:?} 的值为 {}", &local, value);
+        Ok(())
+    }
+}
+
+/// `append_column` 附加指定内容的列到表格最后一列后面。
+#[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, sheet = %sheet_name))]
+pub fn append_column<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+    sheet_name: String,
+    data: Vec<Vec<String>>,
+) -> Result<(), Error> {
+    state.edit(
+        &path,
+        EditScope::AppendColumns(&sheet_name),
+        "append_column",
+        |workbook| workbook.append_column(&sheet_name, &data),
+    )?;
+    emit_changed(&app, &path, Some(&sheet_name), None, ChangeKind::Values);
+    Ok(())
+}
+
+/// `append_row` 附加内容行到表格末尾。
+#[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, sheet = %sheet_name))]
+pub fn append_row<R: Runtime>(
+    app: AppHandle<R>,
+    _window: Window<R>,
+    state: State<'_, WorkbookRegistry>,
+    path: String,
+    sheet_name: String,
+    data: Vec<Vec<String>>,
+) -> Result<(), Error> {
+    state.edit(
+        &path,
+        EditScope::AppendRows(&sheet_name),
+        "append_row",
+        |workbook| workbook.append_row(&sheet_name, &data),
+    )?;
+    emit_changed(&app, &path, Some(&sheet_name), None, ChangeKind::Values);
+    Ok(())
 }
 
 /// `get_sheet_highest_column` 获取 sheet 最大列数。
 #[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, sheet = %sheet_name))]
 pub fn get_sheet_highest_column<R: Runtime>(
     _app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
     path: String,
     sheet_name: String,
 ) -> Result<u32, Error> {
-    get_worksheet(state, path, sheet_name.clone(), |worksheet| {
+    state.with_worksheet(&path, &sheet_name, |worksheet| {
         let data = worksheet.get_highest_column();
-        println!("获取 sheet {} 列数: {}", &sheet_name, &data);
+        debug!("获取 sheet {} 列数: {}", &sheet_name, &data);
         Ok(data)
     })
 }
@@ -103,237 +377,275 @@ pub fn get_sheet_highest_column<R: Runtime>(
 ///
 /// `(u32, u32)` - (column, row)
 #[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, sheet = %sheet_name))]
 pub fn get_sheet_highest_column_and_row<R: Runtime>(
     _app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
     path: String,
     sheet_name: String,
 ) -> Result<(u32, u32), Error> {
-    get_worksheet(state, path, sheet_name, |worksheet| {
+    state.with_worksheet(&path, &sheet_name, |worksheet| {
         let data = worksheet.get_highest_column_and_row();
-        println!("获取 sheet 行列 {:?}", data);
+        debug!("获取 sheet 行列 {:?}", data);
         Ok(data)
     })
 }
 
 /// `get_sheet_highest_raw` 获取 sheet 最大行数。
 #[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, sheet = %sheet_name))]
 pub fn get_sheet_highest_row<R: Runtime>(
     _app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
     path: String,
     sheet_name: String,
 ) -> Result<u32, Error> {
-    get_worksheet(state, path, sheet_name.clone(), |worksheet| {
+    state.with_worksheet(&path, &sheet_name, |worksheet| {
         let data = worksheet.get_highest_row();
-        println!("获取 sheet {} 行数: {}", &sheet_name, &data);
+        debug!("获取 sheet {} 行数: {}", &sheet_name, &data);
         Ok(data)
     })
 }
 
 /// `get_value_by_column_and_row` 根据行列获取值。
 #[command]
+#[instrument(
+    level = "debug",
+    skip_all,
+    fields(workbook = %path, sheet = %sheet_name, cell = ?local)
+)]
 pub fn get_value_by_column_and_row<R: Runtime>(
     _app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
     path: String,
     sheet_name: String,
     local: (u32, u32),
 ) -> Result<String, Error> {
-    get_worksheet(state, path, sheet_name, |worksheet| {
-        let data = worksheet.get_value_by_column_and_row(&local.0, &local.1);
-        println!("获取 sheet 位置 {:?} 的值: {}", &local, &data);
-        Ok(data)
+    state.with_workbook(&path, |workbook| {
+        workbook.get_value_by_column_and_row(&sheet_name, local)
     })
 }
 
+/// `get_collection_by_column` 获取 `range` 范围内各列的值。
 #[command]
+#[instrument(
+    level = "debug",
+    skip_all,
+    fields(workbook = %path, sheet = %sheet_name, range = ?range)
+)]
 pub fn get_collection_by_column<R: Runtime>(
     _app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
+    config: State<'_, Config>,
     path: String,
     sheet_name: String,
-    range: (u32, u32),
+    range: IndexRange,
 ) -> Result<Vec<Vec<String>>, Error> {
-    get_worksheet(state, path, sheet_name, |worksheet| {
-        let mut data: Vec<Vec<String>> = Vec::new();
-        let (start, end) = range;
-        for i in start..=end {
-            let collection = worksheet.get_collection_by_column(&i);
-            let mut temp = vec![String::from(""); collection.len()];
-            for (_, item) in collection.iter().enumerate() {
-                if let Ok(row) = item
-                    .get_coordinate()
-                    .get_row_num()
-                    .to_string()
-                    .parse::<usize>()
-                {
-                    if row - 1 < temp.len() {
-                        temp[row - 1] = item.get_value().to_string();
-                    }
-                }
-            }
-            data.push(temp)
-        }
-        Ok(data)
-    })
+    let data = state.with_workbook(&path, |workbook| {
+        workbook.get_collection_by_column(&sheet_name, &range)
+    })?;
+    debug!(cells = cell_count(&data), "读取 {} 组数据", data.len());
+    config.check_cells(&data)?;
+    Ok(data)
 }
 
+/// `get_collection_by_row` 获取 `range` 范围内各行的值。
 #[command]
+#[instrument(
+    level = "debug",
+    skip_all,
+    fields(workbook = %path, sheet = %sheet_name, range = ?range)
+)]
 pub fn get_collection_by_row<R: Runtime>(
     _app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
+    config: State<'_, Config>,
     path: String,
     sheet_name: String,
-    range: (u32, u32),
+    range: IndexRange,
 ) -> Result<Vec<Vec<String>>, Error> {
-    get_worksheet(state, path, sheet_name, |worksheet| {
-        let mut data: Vec<Vec<String>> = Vec::new();
-        let (start, end) = range;
-        for i in start..=end {
-            let collection = worksheet.get_collection_by_row(&i);
-            let mut temp = vec![String::from(""); collection.len()];
-            for (_, item) in collection.iter().enumerate() {
-                if let Ok(column) = item
-                    .get_coordinate()
-                    .get_col_num()
-                    .to_string()
-                    .parse::<usize>()
-                {
-                    if column - 1 < temp.len() {
-                        temp[column - 1] = item.get_value().to_string();
-                    }
-                }
-            }
-            data.push(temp)
-        }
-        Ok(data)
-    })
+    let data = state.with_workbook(&path, |workbook| {
+        workbook.get_collection_by_row(&sheet_name, &range)
+    })?;
+    debug!(cells = cell_count(&data), "读取 {} 组数据", data.len());
+    config.check_cells(&data)?;
+    Ok(data)
 }
 
 /// `insert_column` 在指定位置插入指定内容的列。
 #[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, sheet = %sheet_name))]
 pub fn insert_column<R: Runtime>(
-    _app: AppHandle<R>,
+    app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
     path: String,
     sheet_name: String,
     column_index: u32,
     data: Vec<Vec<String>>,
     is_add: bool,
 ) -> Result<(), Error> {
-    get_worksheet(state, path, sheet_name, |worksheet| {
-        if is_add {
-            let num_columns: u32 = data.len().try_into().unwrap();
-            worksheet.insert_new_column_by_index(&column_index, &num_columns);
-            println!("插入 sheet 列 {}, {}", &column_index, &num_columns);
-        }
-        let mut column_index = column_index;
-        for column in data {
-            for (i, v) in column.iter().enumerate() {
-                let row_index: u32 = i as u32 + 1;
-                println!("插入数据: {}, {}, {}", &row_index, &column_index, &v);
-                worksheet
-                    .get_cell_by_column_and_row_mut(&column_index, &row_index)
-                    .set_value(v);
-            }
-            column_index += 1;
-        }
-        Ok(())
-    })
+    let count = data.len() as u32;
+    let scope = if is_add {
+        EditScope::InsertColumns(&sheet_name, column_index, count)
+    } else {
+        let height = data.iter().map(Vec::len).max().unwrap_or(0) as u32;
+        EditScope::Cells(&sheet_name, data_range((column_index, 1), count, height))
+    };
+    state.edit(&path, scope, "insert_column", |workbook| {
+        workbook.insert_column(&sheet_name, column_index, &data, is_add)
+    })?;
+    let kind = if is_add {
+        ChangeKind::InsertColumns
+    } else {
+        ChangeKind::Values
+    };
+    let range = column_span(column_index, count);
+    emit_changed(&app, &path, Some(&sheet_name), Some(range), kind);
+    Ok(())
 }
 
 /// `insert_new_column` 在 `column` 位置插入 `num_columns` 列。
 #[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, sheet = %sheet_name))]
 pub fn insert_new_column<R: Runtime>(
-    _app: AppHandle<R>,
+    app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
     path: String,
     sheet_name: String,
     column: String,
     num_columns: u32,
 ) -> Result<(), Error> {
-    get_worksheet(state, path, sheet_name, |worksheet| {
-        worksheet.insert_new_column(&column, &num_columns);
-        println!("插入空白列数据: {}, {}", column, num_columns);
+    let column_index = range::column_index_from_string(&column);
+    let scope = match column_index {
+        Some(index) => EditScope::InsertColumns(&sheet_name, index, num_columns),
+        None => EditScope::Sheet(&sheet_name),
+    };
+    state.edit(&path, scope, "insert_new_column", |workbook| {
+        workbook
+            .worksheet_mut(&sheet_name)?
+            .insert_new_column(&column, &num_columns);
+        debug!("插入空白列数据: {}, {}", column, num_columns);
         Ok(())
-    })
+    })?;
+    let range = column_index.map(|index| column_span(index, num_columns));
+    emit_changed(
+        &app,
+        &path,
+        Some(&sheet_name),
+        range,
+        ChangeKind::InsertColumns,
+    );
+    Ok(())
 }
 
 /// `insert_new_column_by_index` 在 `column_index` 位置插入 `num_columns` 列。
 #[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, sheet = %sheet_name))]
 pub fn insert_new_column_by_index<R: Runtime>(
-    _app: AppHandle<R>,
+    app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
     path: String,
     sheet_name: String,
     column_index: u32,
     num_columns: u32,
 ) -> Result<(), Error> {
-    get_worksheet(state, path, sheet_name, |worksheet| {
-        worksheet.insert_new_column_by_index(&column_index, &num_columns);
-        println!("移除列数据: {}, {}", column_index, num_columns);
-        Ok(())
-    })
+    state.edit(
+        &path,
+        EditScope::InsertColumns(&sheet_name, column_index, num_columns),
+        "insert_new_column_by_index",
+        |workbook| {
+            workbook
+                .worksheet_mut(&sheet_name)?
+                .insert_new_column_by_index(&column_index, &num_columns);
+            debug!("移除列数据: {}, {}", column_index, num_columns);
+            Ok(())
+        },
+    )?;
+    let range = column_span(column_index, num_columns);
+    emit_changed(
+        &app,
+        &path,
+        Some(&sheet_name),
+        Some(range),
+        ChangeKind::InsertColumns,
+    );
+    Ok(())
 }
 
 /// `insert_new_row` 在指定位置插入指定空白行。
 #[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, sheet = %sheet_name))]
 pub fn insert_new_row<R: Runtime>(
-    _app: AppHandle<R>,
+    app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
     path: String,
     sheet_name: String,
     row_index: u32,
     num_rows: u32,
 ) -> Result<(), Error> {
-    get_worksheet(state, path, sheet_name, |worksheet| {
-        worksheet.insert_new_row(&row_index, &num_rows);
-        println!("插入 sheet 行 {}, {}", &row_index, &num_rows);
-        Ok(())
-    })
+    state.edit(
+        &path,
+        EditScope::InsertRows(&sheet_name, row_index, num_rows),
+        "insert_new_row",
+        |workbook| {
+            workbook
+                .worksheet_mut(&sheet_name)?
+                .insert_new_row(&row_index, &num_rows);
+            debug!("插入 sheet 行 {}, {}", &row_index, &num_rows);
+            Ok(())
+        },
+    )?;
+    let range = row_span(row_index, num_rows);
+    emit_changed(
+        &app,
+        &path,
+        Some(&sheet_name),
+        Some(range),
+        ChangeKind::InsertRows,
+    );
+    Ok(())
 }
 
 /// `insert_row` 在指定位置插入指定内容的行。
 #[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, sheet = %sheet_name))]
 pub fn insert_row<R: Runtime>(
-    _app: AppHandle<R>,
+    app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
     path: String,
     sheet_name: String,
     row_index: u32,
     data: Vec<Vec<String>>,
     is_add: bool,
 ) -> Result<(), Error> {
-    get_worksheet(state, path, sheet_name, |worksheet| {
-        if is_add {
-            let num_rows: u32 = data.len().try_into().unwrap();
-            worksheet.insert_new_row(&row_index, &num_rows);
-            println!("插入 sheet 行 {}, {}", &row_index, &num_rows);
-        }
-        let mut row_index = row_index;
-        for row in data {
-            for (i, v) in row.iter().enumerate() {
-                let column_index: u32 = i as u32 + 1;
-                println!("插入数据: {}, {}, {}", &row_index, &column_index, &v);
-                worksheet
-                    .get_cell_by_column_and_row_mut(&column_index, &row_index)
-                    .set_value(v);
-            }
-            row_index += 1;
-        }
-        Ok(())
-    })
+    let count = data.len() as u32;
+    let scope = if is_add {
+        EditScope::InsertRows(&sheet_name, row_index, count)
+    } else {
+        let width = data.iter().map(Vec::len).max().unwrap_or(0) as u32;
+        EditScope::Cells(&sheet_name, data_range((1, row_index), width, count))
+    };
+    state.edit(&path, scope, "insert_row", |workbook| {
+        workbook.insert_row(&sheet_name, row_index, &data, is_add)
+    })?;
+    let kind = if is_add {
+        ChangeKind::InsertRows
+    } else {
+        ChangeKind::Values
+    };
+    let range = row_span(row_index, count);
+    emit_changed(&app, &path, Some(&sheet_name), Some(range), kind);
+    Ok(())
 }
 
 /// `remove_column` 删除从 `column` 开始的 `num_columns` 列。
@@ -343,20 +655,34 @@ pub fn insert_row<R: Runtime>(
 /// - `column` 列数的字符串，如: "B"
 /// - `num_columns` 要移除的列数，如："3"
 #[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, sheet = %sheet_name))]
 pub fn remove_column<R: Runtime>(
-    _app: AppHandle<R>,
+    app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
     path: String,
     sheet_name: String,
     column: String,
     num_columns: u32,
 ) -> Result<(), Error> {
-    get_worksheet(state, path, sheet_name, |worksheet| {
-        worksheet.remove_column(&column, &num_columns);
-        println!("移除列数据: {}, {}", column, num_columns);
+    state.edit_worksheet(&path, &sheet_name, "remove_column", |worksheet| {
+        match range::column_index_from_string(&column) {
+            Some(column_index) => remove_columns(worksheet, column_index, num_columns),
+            None => worksheet.remove_column(&column, &num_columns),
+        }
+        debug!("移除列数据: {}, {}", column, num_columns);
         Ok(())
-    })
+    })?;
+    let range =
+        range::column_index_from_string(&column).map(|index| column_span(index, num_columns));
+    emit_changed(
+        &app,
+        &path,
+        Some(&sheet_name),
+        range,
+        ChangeKind::RemoveColumns,
+    );
+    Ok(())
 }
 
 /// `remove_column_by_index` 删除从 `column_index` 开始的 `num_columns` 列。
@@ -366,72 +692,90 @@ pub fn remove_column<R: Runtime>(
 /// - `column_index` 列数的索引数，如: "2"
 /// - `num_columns` 要移除的列数，如："3"
 #[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, sheet = %sheet_name))]
 pub fn remove_column_by_index<R: Runtime>(
-    _app: AppHandle<R>,
+    app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
     path: String,
     sheet_name: String,
     column_index: u32,
     num_columns: u32,
 ) -> Result<(), Error> {
-    get_worksheet(state, path, sheet_name, |worksheet| {
-        worksheet.remove_column_by_index(&column_index, &num_columns);
-        println!("移除列数据: {}, {}", column_index, num_columns);
+    state.edit_worksheet(&path, &sheet_name, "remove_column_by_index", |worksheet| {
+        remove_columns(worksheet, column_index, num_columns);
+        debug!("移除列数据: {}, {}", column_index, num_columns);
         Ok(())
-    })
+    })?;
+    let range = column_span(column_index, num_columns);
+    emit_changed(
+        &app,
+        &path,
+        Some(&sheet_name),
+        Some(range),
+        ChangeKind::RemoveColumns,
+    );
+    Ok(())
 }
 
 /// `reomove_row` 删除行。
 #[command]
+#[instrument(level = "debug", skip_all, fields(workbook = %path, sheet = %sheet_name))]
 pub fn remove_row<R: Runtime>(
-    _app: AppHandle<R>,
+    app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
     path: String,
     sheet_name: String,
     row_index: u32,
     num_rows: u32,
 ) -> Result<(), Error> {
-    get_worksheet(state, path, sheet_name, |worksheet| {
-        let highest_row = worksheet.get_highest_row();
-        let mut num_rows = num_rows;
-        if row_index < 1 {
-            return Err(Error::String(String::from("row_index 参数不能小于 1!")));
-        }
-        if num_rows < 1 {
-            return Err(Error::String(String::from("num_rows 参数不能小于 1!")));
-        }
-        if row_index >= highest_row {
-            return Err(Error::String(String::from(
-                "row_index 参数大小超过最大行数!",
-            )));
-        } else if row_index + num_rows > highest_row {
-            num_rows = highest_row - row_index;
-        }
-
-        println!("移除 sheet 行 {}, {}", &row_index, &num_rows);
-        worksheet.remove_row(&row_index, &num_rows);
-        Ok(())
-    })
+    state.edit(
+        &path,
+        EditScope::Sheet(&sheet_name),
+        "remove_row",
+        |workbook| workbook.remove_row(&sheet_name, row_index, num_rows),
+    )?;
+    let range = row_span(row_index, num_rows);
+    emit_changed(
+        &app,
+        &path,
+        Some(&sheet_name),
+        Some(range),
+        ChangeKind::RemoveRows,
+    );
+    Ok(())
 }
 
 /// `set_value_by_column_and_row` 根据行列设置值。
 #[command]
+#[instrument(
+    level = "debug",
+    skip_all,
+    fields(workbook = %path, sheet = %sheet_name, cell = ?local)
+)]
 pub fn set_value_by_column_and_row<R: Runtime>(
-    _app: AppHandle<R>,
+    app: AppHandle<R>,
     _window: Window<R>,
-    state: State<'_, SpreadsheetState>,
+    state: State<'_, WorkbookRegistry>,
     path: String,
     sheet_name: String,
     local: (u32, u32),
     value: String,
 ) -> Result<(), Error> {
-    get_worksheet(state, path, sheet_name, |worksheet| {
-        worksheet
-            .get_cell_by_column_and_row_mut(&local.0, &local.1)
-            .set_value(value.clone());
-        println!("设置 sheet 的位置 {:?} 的值为 {}", &local, value);
-        Ok(())
-    })
+    state.edit(
+        &path,
+        EditScope::Cells(&sheet_name, CellRange::new(local, local)),
+        "set_value_by_column_and_row",
+        |workbook| workbook.set_value_by_column_and_row(&sheet_name, local, &value),
+    )?;
+    let range = range::coordinate(local.0, local.1);
+    emit_changed(
+        &app,
+        &path,
+        Some(&sheet_name),
+        Some(range),
+        ChangeKind::Values,
+    );
+    Ok(())
 }
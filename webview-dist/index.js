@@ -1 +1,854 @@
-function e(e,t,n,r){return new(n||(n=Promise))((function(i,o){function s(e){try{a(r.next(e))}catch(e){o(e)}}function u(e){try{a(r.throw(e))}catch(e){o(e)}}function a(e){var t;e.done?i(e.value):(t=e.value,t instanceof n?t:new n((function(e){e(t)}))).then(s,u)}a((r=r.apply(e,t||[])).next())}))}function t(e,t){var n,r,i,o,s={label:0,sent:function(){if(1&i[0])throw i[1];return i[1]},trys:[],ops:[]};return o={next:u(0),throw:u(1),return:u(2)},"function"==typeof Symbol&&(o[Symbol.iterator]=function(){return this}),o;function u(o){return function(u){return function(o){if(n)throw new TypeError("Generator is already executing.");for(;s;)try{if(n=1,r&&(i=2&o[0]?r.return:o[0]?r.throw||((i=r.return)&&i.call(r),0):r.next)&&!(i=i.call(r,o[1])).done)return i;switch(r=0,i&&(o=[2&o[0],i.value]),o[0]){case 0:case 1:i=o;break;case 4:return s.label++,{value:o[1],done:!1};case 5:s.label++,r=o[1],o=[0];continue;case 7:o=s.ops.pop(),s.trys.pop();continue;default:if(!(i=s.trys,(i=i.length>0&&i[i.length-1])||6!==o[0]&&2!==o[0])){s=0;continue}if(3===o[0]&&(!i||o[1]>i[0]&&o[1]<i[3])){s.label=o[1];break}if(6===o[0]&&s.label<i[1]){s.label=i[1],i=o;break}if(i&&s.label<i[2]){s.label=i[2],s.ops.push(o);break}i[2]&&s.ops.pop(),s.trys.pop();continue}o=t.call(e,s)}catch(e){o=[6,e],r=0}finally{n=i=0}if(5&o[0])throw o[1];return{value:o[0]?o[1]:void 0,done:!0}}([o,u])}}}var n=function(){return(n=Object.assign||function(e){for(var t,n=1,r=arguments.length;n<r;n++)for(var i in t=arguments[n])Object.prototype.hasOwnProperty.call(t,i)&&(e[i]=t[i]);return e}).apply(this,arguments)};function r(e,t){void 0===t&&(t=!1);var n=window.crypto.getRandomValues(new Uint32Array(1))[0],r="_".concat(n);return Object.defineProperty(window,r,{value:function(n){return t&&Reflect.deleteProperty(window,r),null==e?void 0:e(n)},writable:!1,configurable:!0}),n}function i(e,t){return void 0===t&&(t={}),function(e,t,n,r){return new(n||(n=Promise))((function(i,o){function s(e){try{a(r.next(e))}catch(e){o(e)}}function u(e){try{a(r.throw(e))}catch(e){o(e)}}function a(e){var t;e.done?i(e.value):(t=e.value,t instanceof n?t:new n((function(e){e(t)}))).then(s,u)}a((r=r.apply(e,t||[])).next())}))}(this,void 0,void 0,(function(){return function(e,t){var n,r,i,o,s={label:0,sent:function(){if(1&i[0])throw i[1];return i[1]},trys:[],ops:[]};return o={next:u(0),throw:u(1),return:u(2)},"function"==typeof Symbol&&(o[Symbol.iterator]=function(){return this}),o;function u(o){return function(u){return function(o){if(n)throw new TypeError("Generator is already executing.");for(;s;)try{if(n=1,r&&(i=2&o[0]?r.return:o[0]?r.throw||((i=r.return)&&i.call(r),0):r.next)&&!(i=i.call(r,o[1])).done)return i;switch(r=0,i&&(o=[2&o[0],i.value]),o[0]){case 0:case 1:i=o;break;case 4:return s.label++,{value:o[1],done:!1};case 5:s.label++,r=o[1],o=[0];continue;case 7:o=s.ops.pop(),s.trys.pop();continue;default:if(!((i=(i=s.trys).length>0&&i[i.length-1])||6!==o[0]&&2!==o[0])){s=0;continue}if(3===o[0]&&(!i||o[1]>i[0]&&o[1]<i[3])){s.label=o[1];break}if(6===o[0]&&s.label<i[1]){s.label=i[1],i=o;break}if(i&&s.label<i[2]){s.label=i[2],s.ops.push(o);break}i[2]&&s.ops.pop(),s.trys.pop();continue}o=t.call(e,s)}catch(e){o=[6,e],r=0}finally{n=i=0}if(5&o[0])throw o[1];return{value:o[0]?o[1]:void 0,done:!0}}([o,u])}}}(this,(function(i){return[2,new Promise((function(i,o){var s=r((function(e){i(e),Reflect.deleteProperty(window,"_".concat(u))}),!0),u=r((function(e){o(e),Reflect.deleteProperty(window,"_".concat(s))}),!0);window.__TAURI_IPC__(n({cmd:e,callback:s,error:u},t))}))]}))}))}Object.freeze({__proto__:null,transformCallback:r,invoke:i,convertFileSrc:function(e,t){void 0===t&&(t="asset");var n=encodeURIComponent(e);return navigator.userAgent.includes("Windows")?"https://".concat(t,".localhost/").concat(n):"".concat(t,"://").concat(n)}});var o=function(){function n(e,t){this.path=e,this.sheetName=t}return n.prototype.appendColumn=function(n){return e(this,void 0,void 0,(function(){return t(this,(function(e){switch(e.label){case 0:return[4,i("plugin:spreadsheet|append_column",{data:n,path:this.path,sheetName:this.sheetName})];case 1:return[2,e.sent()]}}))}))},n.prototype.appendRow=function(n){return e(this,void 0,void 0,(function(){return t(this,(function(e){switch(e.label){case 0:return[4,i("plugin:spreadsheet|append_row",{data:n,path:this.path,sheetName:this.sheetName})];case 1:return[2,e.sent()]}}))}))},n.prototype.close=function(){return e(this,void 0,void 0,(function(){return t(this,(function(e){switch(e.label){case 0:return[4,i("plugin:spreadsheet|close_xlsx",{path:this.path})];case 1:return[2,e.sent()]}}))}))},n.closeAll=function(){return e(this,void 0,void 0,(function(){return t(this,(function(e){switch(e.label){case 0:return[4,i("plugin:spreadsheet|close_all_xlsx")];case 1:return[2,e.sent()]}}))}))},n.prototype.copySheet=function(n,r){return e(this,void 0,void 0,(function(){return t(this,(function(e){switch(e.label){case 0:return[4,i("plugin:spreadsheet|copy_sheet",{path:this.path,sourceSheetName:r||this.sheetName,targetSheetName:n})];case 1:return[2,e.sent()]}}))}))},n.prototype.create=function(){return e(this,void 0,void 0,(function(){return t(this,(function(e){switch(e.label){case 0:return[4,i("plugin:spreadsheet|new_xlsx",{path:this.path})];case 1:return[2,e.sent()]}}))}))},n.prototype.getCollectionByColumn=function(n){return e(this,void 0,void 0,(function(){var e,r;return t(this,(function(t){switch(t.label){case 0:return e=[],n?(e=n,[3,3]):[3,1];case 1:return[4,this.getSheetColumn()];case 2:r=t.sent(),e=[1,r],t.label=3;case 3:return[4,i("plugin:spreadsheet|get_collection_by_column",{path:this.path,sheetName:this.sheetName,range:e})];case 4:return[2,t.sent()]}}))}))},n.prototype.getCollectionByRow=function(n){return e(this,void 0,void 0,(function(){var e,r;return t(this,(function(t){switch(t.label){case 0:return e=[],n?(e=n,[3,3]):[3,1];case 1:return[4,this.getSheetRow()];case 2:r=t.sent(),e=[1,r],t.label=3;case 3:return[4,i("plugin:spreadsheet|get_collection_by_row",{path:this.path,sheetName:this.sheetName,range:e})];case 4:return[2,t.sent()]}}))}))},n.prototype.getSheetColumn=function(){return e(this,void 0,void 0,(function(){return t(this,(function(e){switch(e.label){case 0:return[4,i("plugin:spreadsheet|get_sheet_highest_column",{path:this.path,sheetName:this.sheetName})];case 1:return[2,e.sent()]}}))}))},n.prototype.getSheetRange=function(){return e(this,void 0,void 0,(function(){return t(this,(function(e){switch(e.label){case 0:return[4,i("plugin:spreadsheet|get_sheet_highest_column_and_row",{path:this.path,sheetName:this.sheetName})];case 1:return[2,e.sent()]}}))}))},n.prototype.getSheetRow=function(){return e(this,void 0,void 0,(function(){return t(this,(function(e){switch(e.label){case 0:return[4,i("plugin:spreadsheet|get_sheet_highest_row",{path:this.path,sheetName:this.sheetName})];case 1:return[2,e.sent()]}}))}))},n.prototype.getValue=function(n){return e(this,void 0,void 0,(function(){return t(this,(function(e){switch(e.label){case 0:return[4,i("plugin:spreadsheet|get_value_by_column_and_row",{path:this.path,sheetName:this.sheetName,local:n})];case 1:return[2,e.sent()]}}))}))},n.prototype.insertColumn=function(n,r,o){return void 0===o&&(o=!0),e(this,void 0,void 0,(function(){return t(this,(function(e){switch(e.label){case 0:return[4,i("plugin:spreadsheet|insert_column",{data:r,isAdd:o,columnIndex:n,path:this.path,sheetName:this.sheetName})];case 1:return[2,e.sent()]}}))}))},n.prototype.insertNewColumn=function(n,r){return e(this,void 0,void 0,(function(){return t(this,(function(e){switch(e.label){case 0:return"number"!=typeof n?[3,2]:[4,i("plugin:spreadsheet|insert_new_column_by_index",{columnIndex:n,numColumns:r,path:this.path,sheetName:this.sheetName})];case 1:case 3:return[2,e.sent()];case 2:return"string"!=typeof n?[3,4]:[4,i("plugin:spreadsheet|insert_new_column",{column:n,numColumns:r,path:this.path,sheetName:this.sheetName})];case 4:return[2,Promise.reject("columnIndex 参数类型不对!")]}}))}))},n.prototype.insertRow=function(n,r,o){return void 0===o&&(o=!0),e(this,void 0,void 0,(function(){return t(this,(function(e){switch(e.label){case 0:return[4,i("plugin:spreadsheet|insert_row",{data:r,isAdd:o,rowIndex:n,path:this.path,sheetName:this.sheetName})];case 1:return[2,e.sent()]}}))}))},n.list=function(){return e(this,void 0,void 0,(function(){return t(this,(function(e){switch(e.label){case 0:return[4,i("plugin:spreadsheet|list_xlsx")];case 1:return[2,e.sent()]}}))}))},n.prototype.newSheet=function(n){return e(this,void 0,void 0,(function(){return t(this,(function(e){switch(e.label){case 0:return[4,i("plugin:spreadsheet|new_sheet",{path:this.path,sheetName:n})];case 1:return[2,e.sent()]}}))}))},n.prototype.read=function(){return e(this,void 0,void 0,(function(){return t(this,(function(e){switch(e.label){case 0:return[4,i("plugin:spreadsheet|read_xlsx",{path:this.path})];case 1:return[2,e.sent()]}}))}))},n.prototype.removeColumn=function(n,r){return e(this,void 0,void 0,(function(){return t(this,(function(e){switch(e.label){case 0:return"number"!=typeof n?[3,2]:[4,i("plugin:spreadsheet|insert_new_column_by_index",{columnIndex:n,numColumns:r,path:this.path,sheetName:this.sheetName})];case 1:case 3:return[2,e.sent()];case 2:return"string"!=typeof n?[3,4]:[4,i("plugin:spreadsheet|insert_new_column",{column:n,numColumns:r,path:this.path,sheetName:this.sheetName})];case 4:return[2,Promise.reject("start 参数类型不对!")]}}))}))},n.prototype.removeRow=function(n,r){return e(this,void 0,void 0,(function(){return t(this,(function(e){switch(e.label){case 0:return[4,i("plugin:spreadsheet|remove_row",{rowIndex:n,numRows:r,path:this.path,sheetName:this.sheetName})];case 1:return[2,e.sent()]}}))}))},n.prototype.setValue=function(n,r){return e(this,void 0,void 0,(function(){return t(this,(function(e){switch(e.label){case 0:return[4,i("plugin:spreadsheet|set_value_by_column_and_row",{path:this.path,sheetName:this.sheetName,local:n,value:r})];case 1:return[2,e.sent()]}}))}))},n.prototype.write=function(){return e(this,void 0,void 0,(function(){return t(this,(function(e){switch(e.label){case 0:return[4,i("plugin:spreadsheet|write_xlsx",{path:this.path})];case 1:return[2,e.sent()]}}))}))},n}();export{o as Spreadsheet};
+import { listen } from '@tauri-apps/api/event';
+
+import { invoke } from '@tauri-apps/api/tauri';
+
+class Spreadsheet {
+  constructor(path, sheetName) {
+    this.path = path;
+    this.sheetName = sheetName;
+  }
+
+  /**
+   * @description: 在 `anchorRange` 区域添加图表
+   * @param {string} anchorRange 图表覆盖的单元格区域，如 `E2:L20`
+   * @param {ChartSpec} spec 图表描述
+   * @return {Promise<number>} 图表索引
+   */
+  async addChart(anchorRange, spec) {
+    return await invoke('plugin:spreadsheet|add_chart', {
+      anchorRange,
+      spec,
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 新建作用于整个文件的名称
+   * @param {string} name 名称，如 `InvoiceTotal`
+   * @param {string} refersTo 引用位置，如 `Sheet1!$F$20`
+   * @param {string} scope 作用的 sheet 名称，暂不支持新建 sheet 作用范围的名称，传入时返回错误
+   * @return {Promise<DefinedNameInfo>}
+   */
+  async addDefinedName(
+    name,
+    refersTo,
+    scope,
+  ) {
+    return await invoke('plugin:spreadsheet|add_defined_name', {
+      name,
+      refersTo,
+      scope,
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 附加数据到表格最后一列。
+   * @param {string[][]} data 二维字符串数组
+   * @return {Promise<void>}
+   */
+  async appendColumn(data) {
+    return await invoke('plugin:spreadsheet|append_column', {
+      data,
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 附加数据到表格最后一行。
+   * @param {string[][]} data 二维字符串数组
+   * @return {Promise<void>}
+   */
+  async appendRow(data) {
+    return await invoke('plugin:spreadsheet|append_row', {
+      data,
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 返回 `range` 区域中满足筛选条件的数据行，不修改 sheet，区域首行视为表头
+   * @param {string} range 区域，如 `A1:F100`
+   * @param {FilterColumn[]} columns 各列筛选条件
+   * @return {Promise<FilterResult>}
+   */
+  async applyFilter(
+    range,
+    columns,
+  ) {
+    return await invoke('plugin:spreadsheet|apply_filter', {
+      columns,
+      range,
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 在同一个文件锁内依次执行多个操作，任一操作失败时撤销全部操作，
+   * 并返回 `operation <index> failed, all operations were rolled back: <error>` 错误，`index` 从 0 开始
+   * @param {Operation[]} operations 操作列表，未指定 `sheetName` 时使用当前 sheet
+   * @return {Promise<unknown[]>} 各操作的返回值
+   */
+  async batch(operations) {
+    return await invoke('plugin:spreadsheet|batch', {
+      operations: operations.map((operation) => ({
+        sheetName: this.sheetName,
+        ...operation,
+      })),
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 检查文件读取或保存后是否被其他程序修改或删除过
+   * @return {Promise<boolean>}
+   */
+  async check() {
+    return await invoke('plugin:spreadsheet|check_xlsx', {
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 清除当前 sheet 的筛选
+   * @return {Promise<void>}
+   */
+  async clearAutoFilter() {
+    return await invoke('plugin:spreadsheet|clear_auto_filter', {
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 关闭当前 xlsx 文件
+   * @return {Promise<void>}
+   */
+  async close() {
+    return await invoke('plugin:spreadsheet|close_xlsx', {
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 关闭所有 xlsx 文件
+   * @return {Promise<void>}
+   */
+  static async closeAll() {
+    return await invoke('plugin:spreadsheet|close_all_xlsx');
+  }
+
+  /**
+   * @description: 复制 sheet，不添加 sourceSheetName 时，默认使用当前 sheet
+   * @param {string} targetSheetName 目标 sheet 名称
+   * @param {string} sourceSheetName 源 sheet 名称
+   * @return {Promise<void>}
+   */
+  async copySheet(
+    targetSheetName,
+    sourceSheetName,
+  ) {
+    return await invoke('plugin:spreadsheet|copy_sheet', {
+      path: this.path,
+      sourceSheetName: sourceSheetName || this.sheetName,
+      targetSheetName,
+    });
+  }
+
+  /**
+   * @description: 新建 xlsx 文件
+   * @return {Promise<void>}
+   */
+  async create() {
+    return await invoke('plugin:spreadsheet|new_xlsx', {
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 比较当前文件和另一个打开的文件
+   * @param {string} otherPath 修改后的文件路径
+   * @param {DiffOptions} options 对齐方式和差异文件路径
+   * @return {Promise<WorkbookDiff>}
+   */
+  async diff(otherPath, options) {
+    return await invoke('plugin:spreadsheet|diff_workbooks', {
+      options,
+      pathA: this.path,
+      pathB: otherPath,
+    });
+  }
+
+  /**
+   * @description: 删除当前文件自动保存的内容
+   * @return {Promise<void>}
+   */
+  async discardRecoverable() {
+    return await invoke('plugin:spreadsheet|discard_recoverable', {
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 在文件中查找内容
+   * @param {string} query 查找内容
+   * @param {FindOptions} options 查找选项
+   * @return {Promise<FindMatch[]>}
+   */
+  async find(query, options) {
+    return await invoke('plugin:spreadsheet|find', {
+      options,
+      query,
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 获取指定列数的数据集
+   * @param {number[] | string} range [ min, max ] 范围，或区域字符串、名称
+   * @return {*}
+   */
+  async getCollectionByColumn(range) {
+    let realRange = [];
+    if (range) {
+      realRange = range;
+    } else {
+      const column = await this.getSheetColumn();
+      realRange = [1, column];
+    }
+    return await invoke('plugin:spreadsheet|get_collection_by_column', {
+      path: this.path,
+      sheetName: this.sheetName,
+      range: realRange,
+    });
+  }
+
+  /**
+   * @description: 获取指定行数的数据集
+   * @param {number[] | string} range range [ min, max ] 范围，或区域字符串、名称
+   * @return {*}
+   */
+  async getCollectionByRow(range) {
+    let realRange = [];
+    if (range) {
+      realRange = range;
+    } else {
+      const row = await this.getSheetRow();
+      realRange = [1, row];
+    }
+    return await invoke('plugin:spreadsheet|get_collection_by_row', {
+      path: this.path,
+      sheetName: this.sheetName,
+      range: realRange,
+    });
+  }
+
+  /**
+   * @description: 获取 sheet 列数
+   * @return {Promise<number>}
+   */
+  async getSheetColumn() {
+    return await invoke('plugin:spreadsheet|get_sheet_highest_column', {
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 获取 sheet 行列范围
+   * @return {Promise<number[]>}
+   */
+  async getSheetRange() {
+    return await invoke('plugin:spreadsheet|get_sheet_highest_column_and_row', {
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 获取 sheet 行数
+   * @return {Promise<number>}
+   */
+  async getSheetRow() {
+    return await invoke('plugin:spreadsheet|get_sheet_highest_row', {
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 获取当前 sheet 指定位置的值
+   * @param {number} local 位置，[column, row]
+   * @return {Promise<string>}
+   */
+  async getValue(local) {
+    return await invoke('plugin:spreadsheet|get_value_by_column_and_row', {
+      path: this.path,
+      sheetName: this.sheetName,
+      local,
+    });
+  }
+
+  /**
+   * @description: 获取撤销和重做记录
+   * @return {Promise<HistoryInfo>}
+   */
+  async history() {
+    return await invoke('plugin:spreadsheet|history', {
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 在指定位置 `columnIndex` 插入数据。
+   * @param {number} columnIndex 指定开始列数
+   * @param {string[][]} data 插入的数据，二维字符串数组
+   * @param {boolean} isAdd 是否新增空白插入，false 则覆盖原来数据
+   * @return {Promise<void>}
+   */
+  async insertColumn(
+    columnIndex,
+    data,
+    isAdd = true,
+  ) {
+    return await invoke('plugin:spreadsheet|insert_column', {
+      data,
+      isAdd,
+      columnIndex,
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 在指定位置 `columnIndex` 插入 `num_columns` 空白列。
+   * @param {number} columnIndex 指定开始列数
+   * @param {number} numColumns 插入列数
+   * @return {Promise<void>}
+   */
+  async insertNewColumn(
+    columnIndex,
+    numColumns,
+  ) {
+    if (typeof columnIndex === 'number') {
+      return await invoke('plugin:spreadsheet|insert_new_column_by_index', {
+        columnIndex,
+        numColumns,
+        path: this.path,
+        sheetName: this.sheetName,
+      });
+    } else if (typeof columnIndex === 'string') {
+      return await invoke('plugin:spreadsheet|insert_new_column', {
+        column: columnIndex,
+        numColumns,
+        path: this.path,
+        sheetName: this.sheetName,
+      });
+    } else {
+      return Promise.reject('columnIndex 参数类型不对!');
+    }
+  }
+
+  /**
+   * @description: 在指定位置 `rowIndex` 插入数据。
+   * @param {number} rowIndex 指定行数
+   * @param {string[][]} data 插入的数据，二维字符串数组
+   * @param {boolean} isAdd 是否新增空白插入，false 则覆盖原来数据
+   * @return {Promise<void>}
+   */
+  async insertRow(
+    rowIndex,
+    data,
+    isAdd = true,
+  ) {
+    return await invoke('plugin:spreadsheet|insert_row', {
+      data,
+      isAdd,
+      rowIndex,
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 列出当前 sheet 的图表
+   * @return {Promise<ChartInfo[]>}
+   */
+  async listCharts() {
+    return await invoke('plugin:spreadsheet|list_charts', {
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 列出文件中的所有名称，sheet 作用范围的名称以名称引用的 sheet 作为 `scope`，
+   * 与 Excel 中定义名称的 sheet 不一定相同，保存时写为第一个 sheet 作用范围的隐藏名称
+   * @return {Promise<DefinedNameInfo[]>}
+   */
+  async listDefinedNames() {
+    return await invoke('plugin:spreadsheet|list_defined_names', {
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 列出上次未保存、可以恢复的文件
+   * @return {Promise<RecoverableWorkbook[]>}
+   */
+  static async listRecoverable() {
+    return await invoke('plugin:spreadsheet|list_recoverable');
+  }
+
+  /**
+   * @description: 列出所有打开的 xlsx 文件
+   * @return {Promise<string[]>}
+   */
+  static async list() {
+    return await invoke('plugin:spreadsheet|list_xlsx');
+  }
+
+  /**
+   * @description: 把另一份副本相对共同版本的修改合并到当前文件
+   * @param {string} basePath 共同版本的文件路径
+   * @param {string} theirsPath 另一份副本的文件路径
+   * @return {Promise<MergeResult>} 冲突的单元格保留当前内容
+   */
+  async merge(basePath, theirsPath) {
+    return await invoke('plugin:spreadsheet|merge_workbooks', {
+      base: basePath,
+      ours: this.path,
+      theirs: theirsPath,
+    });
+  }
+
+  /**
+   * @description: 新建 sheet
+   * @param {string} sheetName sheet 名称
+   * @return {Promise<void>}
+   */
+  async newSheet(sheetName) {
+    return await invoke('plugin:spreadsheet|new_sheet', {
+      path: this.path,
+      sheetName: sheetName,
+    });
+  }
+
+  /**
+   * @description: 监听文件内容修改事件，包括其他窗口的修改
+   * @param {function} handler 事件处理函数
+   * @return {Promise<UnlistenFn>} 取消监听的函数
+   */
+  static async onChanged(
+    handler
+  ) {
+    return await listen('spreadsheet://changed', (event) =>
+      handler(event.payload)
+    );
+  }
+
+  /**
+   * @description: 监听文件关闭事件
+   * @param {function} handler 事件处理函数
+   * @return {Promise<UnlistenFn>} 取消监听的函数
+   */
+  static async onClosed(
+    handler
+  ) {
+    return await listen('spreadsheet://closed', (event) =>
+      handler(event.payload)
+    );
+  }
+
+  /**
+   * @description: 监听文件被其他程序修改或删除事件
+   * @param {function} handler 事件处理函数
+   * @return {Promise<UnlistenFn>} 取消监听的函数
+   */
+  static async onModified(
+    handler
+  ) {
+    return await listen('spreadsheet://modified', (event) =>
+      handler(event.payload)
+    );
+  }
+
+  /**
+   * @description: 监听文件打开或新建事件
+   * @param {function} handler 事件处理函数
+   * @return {Promise<UnlistenFn>} 取消监听的函数
+   */
+  static async onOpened(
+    handler
+  ) {
+    return await listen('spreadsheet://opened', (event) =>
+      handler(event.payload)
+    );
+  }
+
+  /**
+   * @description: 监听文件保存事件
+   * @param {function} handler 事件处理函数
+   * @return {Promise<UnlistenFn>} 取消监听的函数
+   */
+  static async onSaved(
+    handler
+  ) {
+    return await listen('spreadsheet://saved', (event) =>
+      handler(event.payload)
+    );
+  }
+
+  /**
+   * @description: 使用 SQL 查询所有打开的 xlsx 文件，表名为 "文件别名/sheet 名称"，sheet 名称唯一时可以直接使用。
+   * 文件别名为文件名，不同目录下有同名文件时加上上级目录，如 "2023/sales.xlsx/Sheet1"
+   * @param {string} sql 只读 SQL，ATTACH、PRAGMA 和写入语句会被拒绝，如: SELECT Region, SUM(Amount) FROM "sales.xlsx/Sheet1" GROUP BY Region
+   * @return {Promise<QueryResult>}
+   */
+  static async query(sql) {
+    return await invoke('plugin:spreadsheet|query', {
+      sql,
+    });
+  }
+
+  /**
+   * @description: 读取 xlsx 文件
+   * @param {string} password 加密文件的打开密码
+   * @return {Promise<void>}
+   */
+  async read(password) {
+    return await invoke('plugin:spreadsheet|read_xlsx', {
+      password,
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 打开当前文件自动保存的内容，调用 `write` 后才会写回原文件
+   * @return {Promise<void>}
+   */
+  async recover() {
+    return await invoke('plugin:spreadsheet|recover', {
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 重做最近一次撤销的修改
+   * @return {Promise<HistoryStep | null>} 没有可重做的修改时为 `null`
+   */
+  async redo() {
+    return await invoke('plugin:spreadsheet|redo', {
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 重新读取 xlsx 文件，丢弃未保存的修改
+   * @return {Promise<void>}
+   */
+  async reload() {
+    return await invoke('plugin:spreadsheet|reload_xlsx', {
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 删除区域中整行都为空的行，下方的行上移，区域外还有内容的行不会删除
+   * @param {string} range 数据区域或名称，如: A1:F500
+   * @return {Promise<RemovedRows>}
+   */
+  async removeBlankRows(range) {
+    return await invoke('plugin:spreadsheet|remove_blank_rows', {
+      path: this.path,
+      range,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 删除指定索引的图表
+   * @param {number} index 图表索引
+   * @return {Promise<void>}
+   */
+  async removeChart(index) {
+    return await invoke('plugin:spreadsheet|remove_chart', {
+      index,
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 删除 `start` 开始的 `length` 的列数。
+   * @param {number} start 开始列数
+   * @param {number} length 删除列数
+   * @return {Promise<void>}
+   */
+  async removeColumn(start, length) {
+    if (typeof start === 'number') {
+      return await invoke('plugin:spreadsheet|insert_new_column_by_index', {
+        columnIndex: start,
+        numColumns: length,
+        path: this.path,
+        sheetName: this.sheetName,
+      });
+    } else if (typeof start === 'string') {
+      return await invoke('plugin:spreadsheet|insert_new_column', {
+        column: start,
+        numColumns: length,
+        path: this.path,
+        sheetName: this.sheetName,
+      });
+    } else {
+      return Promise.reject('start 参数类型不对!');
+    }
+  }
+
+  /**
+   * @description: 删除名称
+   * @param {string} name 名称
+   * @param {string} scope 作用的 sheet 名称
+   * @return {Promise<void>}
+   */
+  async removeDefinedName(name, scope) {
+    return await invoke('plugin:spreadsheet|remove_defined_name', {
+      name,
+      scope,
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 删除区域中的重复行，下方的行上移。删除的是整行，区域外的单元格也会一起删除
+   * @param {string} range 数据区域或名称，如: A2:F500
+   * @param {number[]} keyColumns 判断重复的列索引，默认为区域的所有列
+   * @param {string} keep 保留第一次或最后一次出现的行，默认为 first
+   * @return {Promise<RemovedRows>}
+   */
+  async removeDuplicates(
+    range,
+    keyColumns,
+    keep
+  ) {
+    return await invoke('plugin:spreadsheet|remove_duplicates', {
+      keep,
+      keyColumns,
+      path: this.path,
+      range,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 删除当前 sheet 指定行
+   * @param {number} rowIndex 开始行数
+   * @param {number} numRows 要删除的行数
+   * @return {Promise<void>}
+   */
+  async removeRow(rowIndex, numRows) {
+    return await invoke('plugin:spreadsheet|remove_row', {
+      rowIndex,
+      numRows,
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 使用 `data` 填充模板并保存为新文件，模板文件不会被修改
+   * @param {string} templatePath 模板文件路径，占位符如 `{{customer.name | upper}}`，`{{#each items}}` 和 `{{/each}}` 之间的行按每一项重复
+   * @param {unknown} data 填充的数据
+   * @param {string} outPath 保存的文件路径
+   * @return {Promise<TemplateResult>}
+   */
+  static async renderTemplate(
+    templatePath,
+    data,
+    outPath,
+  ) {
+    return await invoke('plugin:spreadsheet|render_template', {
+      data,
+      outPath,
+      templatePath,
+    });
+  }
+
+  /**
+   * @description: 为 `range` 区域设置筛选按钮，区域首行为表头
+   * @param {string} range 筛选区域，如 `A1:F100`
+   * @return {Promise<void>}
+   */
+  async setAutoFilter(range) {
+    return await invoke('plugin:spreadsheet|set_auto_filter', {
+      range,
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 设置筛选区域中指定列的筛选条件，不传 `criteria` 时清除该列条件。
+   * 筛选条件不会写入文件，保存后重新打开时只保留筛选按钮和已隐藏的行
+   * @param {number} column 列索引，从 1 开始
+   * @param {FilterCriteria} criteria 筛选条件
+   * @return {Promise<number>} 可见的数据行数
+   */
+  async setFilterColumn(
+    column,
+    criteria,
+  ) {
+    return await invoke('plugin:spreadsheet|set_filter_column', {
+      column,
+      criteria,
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 替换文件中所有匹配的内容，替换值时跳过有公式的单元格
+   * @param {string} query 查找内容
+   * @param {string} replacement 替换内容，正则模式下可以使用 `$1` 引用分组
+   * @param {FindOptions} options 查找选项
+   * @return {Promise<number>} 修改的单元格数量
+   */
+  async replaceAll(
+    query,
+    replacement,
+    options,
+  ) {
+    return await invoke('plugin:spreadsheet|replace_all', {
+      options,
+      query,
+      replacement,
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 设置保留的撤销步数，为 0 时不记录撤销信息，修改失败时也不恢复 (`batch` 除外)
+   * @param {number} limit 撤销步数，默认 50
+   * @return {Promise<void>}
+   */
+  async setHistoryLimit(limit) {
+    return await invoke('plugin:spreadsheet|set_history_limit', {
+      limit,
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 设置当前 sheet 指定位置的值
+   * @param {number} local 位置，[column, row]
+   * @param {string} value 值
+   * @return {Promise<void>}
+   */
+  async setValue(local, value) {
+    return await invoke('plugin:spreadsheet|set_value_by_column_and_row', {
+      path: this.path,
+      sheetName: this.sheetName,
+      local,
+      value,
+    });
+  }
+
+  /**
+   * @description: 按一个或多个关键字对区域内的行排序，样式和公式随行移动
+   * @param {string} range 排序区域或名称，如 `A1:F100`
+   * @param {SortKey[]} keys 排序关键字
+   * @param {boolean} hasHeader 区域首行是否为表头
+   * @return {Promise<number[]>} 排序后各行对应的原行索引
+   */
+  async sortRange(
+    range,
+    keys,
+    hasHeader = true,
+  ) {
+    return await invoke('plugin:spreadsheet|sort_range', {
+      hasHeader,
+      keys,
+      range,
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 按分组汇总区域数据，区域首行为表头
+   * @param {string} range 数据区域或名称，如 `A1:F200`
+   * @param {string[]} groupBy 分组的表头名称
+   * @param {Aggregate[]} aggregates 聚合列
+   * @param {SummaryOutput} output 交叉表列分组和写入的新 sheet 名称
+   * @return {Promise<SummaryResult>}
+   */
+  async summarize(
+    range,
+    groupBy,
+    aggregates,
+    output,
+  ) {
+    return await invoke('plugin:spreadsheet|summarize', {
+      aggregates,
+      groupBy,
+      output,
+      range,
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 撤销最近一次修改
+   * @return {Promise<HistoryStep | null>} 没有可撤销的修改时为 `null`
+   */
+  async undo() {
+    return await invoke('plugin:spreadsheet|undo', {
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 修改名称的引用位置，传入 `newName` 时同时重命名
+   * @param {string} name 名称
+   * @param {string} refersTo 新的引用位置
+   * @param {string} scope 作用的 sheet 名称，暂不支持修改 sheet 作用范围的名称
+   * @param {string} newName 新名称
+   * @return {Promise<DefinedNameInfo>}
+   */
+  async updateDefinedName(
+    name,
+    refersTo,
+    scope,
+    newName,
+  ) {
+    return await invoke('plugin:spreadsheet|update_defined_name', {
+      name,
+      newName,
+      refersTo,
+      scope,
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 保存 xlsx 文件，文件被其他程序修改过时失败
+   * @param {boolean} overwrite 覆盖其他程序的修改
+   * @param {string} password 保存为加密文件的打开密码，为空字符串时取消加密
+   * @return {Promise<void>}
+   */
+  async write(overwrite, password) {
+    return await invoke('plugin:spreadsheet|write_xlsx', {
+      overwrite,
+      password,
+      path: this.path,
+    });
+  }
+}
+
+export { Spreadsheet };
+
@@ -1,5 +1,294 @@
+import { listen } from '@tauri-apps/api/event';
+import type { UnlistenFn } from '@tauri-apps/api/event';
 import { invoke } from '@tauri-apps/api/tauri';
 
+type ChangeKind =
+  | 'values'
+  | 'insertRows'
+  | 'removeRows'
+  | 'insertColumns'
+  | 'removeColumns'
+  | 'sort'
+  | 'addSheet'
+  | 'chart'
+  | 'definedName'
+  | 'filter'
+  | 'undo'
+  | 'redo'
+  | 'batch'
+  | 'reload'
+  | 'merge';
+
+interface ChangeEvent {
+  /** 文件路径 */
+  workbook: string;
+  /** sheet 名称，修改涉及多个 sheet 或整个文件时为空 */
+  sheet: string | null;
+  /** 修改的区域，如 `B3`、`5:7`、`B:D` */
+  range: string | null;
+  kind: ChangeKind;
+}
+
+interface WorkbookEvent {
+  workbook: string;
+}
+
+type ChartKind = 'bar' | 'column' | 'line' | 'pie' | 'scatter' | 'area';
+
+type LegendPosition = 'top' | 'bottom' | 'left' | 'right' | 'topRight';
+
+interface ChartSeries {
+  /** 数据区域，如 `B2:B10` 或 `Sheet2!B2:B10` */
+  values: string;
+  title?: string;
+}
+
+interface ChartSpec {
+  kind: ChartKind;
+  series: ChartSeries[];
+  /** 分类区域，如 `A2:A10` 或 `Sheet2!A2:A10`，散点图中作为 x 轴数据 */
+  categories?: string;
+  title?: string;
+  xAxisTitle?: string;
+  yAxisTitle?: string;
+  legend?: LegendPosition;
+}
+
+interface ChartInfo {
+  index: number;
+  anchorRange: string;
+}
+
+type FilterOperator =
+  | 'equal'
+  | 'notEqual'
+  | 'greaterThan'
+  | 'greaterThanOrEqual'
+  | 'lessThan'
+  | 'lessThanOrEqual';
+
+type FilterCriteria =
+  | { type: 'values'; values: string[]; blank?: boolean }
+  | {
+      type: 'custom';
+      conditions: { operator: FilterOperator; value: string }[];
+      and?: boolean;
+    }
+  | { type: 'top10'; top?: boolean; percent?: boolean; value: number };
+
+interface FilterColumn {
+  /** 列索引，从 1 开始 */
+  column: number;
+  criteria: FilterCriteria;
+}
+
+interface FilterResult {
+  rows: number[];
+  data: string[][];
+}
+
+interface DefinedNameInfo {
+  name: string;
+  /**
+   * 作用的 sheet 名称，为空表示整个文件。
+   * 读取文件时不保留 `localSheetId`，sheet 作用范围的名称以名称引用的 sheet 作为作用范围
+   */
+  scope?: string;
+  refersTo: string;
+}
+
+interface DiffOptions {
+  /** 按该列的值对齐行，如 `A`，默认按内容对齐 */
+  keyColumn?: string;
+  /** 表头行数，表头按位置对齐 */
+  headerRows?: number;
+  /** 保存差异文件的路径，标出修改的单元格和插入的行 */
+  outputPath?: string;
+}
+
+interface CellChange {
+  /** 原文件中的坐标 */
+  oldCell: string;
+  cell: string;
+  oldValue: string;
+  newValue: string;
+  oldFormula: string;
+  newFormula: string;
+}
+
+interface SheetDiff {
+  oldName: string;
+  name: string;
+  insertedRows: number[];
+  deletedRows: number[];
+  changedCells: CellChange[];
+}
+
+interface WorkbookDiff {
+  addedSheets: string[];
+  removedSheets: string[];
+  renamedSheets: { from: string; to: string }[];
+  sheets: SheetDiff[];
+}
+
+interface CellContent {
+  value: string;
+  formula: string;
+}
+
+interface MergeConflict {
+  sheet: string;
+  cell: string;
+  /** 共同版本中的内容，为空时该单元格原来是空的 */
+  base?: CellContent;
+  ours?: CellContent;
+  theirs?: CellContent;
+}
+
+interface MergeResult {
+  /** 从 theirs 合并的单元格数量 */
+  applied: number;
+  addedSheets: string[];
+  removedSheets: string[];
+  /** 一方删除、另一方修改过的 sheet */
+  sheetConflicts: string[];
+  conflicts: MergeConflict[];
+}
+
+interface FindOptions {
+  /** 区分大小写 */
+  matchCase?: boolean;
+  /** 单元格内容需要完全一致 */
+  wholeCell?: boolean;
+  /** 查找内容为正则表达式 */
+  regex?: boolean;
+  /** 查找值或公式，默认 `values` */
+  lookIn?: 'values' | 'formulas';
+  /** 查找的 sheet 名称，不传时查找所有 sheet */
+  sheets?: string[];
+  /** 查找的区域或名称 */
+  range?: string;
+}
+
+interface FindMatch {
+  sheetName: string;
+  cell: string;
+  column: number;
+  row: number;
+  value: string;
+  formula: string;
+}
+
+interface RemovedRows {
+  count: number;
+  /** 删除前的行索引 */
+  rows: number[];
+}
+
+interface HistoryInfo {
+  /** 可撤销的操作，最后一项最先撤销 */
+  undo: string[];
+  /** 可重做的操作，最后一项最先重做 */
+  redo: string[];
+  limit: number;
+}
+
+interface HistoryStep {
+  label: string;
+  /** 恢复的 sheet 名称，恢复整个文件时为空 */
+  sheet: string | null;
+}
+
+interface RecoverableWorkbook {
+  /** 原文件路径 */
+  path: string;
+  /** 自动保存的时间，Unix 时间戳 (毫秒) */
+  savedAt: number;
+}
+
+interface SortKey {
+  /** 列索引，从 1 开始 */
+  column: number;
+  order?: 'asc' | 'desc';
+  /** 比较方式，默认 `auto` */
+  dataType?: 'auto' | 'number' | 'text' | 'date';
+  caseSensitive?: boolean;
+  /** 自定义顺序，不在列表中的值排在后面 */
+  customOrder?: string[];
+}
+
+interface Aggregate {
+  /** 表头名称 */
+  column: string;
+  function: 'sum' | 'count' | 'avg' | 'min' | 'max' | 'distinctCount';
+  /** 结果列名称 */
+  alias?: string;
+}
+
+interface SummaryOutput {
+  /** 写入的新 sheet 名称 */
+  sheetName?: string;
+  /** 交叉表的列分组表头名称 */
+  columnGroup?: string;
+}
+
+interface QueryResult {
+  columns: string[];
+  rows: (string | number | null)[][];
+}
+
+interface SummaryResult {
+  headers: string[];
+  rows: string[][];
+}
+
+interface TemplateResult {
+  /** 替换的占位符数量 */
+  placeholders: number;
+  /** 重复区域生成的行数 */
+  rows: number;
+  /** 数据中不存在的字段 */
+  missing: string[];
+}
+
+/** 批量操作，`sheetName` 默认为当前 sheet */
+type Operation =
+  | { op: 'setValue'; sheetName?: string; local: number[]; value: string }
+  | { op: 'appendRow'; sheetName?: string; data: string[][] }
+  | { op: 'appendColumn'; sheetName?: string; data: string[][] }
+  | { op: 'insertRow'; sheetName?: string; rowIndex: number; data: string[][]; isAdd?: boolean }
+  | {
+      op: 'insertColumn';
+      sheetName?: string;
+      columnIndex: number;
+      data: string[][];
+      isAdd?: boolean;
+    }
+  | { op: 'insertNewRow'; sheetName?: string; rowIndex: number; numRows: number }
+  | { op: 'insertNewColumn'; sheetName?: string; columnIndex: number; numColumns: number }
+  | { op: 'removeRow'; sheetName?: string; rowIndex: number; numRows: number }
+  | { op: 'removeColumn'; sheetName?: string; columnIndex: number; numColumns: number }
+  | { op: 'newSheet'; sheetName: string }
+  | { op: 'copySheet'; sourceSheetName: string; targetSheetName: string }
+  | { op: 'sortRange'; sheetName?: string; range: string; keys: SortKey[]; hasHeader?: boolean }
+  | {
+      op: 'removeDuplicates';
+      sheetName?: string;
+      range: string;
+      keyColumns?: number[];
+      keep?: 'first' | 'last';
+    }
+  | { op: 'removeBlankRows'; sheetName?: string; range: string }
+  | { op: 'replaceAll'; query: string; replacement: string; options?: FindOptions }
+  | { op: 'setAutoFilter'; sheetName?: string; range: string }
+  | { op: 'clearAutoFilter'; sheetName?: string }
+  | { op: 'setFilterColumn'; sheetName?: string; column: number; criteria: FilterCriteria | null }
+  | { op: 'addChart'; sheetName?: string; anchorRange: string; spec: ChartSpec }
+  | { op: 'removeChart'; sheetName?: string; index: number }
+  | { op: 'addDefinedName'; name: string; refersTo: string; scope?: string }
+  | { op: 'updateDefinedName'; name: string; refersTo: string; scope?: string; newName?: string }
+  | { op: 'removeDefinedName'; name: string; scope?: string };
+
 class Spreadsheet {
   path: string;
   sheetName: string;
@@ -8,6 +297,41 @@ class Spreadsheet {
     this.sheetName = sheetName;
   }
 
+  /**
+   * @description: 在 `anchorRange` 区域添加图表
+   * @param {string} anchorRange 图表覆盖的单元格区域，如 `E2:L20`
+   * @param {ChartSpec} spec 图表描述
+   * @return {Promise<number>} 图表索引
+   */
+  async addChart(anchorRange: string, spec: ChartSpec): Promise<number> {
+    return await invoke('plugin:spreadsheet|add_chart', {
+      anchorRange,
+      spec,
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 新建作用于整个文件的名称
+   * @param {string} name 名称，如 `InvoiceTotal`
+   * @param {string} refersTo 引用位置，如 `Sheet1!$F$20`
+   * @param {string} scope 作用的 sheet 名称，暂不支持新建 sheet 作用范围的名称，传入时返回错误
+   * @return {Promise<DefinedNameInfo>}
+   */
+  async addDefinedName(
+    name: string,
+    refersTo: string,
+    scope?: string,
+  ): Promise<DefinedNameInfo> {
+    return await invoke('plugin:spreadsheet|add_defined_name', {
+      name,
+      refersTo,
+      scope,
+      path: this.path,
+    });
+  }
+
   /**
    * @description: 附加数据到表格最后一列。
    * @param {string[][]} data 二维字符串数组
@@ -34,6 +358,61 @@ class Spreadsheet {
     });
   }
 
+  /**
+   * @description: 返回 `range` 区域中满足筛选条件的数据行，不修改 sheet，区域首行视为表头
+   * @param {string} range 区域，如 `A1:F100`
+   * @param {FilterColumn[]} columns 各列筛选条件
+   * @return {Promise<FilterResult>}
+   */
+  async applyFilter(
+    range: string,
+    columns: FilterColumn[],
+  ): Promise<FilterResult> {
+    return await invoke('plugin:spreadsheet|apply_filter', {
+      columns,
+      range,
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 在同一个文件锁内依次执行多个操作，任一操作失败时撤销全部操作，
+   * 并返回 `operation <index> failed, all operations were rolled back: <error>` 错误，`index` 从 0 开始
+   * @param {Operation[]} operations 操作列表，未指定 `sheetName` 时使用当前 sheet
+   * @return {Promise<unknown[]>} 各操作的返回值
+   */
+  async batch(operations: Operation[]): Promise<unknown[]> {
+    return await invoke('plugin:spreadsheet|batch', {
+      operations: operations.map((operation) => ({
+        sheetName: this.sheetName,
+        ...operation,
+      })),
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 检查文件读取或保存后是否被其他程序修改或删除过
+   * @return {Promise<boolean>}
+   */
+  async check(): Promise<boolean> {
+    return await invoke('plugin:spreadsheet|check_xlsx', {
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 清除当前 sheet 的筛选
+   * @return {Promise<void>}
+   */
+  async clearAutoFilter(): Promise<void> {
+    return await invoke('plugin:spreadsheet|clear_auto_filter', {
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
   /**
    * @description: 关闭当前 xlsx 文件
    * @return {Promise<void>}
@@ -79,13 +458,51 @@ class Spreadsheet {
     });
   }
 
+  /**
+   * @description: 比较当前文件和另一个打开的文件
+   * @param {string} otherPath 修改后的文件路径
+   * @param {DiffOptions} options 对齐方式和差异文件路径
+   * @return {Promise<WorkbookDiff>}
+   */
+  async diff(otherPath: string, options?: DiffOptions): Promise<WorkbookDiff> {
+    return await invoke('plugin:spreadsheet|diff_workbooks', {
+      options,
+      pathA: this.path,
+      pathB: otherPath,
+    });
+  }
+
+  /**
+   * @description: 删除当前文件自动保存的内容
+   * @return {Promise<void>}
+   */
+  async discardRecoverable(): Promise<void> {
+    return await invoke('plugin:spreadsheet|discard_recoverable', {
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 在文件中查找内容
+   * @param {string} query 查找内容
+   * @param {FindOptions} options 查找选项
+   * @return {Promise<FindMatch[]>}
+   */
+  async find(query: string, options?: FindOptions): Promise<FindMatch[]> {
+    return await invoke('plugin:spreadsheet|find', {
+      options,
+      query,
+      path: this.path,
+    });
+  }
+
   /**
    * @description: 获取指定列数的数据集
-   * @param {number[]} range [ min, max ] 范围
+   * @param {number[] | string} range [ min, max ] 范围，或区域字符串、名称
    * @return {*}
    */
-  async getCollectionByColumn(range?: number[]): Promise<string[][]> {
-    let realRange = [];
+  async getCollectionByColumn(range?: number[] | string): Promise<string[][]> {
+    let realRange: number[] | string = [];
     if (range) {
       realRange = range;
     } else {
@@ -101,11 +518,11 @@ class Spreadsheet {
 
   /**
    * @description: 获取指定行数的数据集
-   * @param {number[]} range range [ min, max ] 范围
+   * @param {number[] | string} range range [ min, max ] 范围，或区域字符串、名称
    * @return {*}
    */
-  async getCollectionByRow(range?: number[]): Promise<string[][]> {
-    let realRange = [];
+  async getCollectionByRow(range?: number[] | string): Promise<string[][]> {
+    let realRange: number[] | string = [];
     if (range) {
       realRange = range;
     } else {
@@ -165,6 +582,16 @@ class Spreadsheet {
     });
   }
 
+  /**
+   * @description: 获取撤销和重做记录
+   * @return {Promise<HistoryInfo>}
+   */
+  async history(): Promise<HistoryInfo> {
+    return await invoke('plugin:spreadsheet|history', {
+      path: this.path,
+    });
+  }
+
   /**
    * @description: 在指定位置 `columnIndex` 插入数据。
    * @param {number} columnIndex 指定开始列数
@@ -236,6 +663,36 @@ class Spreadsheet {
     });
   }
 
+  /**
+   * @description: 列出当前 sheet 的图表
+   * @return {Promise<ChartInfo[]>}
+   */
+  async listCharts(): Promise<ChartInfo[]> {
+    return await invoke('plugin:spreadsheet|list_charts', {
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 列出文件中的所有名称，sheet 作用范围的名称以名称引用的 sheet 作为 `scope`，
+   * 与 Excel 中定义名称的 sheet 不一定相同，保存时写为第一个 sheet 作用范围的隐藏名称
+   * @return {Promise<DefinedNameInfo[]>}
+   */
+  async listDefinedNames(): Promise<DefinedNameInfo[]> {
+    return await invoke('plugin:spreadsheet|list_defined_names', {
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 列出上次未保存、可以恢复的文件
+   * @return {Promise<RecoverableWorkbook[]>}
+   */
+  static async listRecoverable(): Promise<RecoverableWorkbook[]> {
+    return await invoke('plugin:spreadsheet|list_recoverable');
+  }
+
   /**
    * @description: 列出所有打开的 xlsx 文件
    * @return {Promise<string[]>}
@@ -244,6 +701,20 @@ class Spreadsheet {
     return await invoke('plugin:spreadsheet|list_xlsx');
   }
 
+  /**
+   * @description: 把另一份副本相对共同版本的修改合并到当前文件
+   * @param {string} basePath 共同版本的文件路径
+   * @param {string} theirsPath 另一份副本的文件路径
+   * @return {Promise<MergeResult>} 冲突的单元格保留当前内容
+   */
+  async merge(basePath: string, theirsPath: string): Promise<MergeResult> {
+    return await invoke('plugin:spreadsheet|merge_workbooks', {
+      base: basePath,
+      ours: this.path,
+      theirs: theirsPath,
+    });
+  }
+
   /**
    * @description: 新建 sheet
    * @param {string} sheetName sheet 名称
@@ -256,13 +727,148 @@ class Spreadsheet {
     });
   }
 
+  /**
+   * @description: 监听文件内容修改事件，包括其他窗口的修改
+   * @param {function} handler 事件处理函数
+   * @return {Promise<UnlistenFn>} 取消监听的函数
+   */
+  static async onChanged(
+    handler: (event: ChangeEvent) => void
+  ): Promise<UnlistenFn> {
+    return await listen<ChangeEvent>('spreadsheet://changed', (event) =>
+      handler(event.payload)
+    );
+  }
+
+  /**
+   * @description: 监听文件关闭事件
+   * @param {function} handler 事件处理函数
+   * @return {Promise<UnlistenFn>} 取消监听的函数
+   */
+  static async onClosed(
+    handler: (event: WorkbookEvent) => void
+  ): Promise<UnlistenFn> {
+    return await listen<WorkbookEvent>('spreadsheet://closed', (event) =>
+      handler(event.payload)
+    );
+  }
+
+  /**
+   * @description: 监听文件被其他程序修改或删除事件
+   * @param {function} handler 事件处理函数
+   * @return {Promise<UnlistenFn>} 取消监听的函数
+   */
+  static async onModified(
+    handler: (event: WorkbookEvent) => void
+  ): Promise<UnlistenFn> {
+    return await listen<WorkbookEvent>('spreadsheet://modified', (event) =>
+      handler(event.payload)
+    );
+  }
+
+  /**
+   * @description: 监听文件打开或新建事件
+   * @param {function} handler 事件处理函数
+   * @return {Promise<UnlistenFn>} 取消监听的函数
+   */
+  static async onOpened(
+    handler: (event: WorkbookEvent) => void
+  ): Promise<UnlistenFn> {
+    return await listen<WorkbookEvent>('spreadsheet://opened', (event) =>
+      handler(event.payload)
+    );
+  }
+
+  /**
+   * @description: 监听文件保存事件
+   * @param {function} handler 事件处理函数
+   * @return {Promise<UnlistenFn>} 取消监听的函数
+   */
+  static async onSaved(
+    handler: (event: WorkbookEvent) => void
+  ): Promise<UnlistenFn> {
+    return await listen<WorkbookEvent>('spreadsheet://saved', (event) =>
+      handler(event.payload)
+    );
+  }
+
+  /**
+   * @description: 使用 SQL 查询所有打开的 xlsx 文件，表名为 "文件别名/sheet 名称"，sheet 名称唯一时可以直接使用。
+   * 文件别名为文件名，不同目录下有同名文件时加上上级目录，如 "2023/sales.xlsx/Sheet1"
+   * @param {string} sql 只读 SQL，ATTACH、PRAGMA 和写入语句会被拒绝，如: SELECT Region, SUM(Amount) FROM "sales.xlsx/Sheet1" GROUP BY Region
+   * @return {Promise<QueryResult>}
+   */
+  static async query(sql: string): Promise<QueryResult> {
+    return await invoke('plugin:spreadsheet|query', {
+      sql,
+    });
+  }
+
   /**
    * @description: 读取 xlsx 文件
+   * @param {string} password 加密文件的打开密码
    * @return {Promise<void>}
    */
-  async read(): Promise<void> {
+  async read(password?: string): Promise<void> {
     return await invoke('plugin:spreadsheet|read_xlsx', {
+      password,
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 打开当前文件自动保存的内容，调用 `write` 后才会写回原文件
+   * @return {Promise<void>}
+   */
+  async recover(): Promise<void> {
+    return await invoke('plugin:spreadsheet|recover', {
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 重做最近一次撤销的修改
+   * @return {Promise<HistoryStep | null>} 没有可重做的修改时为 `null`
+   */
+  async redo(): Promise<HistoryStep | null> {
+    return await invoke('plugin:spreadsheet|redo', {
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 重新读取 xlsx 文件，丢弃未保存的修改
+   * @return {Promise<void>}
+   */
+  async reload(): Promise<void> {
+    return await invoke('plugin:spreadsheet|reload_xlsx', {
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 删除区域中整行都为空的行，下方的行上移，区域外还有内容的行不会删除
+   * @param {string} range 数据区域或名称，如: A1:F500
+   * @return {Promise<RemovedRows>}
+   */
+  async removeBlankRows(range: string): Promise<RemovedRows> {
+    return await invoke('plugin:spreadsheet|remove_blank_rows', {
+      path: this.path,
+      range,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 删除指定索引的图表
+   * @param {number} index 图表索引
+   * @return {Promise<void>}
+   */
+  async removeChart(index: number): Promise<void> {
+    return await invoke('plugin:spreadsheet|remove_chart', {
+      index,
       path: this.path,
+      sheetName: this.sheetName,
     });
   }
 
@@ -292,6 +898,41 @@ class Spreadsheet {
     }
   }
 
+  /**
+   * @description: 删除名称
+   * @param {string} name 名称
+   * @param {string} scope 作用的 sheet 名称
+   * @return {Promise<void>}
+   */
+  async removeDefinedName(name: string, scope?: string): Promise<void> {
+    return await invoke('plugin:spreadsheet|remove_defined_name', {
+      name,
+      scope,
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 删除区域中的重复行，下方的行上移。删除的是整行，区域外的单元格也会一起删除
+   * @param {string} range 数据区域或名称，如: A2:F500
+   * @param {number[]} keyColumns 判断重复的列索引，默认为区域的所有列
+   * @param {string} keep 保留第一次或最后一次出现的行，默认为 first
+   * @return {Promise<RemovedRows>}
+   */
+  async removeDuplicates(
+    range: string,
+    keyColumns?: number[],
+    keep?: 'first' | 'last'
+  ): Promise<RemovedRows> {
+    return await invoke('plugin:spreadsheet|remove_duplicates', {
+      keep,
+      keyColumns,
+      path: this.path,
+      range,
+      sheetName: this.sheetName,
+    });
+  }
+
   /**
    * @description: 删除当前 sheet 指定行
    * @param {number} rowIndex 开始行数
@@ -307,6 +948,89 @@ class Spreadsheet {
     });
   }
 
+  /**
+   * @description: 使用 `data` 填充模板并保存为新文件，模板文件不会被修改
+   * @param {string} templatePath 模板文件路径，占位符如 `{{customer.name | upper}}`，`{{#each items}}` 和 `{{/each}}` 之间的行按每一项重复
+   * @param {unknown} data 填充的数据
+   * @param {string} outPath 保存的文件路径
+   * @return {Promise<TemplateResult>}
+   */
+  static async renderTemplate(
+    templatePath: string,
+    data: unknown,
+    outPath: string,
+  ): Promise<TemplateResult> {
+    return await invoke('plugin:spreadsheet|render_template', {
+      data,
+      outPath,
+      templatePath,
+    });
+  }
+
+  /**
+   * @description: 为 `range` 区域设置筛选按钮，区域首行为表头
+   * @param {string} range 筛选区域，如 `A1:F100`
+   * @return {Promise<void>}
+   */
+  async setAutoFilter(range: string): Promise<void> {
+    return await invoke('plugin:spreadsheet|set_auto_filter', {
+      range,
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 设置筛选区域中指定列的筛选条件，不传 `criteria` 时清除该列条件。
+   * 筛选条件不会写入文件，保存后重新打开时只保留筛选按钮和已隐藏的行
+   * @param {number} column 列索引，从 1 开始
+   * @param {FilterCriteria} criteria 筛选条件
+   * @return {Promise<number>} 可见的数据行数
+   */
+  async setFilterColumn(
+    column: number,
+    criteria?: FilterCriteria,
+  ): Promise<number> {
+    return await invoke('plugin:spreadsheet|set_filter_column', {
+      column,
+      criteria,
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 替换文件中所有匹配的内容，替换值时跳过有公式的单元格
+   * @param {string} query 查找内容
+   * @param {string} replacement 替换内容，正则模式下可以使用 `$1` 引用分组
+   * @param {FindOptions} options 查找选项
+   * @return {Promise<number>} 修改的单元格数量
+   */
+  async replaceAll(
+    query: string,
+    replacement: string,
+    options?: FindOptions,
+  ): Promise<number> {
+    return await invoke('plugin:spreadsheet|replace_all', {
+      options,
+      query,
+      replacement,
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 设置保留的撤销步数，为 0 时不记录撤销信息，修改失败时也不恢复 (`batch` 除外)
+   * @param {number} limit 撤销步数，默认 50
+   * @return {Promise<void>}
+   */
+  async setHistoryLimit(limit: number): Promise<void> {
+    return await invoke('plugin:spreadsheet|set_history_limit', {
+      limit,
+      path: this.path,
+    });
+  }
+
   /**
    * @description: 设置当前 sheet 指定位置的值
    * @param {number} local 位置，[column, row]
@@ -323,14 +1047,131 @@ class Spreadsheet {
   }
 
   /**
-   * @description: 保存 xlsx 文件
+   * @description: 按一个或多个关键字对区域内的行排序，样式和公式随行移动
+   * @param {string} range 排序区域或名称，如 `A1:F100`
+   * @param {SortKey[]} keys 排序关键字
+   * @param {boolean} hasHeader 区域首行是否为表头
+   * @return {Promise<number[]>} 排序后各行对应的原行索引
+   */
+  async sortRange(
+    range: string,
+    keys: SortKey[],
+    hasHeader = true,
+  ): Promise<number[]> {
+    return await invoke('plugin:spreadsheet|sort_range', {
+      hasHeader,
+      keys,
+      range,
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 按分组汇总区域数据，区域首行为表头
+   * @param {string} range 数据区域或名称，如 `A1:F200`
+   * @param {string[]} groupBy 分组的表头名称
+   * @param {Aggregate[]} aggregates 聚合列
+   * @param {SummaryOutput} output 交叉表列分组和写入的新 sheet 名称
+   * @return {Promise<SummaryResult>}
+   */
+  async summarize(
+    range: string,
+    groupBy: string[],
+    aggregates: Aggregate[],
+    output?: SummaryOutput,
+  ): Promise<SummaryResult> {
+    return await invoke('plugin:spreadsheet|summarize', {
+      aggregates,
+      groupBy,
+      output,
+      range,
+      path: this.path,
+      sheetName: this.sheetName,
+    });
+  }
+
+  /**
+   * @description: 撤销最近一次修改
+   * @return {Promise<HistoryStep | null>} 没有可撤销的修改时为 `null`
+   */
+  async undo(): Promise<HistoryStep | null> {
+    return await invoke('plugin:spreadsheet|undo', {
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 修改名称的引用位置，传入 `newName` 时同时重命名
+   * @param {string} name 名称
+   * @param {string} refersTo 新的引用位置
+   * @param {string} scope 作用的 sheet 名称，暂不支持修改 sheet 作用范围的名称
+   * @param {string} newName 新名称
+   * @return {Promise<DefinedNameInfo>}
+   */
+  async updateDefinedName(
+    name: string,
+    refersTo: string,
+    scope?: string,
+    newName?: string,
+  ): Promise<DefinedNameInfo> {
+    return await invoke('plugin:spreadsheet|update_defined_name', {
+      name,
+      newName,
+      refersTo,
+      scope,
+      path: this.path,
+    });
+  }
+
+  /**
+   * @description: 保存 xlsx 文件，文件被其他程序修改过时失败
+   * @param {boolean} overwrite 覆盖其他程序的修改
+   * @param {string} password 保存为加密文件的打开密码，为空字符串时取消加密
    * @return {Promise<void>}
    */
-  async write(): Promise<void> {
+  async write(overwrite?: boolean, password?: string): Promise<void> {
     return await invoke('plugin:spreadsheet|write_xlsx', {
+      overwrite,
+      password,
       path: this.path,
     });
   }
 }
 
 export { Spreadsheet };
+export type {
+  Aggregate,
+  CellChange,
+  CellContent,
+  ChangeEvent,
+  ChangeKind,
+  ChartInfo,
+  ChartKind,
+  ChartSeries,
+  ChartSpec,
+  DefinedNameInfo,
+  DiffOptions,
+  FilterColumn,
+  FilterCriteria,
+  FilterOperator,
+  FilterResult,
+  FindMatch,
+  FindOptions,
+  HistoryInfo,
+  HistoryStep,
+  LegendPosition,
+  MergeConflict,
+  MergeResult,
+  Operation,
+  QueryResult,
+  RecoverableWorkbook,
+  RemovedRows,
+  SheetDiff,
+  SortKey,
+  SummaryOutput,
+  SummaryResult,
+  TemplateResult,
+  WorkbookDiff,
+  WorkbookEvent,
+};